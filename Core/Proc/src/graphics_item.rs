@@ -16,6 +16,52 @@ pub fn graphics_item(attrib: TokenStream, input: TokenStream) -> TokenStream {
         _ => unreachable!()
     };
 
+    let null = match &input.data {
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .find(|variant| variant.ident == "Null")
+            .and_then(|variant| match &variant.fields {
+                Fields::Unnamed(fields) => Some(fields.unnamed[0].ty.clone()),
+                _ => None,
+            }),
+        _ => unreachable!()
+    };
+
+    let null_accessors = match (s.as_str(), null) {
+        (_, None) => quote! {},
+        ("ref", Some(null)) => quote! {
+            impl #i #ident #t #w {
+                pub fn as_null(&self) -> &#null {
+                    let #ident::Null(item) = self else { unreachable!() };
+                    item
+                }
+
+                pub fn as_null_mut(&mut self) -> &mut #null {
+                    let #ident::Null(item) = self else { unreachable!() };
+                    item
+                }
+            }
+        },
+        ("clone", Some(null)) => quote! {
+            impl #i #ident #t #w {
+                pub fn as_null(&self) -> #null {
+                    let #ident::Null(item) = self else { unreachable!() };
+                    item.clone()
+                }
+            }
+        },
+        ("copy", Some(null)) => quote! {
+            impl #i #ident #t #w {
+                pub fn as_null(&self) -> #null {
+                    let #ident::Null(item) = self else { unreachable!() };
+                    *item
+                }
+            }
+        },
+        _ => unreachable!()
+    };
+
     match s.as_str() {
         "ref" => quote! {
             #input
@@ -39,6 +85,8 @@ pub fn graphics_item(attrib: TokenStream, input: TokenStream) -> TokenStream {
                     item
                 }
             }
+
+            #null_accessors
         },
         "clone" => quote! {
             #input
@@ -56,6 +104,8 @@ pub fn graphics_item(attrib: TokenStream, input: TokenStream) -> TokenStream {
                     item
                 }
             }
+
+            #null_accessors
         },
         "copy" => quote! {
             #input
@@ -73,6 +123,8 @@ pub fn graphics_item(attrib: TokenStream, input: TokenStream) -> TokenStream {
                     item
                 }
             }
+
+            #null_accessors
         },
         _ => unreachable!()
     }.into()
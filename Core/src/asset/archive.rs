@@ -0,0 +1,344 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use bytebuffer::{ByteBuffer, Endian};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use hashbrown::HashMap;
+use parking_lot::Mutex;
+
+use crate::asset::vfs;
+
+const MAGIC: &[u8; 4] = b"MVPK";
+const VERSION: u32 = 1;
+/// Magic, version, entry count and index size.
+const HEADER_SIZE: usize = 20;
+/// An entry with an empty path, the index can't hold more entries than fit in it at this size.
+const MIN_ENTRY_SIZE: usize = 4 + 1 + 8 + 8 + 8 + 4;
+/// Deflate can't compress data by much more than 1032:1, entries claiming more are corrupted.
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+/// How the data of an archive entry is stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+}
+
+impl Compression {
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Deflate),
+            _ => None,
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchiveEntry {
+    pub compression: Compression,
+    /// Offset of the stored data from the start of the data section.
+    pub offset: u64,
+    pub stored_size: u64,
+    pub size: u64,
+    /// Crc32 of the uncompressed data.
+    pub checksum: u32,
+}
+
+enum ArchiveData {
+    File(Mutex<File>),
+    Memory(Cow<'static, [u8]>),
+}
+
+/// A packed asset archive, read with [`AssetArchive::open`] and written with [`ArchiveWriter`].
+///
+/// The archive starts with a header holding the magic `MVPK`, the format version, the number of
+/// entries and the size of the index, all little endian. The index follows, with the normalized
+/// path, compression, offset, stored size, size and checksum of every entry, then the data of all
+/// entries. Entries are compressed individually and checked against their checksum when read.
+pub struct AssetArchive {
+    entries: HashMap<String, ArchiveEntry>,
+    data: ArchiveData,
+    data_offset: u64,
+}
+
+impl AssetArchive {
+    /// Opens an archive on disk, only the index is read up front.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let mut header = [0u8; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let (count, index_size) = Self::read_header(&header)?;
+        let data_offset = Self::data_offset(index_size, length)?;
+        let mut index = vec![0u8; index_size as usize];
+        file.read_exact(&mut index)?;
+        Ok(Self {
+            entries: Self::read_index(&index, count, length - data_offset)?,
+            data: ArchiveData::File(Mutex::new(file)),
+            data_offset,
+        })
+    }
+
+    /// Reads an archive from memory, for example one embedded with `include_bytes!`.
+    pub fn from_bytes(bytes: impl Into<Cow<'static, [u8]>>) -> io::Result<Self> {
+        let bytes = bytes.into();
+        let header = bytes
+            .get(..HEADER_SIZE)
+            .ok_or_else(|| invalid("Archive is too small"))?;
+        let (count, index_size) = Self::read_header(header)?;
+        let length = bytes.len() as u64;
+        let data_offset = Self::data_offset(index_size, length)?;
+        let index = &bytes[HEADER_SIZE..data_offset as usize];
+        Ok(Self {
+            entries: Self::read_index(index, count, length - data_offset)?,
+            data: ArchiveData::Memory(bytes),
+            data_offset,
+        })
+    }
+
+    fn read_header(header: &[u8]) -> io::Result<(u32, u64)> {
+        let mut buffer = ByteBuffer::from_bytes(header);
+        buffer.set_endian(Endian::LittleEndian);
+        if buffer.read_bytes(4)? != MAGIC {
+            return Err(invalid("Not an asset archive"));
+        }
+        let version = buffer.read_u32()?;
+        if version != VERSION {
+            return Err(invalid(format!(
+                "Unsupported asset archive version {version}"
+            )));
+        }
+        Ok((buffer.read_u32()?, buffer.read_u64()?))
+    }
+
+    /// The index has to fit into the archive, the header is untrusted.
+    fn data_offset(index_size: u64, length: u64) -> io::Result<u64> {
+        (HEADER_SIZE as u64)
+            .checked_add(index_size)
+            .filter(|offset| *offset <= length)
+            .ok_or_else(|| invalid("Archive index is truncated"))
+    }
+
+    fn read_index(
+        index: &[u8],
+        count: u32,
+        data_size: u64,
+    ) -> io::Result<HashMap<String, ArchiveEntry>> {
+        let mut buffer = ByteBuffer::from_bytes(index);
+        buffer.set_endian(Endian::LittleEndian);
+        let mut entries =
+            HashMap::with_capacity((count as usize).min(index.len() / MIN_ENTRY_SIZE));
+        for _ in 0..count {
+            let path = buffer.read_string()?;
+            let compression = buffer.read_u8()?;
+            let compression = Compression::from_id(compression)
+                .ok_or_else(|| invalid(format!("Unknown compression {compression} of '{path}'")))?;
+            let entry = ArchiveEntry {
+                compression,
+                offset: buffer.read_u64()?,
+                stored_size: buffer.read_u64()?,
+                size: buffer.read_u64()?,
+                checksum: buffer.read_u32()?,
+            };
+            // Checked here so reading never allocates more than the archive could hold
+            if entry
+                .offset
+                .checked_add(entry.stored_size)
+                .is_none_or(|end| end > data_size)
+            {
+                return Err(invalid(format!("Data of '{path}' is out of bounds")));
+            }
+            let size_valid = match compression {
+                Compression::None => entry.size == entry.stored_size,
+                Compression::Deflate => {
+                    entry.size <= entry.stored_size.saturating_mul(MAX_DEFLATE_RATIO)
+                }
+            };
+            if !size_valid {
+                return Err(invalid(format!(
+                    "Size of '{path}' doesn't match its stored data"
+                )));
+            }
+            entries.insert(path, entry);
+        }
+        Ok(entries)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(&vfs::normalize(path))
+    }
+
+    pub fn get_entry(&self, path: &str) -> Option<&ArchiveEntry> {
+        self.entries.get(&vfs::normalize(path))
+    }
+
+    /// Normalized paths of all entries, in no particular order.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reads and decompresses an entry, fails if its data doesn't match the checksum.
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let Some(entry) = self.get_entry(path) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{path}' is not in the archive"),
+            ));
+        };
+
+        let stored = match &self.data {
+            ArchiveData::File(file) => {
+                let mut file = file.lock();
+                file.seek(SeekFrom::Start(self.data_offset + entry.offset))?;
+                let mut stored = vec![0u8; entry.stored_size as usize];
+                file.read_exact(&mut stored)?;
+                stored
+            }
+            ArchiveData::Memory(bytes) => {
+                let start = (self.data_offset + entry.offset) as usize;
+                bytes
+                    .get(start..start + entry.stored_size as usize)
+                    .ok_or_else(|| invalid(format!("Data of '{path}' is truncated")))?
+                    .to_vec()
+            }
+        };
+
+        let data = match entry.compression {
+            Compression::None => stored,
+            Compression::Deflate => {
+                let mut data = Vec::with_capacity(entry.size as usize);
+                DeflateDecoder::new(stored.as_slice()).read_to_end(&mut data)?;
+                data
+            }
+        };
+
+        if data.len() as u64 != entry.size || crc32fast::hash(&data) != entry.checksum {
+            return Err(invalid(format!(
+                "Checksum mismatch of '{path}', the archive is corrupted"
+            )));
+        }
+        Ok(data)
+    }
+}
+
+/// Collects files and writes them as an [`AssetArchive`].
+#[derive(Default)]
+pub struct ArchiveWriter {
+    entries: Vec<(String, Compression, Vec<u8>)>,
+}
+
+impl ArchiveWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing the one previously added at the same path. Deflated entries are
+    /// stored uncompressed if compressing doesn't make them smaller.
+    pub fn add(&mut self, path: &str, data: Vec<u8>, compression: Compression) {
+        let path = vfs::normalize(path);
+        self.entries.retain(|(existing, _, _)| *existing != path);
+        self.entries.push((path, compression, data));
+    }
+
+    /// Adds all files below `root`, with their paths relative to it.
+    pub fn add_directory(
+        &mut self,
+        root: impl AsRef<Path>,
+        compression: Compression,
+    ) -> io::Result<()> {
+        let root = root.as_ref();
+        let mut directories = vec![root.to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in std::fs::read_dir(&directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    directories.push(path);
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(root)
+                    .expect("Directory entry outside of root");
+                let relative = relative
+                    .to_str()
+                    .ok_or_else(|| invalid(format!("Invalid file name {}", relative.display())))?;
+                self.add(relative, std::fs::read(&path)?, compression);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut index = ByteBuffer::new();
+        index.set_endian(Endian::LittleEndian);
+        let mut data = Vec::new();
+        for (path, compression, content) in &self.entries {
+            let (compression, stored) = match compression {
+                Compression::None => (Compression::None, Cow::Borrowed(content.as_slice())),
+                Compression::Deflate => {
+                    let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                    encoder.write_all(content)?;
+                    let compressed = encoder.finish()?;
+                    if compressed.len() < content.len() {
+                        (Compression::Deflate, Cow::Owned(compressed))
+                    } else {
+                        (Compression::None, Cow::Borrowed(content.as_slice()))
+                    }
+                }
+            };
+            index.write_string(path);
+            index.write_u8(compression.id());
+            index.write_u64(data.len() as u64);
+            index.write_u64(stored.len() as u64);
+            index.write_u64(content.len() as u64);
+            index.write_u32(crc32fast::hash(content));
+            data.extend_from_slice(&stored);
+        }
+
+        let mut header = ByteBuffer::new();
+        header.set_endian(Endian::LittleEndian);
+        header.write_bytes(MAGIC);
+        header.write_u32(VERSION);
+        header.write_u32(self.entries.len() as u32);
+        header.write_u64(index.len() as u64);
+
+        writer.write_all(header.as_bytes())?;
+        writer.write_all(index.as_bytes())?;
+        writer.write_all(&data)?;
+        writer.flush()
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(io::BufWriter::new(File::create(path)?))
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -1,87 +1,317 @@
-use std::any::Any;
 use crate::render::texture::Texture;
+use hashbrown::HashSet;
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-pub use shaderc::ShaderKind;
-use crate::asset::manager::AssetHandle;
+use crate::asset::importer::{
+    DynAssetImporter, ImportContext, ModelImporter, ShaderImporter, TextureImporter,
+};
+use crate::asset::manager::{AssetHandle, LoadPriority};
+use crate::asset::token::{LoadStatus, LoadToken};
 use crate::render::backend::shader::Shader;
 use crate::render::model::Model;
+pub use shaderc::ShaderKind;
 
+/// The built in asset kinds, other kinds are picked by file extension from the registered
+/// [`AssetImporter`](crate::asset::importer::AssetImporter)s.
 pub enum AssetType {
     Texture,
     Model,
-    Shader(ShaderKind)
+    Shader(ShaderKind),
+}
+
+impl AssetType {
+    pub(crate) fn importer(&self) -> Arc<dyn DynAssetImporter> {
+        match self {
+            AssetType::Texture => Arc::new(TextureImporter),
+            AssetType::Model => Arc::new(ModelImporter),
+            AssetType::Shader(kind) => Arc::new(ShaderImporter::with_kind(*kind)),
+        }
+    }
 }
 
 pub enum InnerAsset {
-    Texture(Texture),
-    Model(Model),
-    Shader(Shader),
+    Loaded(Arc<dyn Any + Send + Sync>),
     Unloaded,
-    Failed(Box<dyn Any + Send + 'static>),
+    Failed(Box<dyn Any + Send + Sync + 'static>),
 }
 
 pub struct Asset {
-    pub(crate) inner: InnerAsset,
-    pub(crate) ty: AssetType,
+    pub(crate) inner: RwLock<InnerAsset>,
+    pub(crate) importer: Arc<dyn DynAssetImporter>,
     pub(crate) handle: AssetHandle,
+    /// Has to be held while loading, unloading or reloading, readers only ever wait for the final swap.
+    pub(crate) busy: Mutex<()>,
+    /// Tokens of loads that haven't finished yet.
+    pub(crate) waiting: Mutex<Vec<LoadToken>>,
+    /// Assets declared during import, each of them holds one reference for this asset.
+    pub(crate) dependencies: Mutex<Vec<AssetHandle>>,
+    pub(crate) dependents: Mutex<Vec<AssetHandle>>,
+    /// Bytes counted against the memory budget of the manager, see [`AssetImporter::size_of`](crate::asset::importer::AssetImporter::size_of).
+    size: AtomicU64,
 }
 
 impl Asset {
-    pub(crate) fn load(&mut self) {
-        if self.is_loaded() { return; }
-        let loader = self.handle.get_manager().get_loader();
-        match self.ty {
-            AssetType::Texture => {
-                match loader.import_texture(self.handle.get_path()) {
-                    Ok(texture) => self.inner = InnerAsset::Texture(texture),
-                    Err(err) => self.inner = InnerAsset::Failed(Box::new(err)),
-                }
+    pub(crate) fn new(
+        inner: InnerAsset,
+        importer: Arc<dyn DynAssetImporter>,
+        handle: AssetHandle,
+    ) -> Self {
+        let size = importer.size_of(&inner);
+        handle.get_manager().track_memory(0, size);
+        Self {
+            inner: RwLock::new(inner),
+            importer,
+            handle,
+            busy: Mutex::new(()),
+            waiting: Mutex::new(Vec::new()),
+            dependencies: Mutex::new(Vec::new()),
+            dependents: Mutex::new(Vec::new()),
+            size: AtomicU64::new(size),
+        }
+    }
+
+    /// Completes the waiting load tokens, unless the asset is unloaded but a load is still queued,
+    /// or it is waiting for dependencies. Assets depending on this one are checked as well.
+    pub(crate) fn finish_waiting(&self) {
+        self.finish_waiting_visiting(&mut HashSet::new());
+    }
+
+    /// Dependencies can form cycles, every asset is only checked once.
+    fn finish_waiting_visiting(&self, visited: &mut HashSet<AssetHandle>) {
+        if !visited.insert(self.handle.clone()) {
+            return;
+        }
+        let mut waiting = self.waiting.lock();
+        let status = LoadStatus::of(self);
+        if matches!(status, LoadStatus::Pending | LoadStatus::Loading) {
+            return;
+        }
+        let tokens = std::mem::take(&mut *waiting);
+        drop(waiting);
+        for token in tokens {
+            token.complete(status);
+        }
+
+        let dependents = self.dependents.lock().clone();
+        for dependent in dependents {
+            dependent.get().finish_waiting_visiting(visited);
+        }
+    }
+
+    pub(crate) fn load(&self, priority: LoadPriority) {
+        if self.is_imported() {
+            return;
+        }
+        let (new, size, dependencies) = self.import(priority);
+        self.set_inner(new, size);
+        self.set_dependencies(dependencies);
+    }
+
+    /// Frees the asset and releases its dependencies. Gpu resources aren't destroyed right away,
+    /// they are handed to the manager, which destroys them once no frame in flight can use them anymore.
+    pub(crate) fn unload(&self) {
+        if !self.is_imported() {
+            return;
+        }
+        let old = self.set_inner(InnerAsset::Unloaded, 0);
+        self.handle.get_manager().retire(old);
+        let dependencies = self.set_dependencies(Vec::new());
+        self.release(dependencies);
+    }
+
+    /// Imports the new version while the old one stays usable, then swaps them. The old version
+    /// is destroyed the same way as in [`Asset::unload`]. If the import fails, the old version is kept.
+    /// Returns whether a new version was swapped in.
+    pub(crate) fn reload(&self, priority: LoadPriority) -> bool {
+        if !self.is_imported() {
+            self.load(priority);
+            return self.is_imported();
+        }
+
+        match self.import(priority) {
+            (InnerAsset::Failed(_), _, _) => {
+                log::error!(
+                    "Failed to reload asset '{}', keeping the previous version",
+                    self.handle.get_path()
+                );
+                false
+            }
+            (new, size, dependencies) => {
+                let old = self.set_inner(new, size);
+                self.handle.get_manager().retire(old);
+                let old_dependencies = self.set_dependencies(dependencies);
+                self.release(old_dependencies);
+                true
             }
-            AssetType::Model => {}
-            AssetType::Shader(kind) => {}
         }
     }
 
-    pub(crate) fn unload(&mut self) {
-        if !self.is_loaded() { return; }
+    /// Replaces the asset with a version that was loaded elsewhere, returns the old one.
+    pub(crate) fn replace(&self, inner: InnerAsset) -> InnerAsset {
+        let _busy = self.busy.lock();
+        let size = self.importer.size_of(&inner);
+        self.set_inner(inner, size)
     }
 
-    pub(crate) fn reload(&mut self) {
-        // This isn't the same as calling unload then load,
-        // since we want to load it into memory, swap it with the loaded asset,
-        // then unloaded the memory from the previous asset
+    /// Swaps in a new version and updates the memory use of the manager, returns the old version.
+    fn set_inner(&self, inner: InnerAsset, size: u64) -> InnerAsset {
+        let old = std::mem::replace(&mut *self.inner.write(), inner);
+        let old_size = self.size.swap(size, Ordering::AcqRel);
+        self.handle.get_manager().track_memory(old_size, size);
+        old
     }
 
+    /// Imports the asset, returns it with its size and dependencies.
+    fn import(&self, priority: LoadPriority) -> (InnerAsset, u64, Vec<AssetHandle>) {
+        let manager = self.handle.get_manager();
+        let mut context = ImportContext::new(manager.clone(), self.handle.get_path(), priority);
+        let (path, importer) = manager
+            .find_cooked(self.handle.get_path(), &self.importer)
+            .unwrap_or_else(|| (self.handle.get_path().to_string(), self.importer.clone()));
+        let inner = importer.import(&path, &mut context);
+        let size = importer.size_of(&inner);
+        if let InnerAsset::Failed(_) = inner {
+            self.release(context.dependencies);
+            return (inner, 0, Vec::new());
+        }
+        (inner, size, context.dependencies)
+    }
+
+    /// Sets the dependencies and registers this asset as their dependent, returns the previous ones.
+    fn set_dependencies(&self, dependencies: Vec<AssetHandle>) -> Vec<AssetHandle> {
+        for dependency in &dependencies {
+            dependency.get().dependents.lock().push(self.handle.clone());
+        }
+        std::mem::replace(&mut *self.dependencies.lock(), dependencies)
+    }
+
+    fn release(&self, dependencies: Vec<AssetHandle>) {
+        for dependency in dependencies {
+            let asset = dependency.get();
+            let mut dependents = asset.dependents.lock();
+            if let Some(index) = dependents
+                .iter()
+                .position(|dependent| *dependent == self.handle)
+            {
+                dependents.swap_remove(index);
+            }
+            drop(dependents);
+            dependency.unload();
+        }
+    }
+
+    /// Whether all dependencies, and their dependencies, are loaded or failed.
+    pub fn dependencies_ready(&self) -> bool {
+        self.dependencies_ready_visiting(&mut HashSet::from([self.handle.clone()]))
+    }
+
+    /// Assets already visited are either ready or being checked further up in a cycle.
+    fn dependencies_ready_visiting(&self, visited: &mut HashSet<AssetHandle>) -> bool {
+        let dependencies = self.dependencies.lock().clone();
+        dependencies.iter().all(|dependency| {
+            if !visited.insert(dependency.clone()) {
+                return true;
+            }
+            let asset = dependency.get();
+            let unloaded = matches!(*asset.read(), InnerAsset::Unloaded);
+            !unloaded && asset.dependencies_ready_visiting(visited)
+        })
+    }
+
+    pub fn get_dependencies(&self) -> Vec<AssetHandle> {
+        self.dependencies.lock().clone()
+    }
+
+    pub fn get_dependents(&self) -> Vec<AssetHandle> {
+        self.dependents.lock().clone()
+    }
+
+    /// Bytes the loaded asset uses, 0 if it isn't loaded.
+    pub fn get_size(&self) -> u64 {
+        self.size.load(Ordering::Acquire)
+    }
+
+    fn downcast<T: Send + Sync + 'static>(inner: &InnerAsset) -> Option<Arc<T>> {
+        match inner {
+            InnerAsset::Loaded(asset) => asset.clone().downcast::<T>().ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether the asset itself is loaded, without looking at its dependencies.
+    pub(crate) fn is_imported(&self) -> bool {
+        matches!(*self.inner.read(), InnerAsset::Loaded(_))
+    }
+
+    /// Whether the asset and all of its dependencies are loaded.
     pub fn is_loaded(&self) -> bool {
-        !matches!(self.inner, InnerAsset::Failed(_) | InnerAsset::Unloaded)
+        self.is_imported() && self.dependencies_ready()
     }
 
     pub fn failed(&self) -> bool {
-        matches!(self.inner, InnerAsset::Failed(_))
+        matches!(*self.inner.read(), InnerAsset::Failed(_))
+    }
+
+    /// The current state of the asset. Unlike the status of a [`LoadToken`], this changes again when
+    /// the asset is unloaded.
+    pub fn status(&self) -> LoadStatus {
+        LoadStatus::of(self)
+    }
+
+    /// Locks the asset for reading. The asset can't be swapped out while the guard is alive, so
+    /// keep it short lived, a reload waits for all guards to be dropped.
+    pub fn read(&self) -> RwLockReadGuard<'_, InnerAsset> {
+        self.inner.read()
     }
 
-    pub fn error(&self) -> Option<&Box<dyn Any + Send + 'static>> {
-        match &self.inner {
+    pub fn error(&self) -> Option<MappedRwLockReadGuard<'_, Box<dyn Any + Send + Sync + 'static>>> {
+        RwLockReadGuard::try_map(self.inner.read(), |inner| match inner {
             InnerAsset::Failed(err) => Some(err),
             _ => None,
-        }
+        })
+        .ok()
+    }
+
+    /// The error of a failed asset, if it is of type `T`.
+    pub fn error_as<T: Any>(&self) -> Option<MappedRwLockReadGuard<'_, T>> {
+        RwLockReadGuard::try_map(self.inner.read(), |inner| match inner {
+            InnerAsset::Failed(err) => err.downcast_ref::<T>(),
+            _ => None,
+        })
+        .ok()
     }
 
-    #[allow(invalid_reference_casting)]
-    pub fn take_error(&self) -> Option<Box<dyn Any + Send + 'static>> {
-        if self.failed() {
-            let unsafe_ref = unsafe { &mut *(&self.inner as *const _ as *mut InnerAsset) };
-            let InnerAsset::Failed(err) = std::mem::replace(unsafe_ref, InnerAsset::Unloaded) else { unreachable!() };
+    pub fn take_error(&self) -> Option<Box<dyn Any + Send + Sync + 'static>> {
+        let mut inner = self.inner.write();
+        if matches!(*inner, InnerAsset::Failed(_)) {
+            let InnerAsset::Failed(err) = std::mem::replace(&mut *inner, InnerAsset::Unloaded)
+            else {
+                unreachable!()
+            };
             Some(err)
         } else {
             None
         }
     }
 
+    /// The loaded asset if it is of type `T`. The returned asset stays valid when the asset is
+    /// unloaded or reloaded, it just won't be updated anymore.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        Self::downcast(&self.inner.read())
+    }
+
     pub fn as_texture(&self) -> Option<Texture> {
-        match &self.inner {
-            InnerAsset::Texture(texture) => Some(texture.clone()),
-            _ => None,
-        }
+        self.get::<Texture>().map(|texture| (*texture).clone())
     }
-}
\ No newline at end of file
+
+    pub fn as_shader(&self) -> Option<Shader> {
+        self.get::<Shader>().map(|shader| (*shader).clone())
+    }
+
+    pub fn as_model(&self) -> Option<Arc<Model>> {
+        self.get::<Model>()
+    }
+}
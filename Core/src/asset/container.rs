@@ -0,0 +1,398 @@
+use std::io;
+
+use bytebuffer::{ByteBuffer, Endian};
+
+use crate::render::backend::image::{ImageFormat, ImageLimits};
+use crate::render::backend::Extent2D;
+
+const KTX2_IDENTIFIER: &[u8; 12] = b"\xABKTX 20\xBB\r\n\x1A\n";
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const DDS_DIMENSION_TEXTURE3D: u32 = 4;
+
+/// A texture read from a KTX2 or DDS file, in the format it is uploaded in.
+pub struct ContainerTexture {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    /// Array layers, six per cubemap.
+    pub layer_count: u32,
+    pub cubemap: bool,
+    /// Data of every mip level in the file, starting with the full size one. Each level holds all
+    /// of its layers one after another.
+    pub levels: Vec<Vec<u8>>,
+    /// The file only holds the first mip level and asks for the others to be generated.
+    pub generate_mipmaps: bool,
+}
+
+impl ContainerTexture {
+    fn validate(&self) -> io::Result<()> {
+        let extent = Extent2D {
+            width: self.width,
+            height: self.height,
+        };
+        if self.width == 0 || self.height == 0 || self.layer_count == 0 {
+            return Err(invalid("Texture is empty"));
+        }
+        if self.levels.len() as u32 > extent.get_max_mip_levels() {
+            return Err(invalid(format!(
+                "Texture has {} mip levels, more than its size allows",
+                self.levels.len()
+            )));
+        }
+        for (level, data) in self.levels.iter().enumerate() {
+            let expected = self
+                .format
+                .checked_data_size(extent.get_mip_extent(level as u32))
+                .and_then(|size| size.checked_mul(self.layer_count as u64))
+                .ok_or_else(|| invalid("Texture is too large"))?;
+            if data.len() as u64 != expected {
+                return Err(invalid(format!(
+                    "Mip level {level} is {} bytes, expected {expected}",
+                    data.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a KTX2 or DDS file, which one is told apart by its header. Textures larger than `limits`
+/// are rejected before their size is computed, see [`Device::get_image_limits`].
+///
+/// [`Device::get_image_limits`]: crate::render::backend::device::Device::get_image_limits
+pub fn read_texture(data: &[u8], limits: ImageLimits) -> io::Result<ContainerTexture> {
+    if data.starts_with(KTX2_IDENTIFIER) {
+        read_ktx2(data, limits)
+    } else if data.starts_with(DDS_MAGIC) {
+        read_dds(data, limits)
+    } else {
+        Err(invalid("Not a KTX2 or DDS file"))
+    }
+}
+
+fn check_limits(
+    width: u32,
+    height: u32,
+    layer_count: u32,
+    cubemap: bool,
+    limits: ImageLimits,
+) -> io::Result<()> {
+    let max_dimension = if cubemap {
+        limits.max_cube_dimension
+    } else {
+        limits.max_dimension
+    };
+    if width > max_dimension || height > max_dimension {
+        return Err(invalid(format!(
+            "Texture is {width}x{height}, the device supports at most {max_dimension}x{max_dimension}"
+        )));
+    }
+    if layer_count > limits.max_array_layers {
+        return Err(invalid(format!(
+            "Texture has {layer_count} layers, the device supports at most {}",
+            limits.max_array_layers
+        )));
+    }
+    Ok(())
+}
+
+/// Reads a KTX2 file. Supercompressed files, like Basis Universal ones, and 3d textures aren't
+/// supported.
+pub fn read_ktx2(data: &[u8], limits: ImageLimits) -> io::Result<ContainerTexture> {
+    let mut buffer = ByteBuffer::from_bytes(data);
+    buffer.set_endian(Endian::LittleEndian);
+    if buffer.read_bytes(KTX2_IDENTIFIER.len())? != KTX2_IDENTIFIER {
+        return Err(invalid("Not a KTX2 file"));
+    }
+    let vk_format = ash::vk::Format::from_raw(buffer.read_i32()?);
+    let _type_size = buffer.read_u32()?;
+    let width = buffer.read_u32()?;
+    let height = buffer.read_u32()?.max(1);
+    let depth = buffer.read_u32()?;
+    let layer_count = buffer.read_u32()?.max(1);
+    let face_count = buffer.read_u32()?;
+    let level_count = buffer.read_u32()?;
+    let supercompression = buffer.read_u32()?;
+    // Data format descriptor, key/value data and supercompression global data aren't needed
+    buffer.read_bytes(4 * 4 + 8 * 2)?;
+
+    if supercompression != 0 {
+        return Err(invalid(format!(
+            "Supercompression scheme {supercompression} is not supported"
+        )));
+    }
+    if depth > 1 {
+        return Err(invalid("3d textures are not supported"));
+    }
+    if face_count != 1 && face_count != 6 {
+        return Err(invalid(format!("Invalid face count {face_count}")));
+    }
+    let format = ktx2_format(vk_format)
+        .ok_or_else(|| invalid(format!("Format {vk_format:?} is not supported")))?;
+    let layer_count = layer_count
+        .checked_mul(face_count)
+        .ok_or_else(|| invalid(format!("Invalid layer count {layer_count}")))?;
+    check_limits(width, height, layer_count, face_count == 6, limits)?;
+
+    let levels = (0..level_count.max(1))
+        .map(|_| {
+            let offset = buffer.read_u64()? as usize;
+            let length = buffer.read_u64()? as usize;
+            let _uncompressed_length = buffer.read_u64()?;
+            data.get(offset..offset.saturating_add(length))
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| invalid("Mip level is out of bounds"))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let texture = ContainerTexture {
+        width,
+        height,
+        format,
+        layer_count,
+        cubemap: face_count == 6,
+        levels,
+        generate_mipmaps: level_count == 0,
+    };
+    texture.validate()?;
+    Ok(texture)
+}
+
+/// Reads a DDS file, with or without the DX10 header extension. Files without it don't store a
+/// color space and are read as linear. Volume textures and cubemaps missing faces aren't supported.
+pub fn read_dds(data: &[u8], limits: ImageLimits) -> io::Result<ContainerTexture> {
+    let mut buffer = ByteBuffer::from_bytes(data);
+    buffer.set_endian(Endian::LittleEndian);
+    if buffer.read_bytes(DDS_MAGIC.len())? != DDS_MAGIC {
+        return Err(invalid("Not a DDS file"));
+    }
+    let header_size = buffer.read_u32()?;
+    if header_size != 124 {
+        return Err(invalid(format!("Invalid header size {header_size}")));
+    }
+    let flags = buffer.read_u32()?;
+    let height = buffer.read_u32()?;
+    let width = buffer.read_u32()?;
+    let _pitch = buffer.read_u32()?;
+    let _depth = buffer.read_u32()?;
+    let mip_count = buffer.read_u32()?;
+    buffer.read_bytes(11 * 4)?;
+    let _pixel_format_size = buffer.read_u32()?;
+    let pixel_flags = buffer.read_u32()?;
+    let four_cc = buffer.read_bytes(4)?;
+    let bit_count = buffer.read_u32()?;
+    let masks = [
+        buffer.read_u32()?,
+        buffer.read_u32()?,
+        buffer.read_u32()?,
+        buffer.read_u32()?,
+    ];
+    let _caps = buffer.read_u32()?;
+    let caps2 = buffer.read_u32()?;
+    buffer.read_bytes(3 * 4)?;
+
+    if caps2 & DDSCAPS2_VOLUME != 0 {
+        return Err(invalid("Volume textures are not supported"));
+    }
+
+    let (format, array_size, cubemap) = if pixel_flags & DDPF_FOURCC != 0 && four_cc == b"DX10" {
+        let dxgi_format = buffer.read_u32()?;
+        let dimension = buffer.read_u32()?;
+        let misc_flags = buffer.read_u32()?;
+        let array_size = buffer.read_u32()?.max(1);
+        let _misc_flags2 = buffer.read_u32()?;
+        if dimension == DDS_DIMENSION_TEXTURE3D {
+            return Err(invalid("Volume textures are not supported"));
+        }
+        let format = dxgi_format_to_format(dxgi_format)
+            .ok_or_else(|| invalid(format!("DXGI format {dxgi_format} is not supported")))?;
+        (
+            format,
+            array_size,
+            misc_flags & DDS_RESOURCE_MISC_TEXTURECUBE != 0,
+        )
+    } else {
+        let format = if pixel_flags & DDPF_FOURCC != 0 {
+            four_cc_to_format(&four_cc).ok_or_else(|| {
+                invalid(format!(
+                    "FourCC {} is not supported",
+                    String::from_utf8_lossy(&four_cc)
+                ))
+            })?
+        } else if pixel_flags & DDPF_RGB != 0
+            && bit_count == 32
+            && masks == [0xFF, 0xFF00, 0xFF0000, 0xFF000000]
+        {
+            ImageFormat::R8G8B8A8
+        } else {
+            return Err(invalid(
+                "Only block compressed and RGBA8 DDS files are supported",
+            ));
+        };
+        let cubemap = caps2 & DDSCAPS2_CUBEMAP != 0;
+        if cubemap && caps2 & DDSCAPS2_CUBEMAP_ALL_FACES != DDSCAPS2_CUBEMAP_ALL_FACES {
+            return Err(invalid("Cubemaps missing faces are not supported"));
+        }
+        (format, 1, cubemap)
+    };
+
+    let layer_count = if cubemap {
+        array_size.checked_mul(6)
+    } else {
+        Some(array_size)
+    };
+    let layer_count =
+        layer_count.ok_or_else(|| invalid(format!("Invalid array size {array_size}")))?;
+    check_limits(width, height, layer_count, cubemap, limits)?;
+    let level_count = if flags & DDSD_MIPMAPCOUNT != 0 {
+        mip_count.max(1)
+    } else {
+        1
+    };
+    let extent = Extent2D { width, height };
+    if level_count > extent.get_max_mip_levels() {
+        return Err(invalid(format!(
+            "Texture has {level_count} mip levels, more than its size allows"
+        )));
+    }
+
+    // DDS stores the whole mip chain of one layer after another, uploads are ordered by mip level
+    let mut levels = vec![Vec::new(); level_count as usize];
+    for _ in 0..layer_count {
+        for (level, data) in levels.iter_mut().enumerate() {
+            let size = format
+                .checked_data_size(extent.get_mip_extent(level as u32))
+                .ok_or_else(|| invalid("Texture is too large"))?;
+            let size = usize::try_from(size).map_err(|_| invalid("Texture is too large"))?;
+            data.extend_from_slice(&buffer.read_bytes(size)?);
+        }
+    }
+
+    let texture = ContainerTexture {
+        width,
+        height,
+        format,
+        layer_count,
+        cubemap,
+        levels,
+        generate_mipmaps: false,
+    };
+    texture.validate()?;
+    Ok(texture)
+}
+
+// 8 bit formats with less than four channels have no sRGB variant and are read as linear, BC1
+// without alpha is read as BC1 with alpha
+fn ktx2_format(format: ash::vk::Format) -> Option<ImageFormat> {
+    use ash::vk::Format;
+    Some(match format {
+        Format::R8_UNORM | Format::R8_SRGB => ImageFormat::R8,
+        Format::R8G8_UNORM | Format::R8G8_SRGB => ImageFormat::R8G8,
+        Format::R8G8B8_UNORM | Format::R8G8B8_SRGB => ImageFormat::R8G8B8,
+        Format::R8G8B8A8_UNORM => ImageFormat::R8G8B8A8,
+        Format::R8G8B8A8_SRGB => ImageFormat::R8G8B8A8Srgb,
+        Format::B8G8R8A8_UNORM => ImageFormat::B8G8R8A8,
+        Format::B8G8R8A8_SRGB => ImageFormat::B8G8R8A8Srgb,
+        Format::R16_SFLOAT => ImageFormat::R16,
+        Format::R16G16_SFLOAT => ImageFormat::R16G16,
+        Format::R16G16B16_SFLOAT => ImageFormat::R16G16B16,
+        Format::R16G16B16A16_SFLOAT => ImageFormat::R16G16B16A16,
+        Format::R32_SFLOAT => ImageFormat::R32,
+        Format::R32G32_SFLOAT => ImageFormat::R32G32,
+        Format::R32G32B32_SFLOAT => ImageFormat::R32G32B32,
+        Format::R32G32B32A32_SFLOAT => ImageFormat::R32G32B32A32,
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => ImageFormat::Bc1,
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => ImageFormat::Bc1Srgb,
+        Format::BC2_UNORM_BLOCK => ImageFormat::Bc2,
+        Format::BC2_SRGB_BLOCK => ImageFormat::Bc2Srgb,
+        Format::BC3_UNORM_BLOCK => ImageFormat::Bc3,
+        Format::BC3_SRGB_BLOCK => ImageFormat::Bc3Srgb,
+        Format::BC4_UNORM_BLOCK => ImageFormat::Bc4,
+        Format::BC5_UNORM_BLOCK => ImageFormat::Bc5,
+        Format::BC6H_UFLOAT_BLOCK => ImageFormat::Bc6H,
+        Format::BC7_UNORM_BLOCK => ImageFormat::Bc7,
+        Format::BC7_SRGB_BLOCK => ImageFormat::Bc7Srgb,
+        Format::ASTC_4X4_UNORM_BLOCK => ImageFormat::Astc4x4,
+        Format::ASTC_4X4_SRGB_BLOCK => ImageFormat::Astc4x4Srgb,
+        Format::ASTC_5X4_UNORM_BLOCK => ImageFormat::Astc5x4,
+        Format::ASTC_5X4_SRGB_BLOCK => ImageFormat::Astc5x4Srgb,
+        Format::ASTC_5X5_UNORM_BLOCK => ImageFormat::Astc5x5,
+        Format::ASTC_5X5_SRGB_BLOCK => ImageFormat::Astc5x5Srgb,
+        Format::ASTC_6X5_UNORM_BLOCK => ImageFormat::Astc6x5,
+        Format::ASTC_6X5_SRGB_BLOCK => ImageFormat::Astc6x5Srgb,
+        Format::ASTC_6X6_UNORM_BLOCK => ImageFormat::Astc6x6,
+        Format::ASTC_6X6_SRGB_BLOCK => ImageFormat::Astc6x6Srgb,
+        Format::ASTC_8X5_UNORM_BLOCK => ImageFormat::Astc8x5,
+        Format::ASTC_8X5_SRGB_BLOCK => ImageFormat::Astc8x5Srgb,
+        Format::ASTC_8X6_UNORM_BLOCK => ImageFormat::Astc8x6,
+        Format::ASTC_8X6_SRGB_BLOCK => ImageFormat::Astc8x6Srgb,
+        Format::ASTC_8X8_UNORM_BLOCK => ImageFormat::Astc8x8,
+        Format::ASTC_8X8_SRGB_BLOCK => ImageFormat::Astc8x8Srgb,
+        Format::ASTC_10X5_UNORM_BLOCK => ImageFormat::Astc10x5,
+        Format::ASTC_10X5_SRGB_BLOCK => ImageFormat::Astc10x5Srgb,
+        Format::ASTC_10X6_UNORM_BLOCK => ImageFormat::Astc10x6,
+        Format::ASTC_10X6_SRGB_BLOCK => ImageFormat::Astc10x6Srgb,
+        Format::ASTC_10X8_UNORM_BLOCK => ImageFormat::Astc10x8,
+        Format::ASTC_10X8_SRGB_BLOCK => ImageFormat::Astc10x8Srgb,
+        Format::ASTC_10X10_UNORM_BLOCK => ImageFormat::Astc10x10,
+        Format::ASTC_10X10_SRGB_BLOCK => ImageFormat::Astc10x10Srgb,
+        Format::ASTC_12X10_UNORM_BLOCK => ImageFormat::Astc12x10,
+        Format::ASTC_12X10_SRGB_BLOCK => ImageFormat::Astc12x10Srgb,
+        Format::ASTC_12X12_UNORM_BLOCK => ImageFormat::Astc12x12,
+        Format::ASTC_12X12_SRGB_BLOCK => ImageFormat::Astc12x12Srgb,
+        _ => return None,
+    })
+}
+
+fn dxgi_format_to_format(format: u32) -> Option<ImageFormat> {
+    Some(match format {
+        2 => ImageFormat::R32G32B32A32,
+        6 => ImageFormat::R32G32B32,
+        10 => ImageFormat::R16G16B16A16,
+        16 => ImageFormat::R32G32,
+        28 => ImageFormat::R8G8B8A8,
+        29 => ImageFormat::R8G8B8A8Srgb,
+        34 => ImageFormat::R16G16,
+        41 => ImageFormat::R32,
+        49 => ImageFormat::R8G8,
+        54 => ImageFormat::R16,
+        61 => ImageFormat::R8,
+        71 => ImageFormat::Bc1,
+        72 => ImageFormat::Bc1Srgb,
+        74 => ImageFormat::Bc2,
+        75 => ImageFormat::Bc2Srgb,
+        77 => ImageFormat::Bc3,
+        78 => ImageFormat::Bc3Srgb,
+        80 => ImageFormat::Bc4,
+        83 => ImageFormat::Bc5,
+        87 => ImageFormat::B8G8R8A8,
+        91 => ImageFormat::B8G8R8A8Srgb,
+        95 => ImageFormat::Bc6H,
+        98 => ImageFormat::Bc7,
+        99 => ImageFormat::Bc7Srgb,
+        _ => return None,
+    })
+}
+
+fn four_cc_to_format(four_cc: &[u8]) -> Option<ImageFormat> {
+    Some(match four_cc {
+        b"DXT1" => ImageFormat::Bc1,
+        b"DXT2" | b"DXT3" => ImageFormat::Bc2,
+        b"DXT4" | b"DXT5" => ImageFormat::Bc3,
+        b"ATI1" | b"BC4U" => ImageFormat::Bc4,
+        b"ATI2" | b"BC5U" => ImageFormat::Bc5,
+        _ => return None,
+    })
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,855 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bytebuffer::{ByteBuffer, Endian};
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage};
+use shaderc::ShaderKind;
+
+use crate::asset::importer::{
+    AssetImporter, AssetLoader, ImageData, MeshData, ModelData, ModelImporter, PrimitiveData,
+    ShaderImporter, TextureImporter,
+};
+use crate::asset::vfs::{self, AssetSource, VirtualFileSystem};
+use crate::math::quat::Quat;
+use crate::math::vec::{Vec2, Vec3, Vec4};
+use crate::render::atlas::{AtlasBuilder, AtlasOptions, AtlasRect, ATLAS_SOURCE_EXTENSION};
+use crate::render::backend::image::ColorSpace;
+use crate::render::backend::pipeline::Topology;
+use crate::render::backend::shader::{Shader, ShaderCompileError, ShaderStage};
+use crate::render::backend::Extent2D;
+use crate::render::model::{Material, ModelVertex, Node};
+
+pub const TEXTURE_EXTENSION: &str = "mvtex";
+pub const MODEL_EXTENSION: &str = "mvmodel";
+pub const SHADER_EXTENSION: &str = "mvshader";
+pub const ATLAS_EXTENSION: &str = "mvatlas";
+/// Name of the manifest the [`Cooker`] writes into the root of the output directory.
+pub const MANIFEST_NAME: &str = "cooked.manifest";
+
+const TEXTURE_MAGIC: &[u8; 4] = b"MVTX";
+const MODEL_MAGIC: &[u8; 4] = b"MVMD";
+const SHADER_MAGIC: &[u8; 4] = b"MVSH";
+const ATLAS_MAGIC: &[u8; 4] = b"MVAT";
+const VERSION: u32 = 1;
+const MANIFEST_HEADER: &str = "mvcook 1";
+const NONE: u32 = u32::MAX;
+
+// Smallest sizes of the items in cooked files, counts are checked against them before reading
+const LEVEL_SIZE: usize = 8;
+const WORD_SIZE: usize = 4;
+const STRING_SIZE: usize = 4;
+const SPRITE_SIZE: usize = STRING_SIZE + 5 * 4;
+const MATERIAL_SIZE: usize = (6 * 4 + 11 + 11) * 4;
+const MESH_SIZE: usize = 1 + 4;
+const PRIMITIVE_SIZE: usize = 1 + 4 + 4 + 4;
+const VERTEX_SIZE: usize = 16 * 4;
+const INDEX_SIZE: usize = 4;
+const NODE_SIZE: usize = 1 + 4 + 4 + 10 * 4;
+
+/// A texture expanded to four channels with its full mip chain, as stored in cooked texture files.
+pub struct CookedTexture {
+    pub width: u32,
+    pub height: u32,
+    pub color: ColorType,
+    /// Pixel data of every mip level, starting with the full size image.
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// Decodes an image file and cooks it, see [`cook_image`].
+pub fn cook_texture(path: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let image = AssetLoader::decode_image(path, data)
+        .map_err(|e| format!("Failed to cook texture {path}: {e}"))?;
+    Ok(cook_image(image))
+}
+
+/// Expands the image to the format the asset loader uploads and generates its mip chain.
+pub fn cook_image(image: DynamicImage) -> Vec<u8> {
+    let image = AssetLoader::expand_to_rgba(image);
+    let color = match image.color() {
+        ColorType::Rgba16 => 1,
+        ColorType::Rgba32F => 2,
+        _ => 0,
+    };
+    let image = if color == 0 {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        image
+    };
+
+    let mut levels = vec![image];
+    loop {
+        let last = levels.last().unwrap();
+        if last.width() == 1 && last.height() == 1 {
+            break;
+        }
+        let next = last.resize_exact(
+            (last.width() / 2).max(1),
+            (last.height() / 2).max(1),
+            FilterType::Triangle,
+        );
+        levels.push(next);
+    }
+
+    let mut buffer = buffer();
+    buffer.write_bytes(TEXTURE_MAGIC);
+    buffer.write_u32(VERSION);
+    buffer.write_u8(color);
+    buffer.write_u32(levels[0].width());
+    buffer.write_u32(levels[0].height());
+    buffer.write_u32(levels.len() as u32);
+    for level in levels {
+        let bytes = level.into_bytes();
+        buffer.write_u64(bytes.len() as u64);
+        buffer.write_bytes(&bytes);
+    }
+    buffer.into_vec()
+}
+
+pub fn read_texture(data: &[u8]) -> io::Result<CookedTexture> {
+    let mut buffer = read_buffer(data, TEXTURE_MAGIC)?;
+    let color = match buffer.read_u8()? {
+        0 => ColorType::Rgba8,
+        1 => ColorType::Rgba16,
+        2 => ColorType::Rgba32F,
+        color => return Err(invalid(format!("Unknown texture color type {color}"))),
+    };
+    let width = buffer.read_u32()?;
+    let height = buffer.read_u32()?;
+    let size = Extent2D { width, height };
+    let level_count = read_count(&mut buffer, LEVEL_SIZE)?;
+    // The loader uploads either the whole mip chain or only the first level
+    let full_chain = size.get_max_mip_levels();
+    if width == 0 || height == 0 || (level_count != 1 && level_count != full_chain) {
+        return Err(invalid(format!(
+            "Cooked texture of {width}x{height} has {level_count} mip levels"
+        )));
+    }
+    let levels = (0..level_count)
+        .map(|level| {
+            let len = buffer.read_u64()?;
+            let extent = size.get_mip_extent(level);
+            let expected = (extent.width as u64 * extent.height as u64)
+                .saturating_mul(color.bytes_per_pixel() as u64);
+            if len != expected {
+                return Err(invalid(format!(
+                    "Mip level {level} has {len} bytes instead of {expected}"
+                )));
+            }
+            if len > remaining(&buffer) as u64 {
+                return Err(invalid(format!("Mip level {level} is truncated")));
+            }
+            buffer.read_bytes(len as usize)
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(CookedTexture {
+        width,
+        height,
+        color,
+        levels,
+    })
+}
+
+/// Compiles a glsl shader to SPIR-V, includes are read with `read`.
+pub fn cook_shader(
+    path: &str,
+    source: &str,
+    kind: ShaderKind,
+    read: impl Fn(&str) -> io::Result<String>,
+) -> Result<Vec<u8>, ShaderCompileError> {
+    let code = Shader::compile_spirv(source, kind, path, &[], read)?;
+    let stage: ShaderStage = kind.into();
+    let mut buffer = buffer();
+    buffer.write_bytes(SHADER_MAGIC);
+    buffer.write_u32(VERSION);
+    buffer.write_u32(stage.bits());
+    buffer.write_u32(code.len() as u32);
+    for word in code {
+        buffer.write_u32(word);
+    }
+    Ok(buffer.into_vec())
+}
+
+pub(crate) fn read_shader(data: &[u8]) -> io::Result<(ShaderStage, Vec<u32>)> {
+    let mut buffer = read_buffer(data, SHADER_MAGIC)?;
+    let stage = buffer.read_u32()?;
+    let stage = ShaderStage::from_bits(stage)
+        .ok_or_else(|| invalid(format!("Unknown shader stage {stage}")))?;
+    let code = (0..read_count(&mut buffer, WORD_SIZE)?)
+        .map(|_| buffer.read_u32())
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok((stage, code))
+}
+
+/// The layout of a texture atlas packed by the [`Cooker`], its pages are cooked textures next to it.
+pub struct CookedAtlas {
+    pub options: AtlasOptions,
+    /// Paths of the pages relative to the cooked atlas.
+    pub pages: Vec<String>,
+    /// Name, page and rect of every sprite.
+    pub sprites: Vec<(String, usize, AtlasRect)>,
+}
+
+/// Writes the layout of a packed atlas, `pages` are the paths of its cooked pages relative to it.
+pub fn write_atlas(builder: &AtlasBuilder, pages: &[String]) -> Vec<u8> {
+    let mut buffer = buffer();
+    buffer.write_bytes(ATLAS_MAGIC);
+    buffer.write_u32(VERSION);
+    let options = builder.get_options();
+    buffer.write_u32(options.padding);
+    buffer.write_u32(options.extrude);
+    buffer.write_u32(options.page_size);
+    buffer.write_u32(options.max_page_size);
+    buffer.write_u8(matches!(options.color_space, ColorSpace::Linear) as u8);
+
+    buffer.write_u32(pages.len() as u32);
+    for page in pages {
+        buffer.write_string(page);
+    }
+    let sprites = builder.get_sprites();
+    buffer.write_u32(sprites.len() as u32);
+    for (name, page, rect) in sprites {
+        buffer.write_string(name);
+        buffer.write_u32(page as u32);
+        buffer.write_u32(rect.x);
+        buffer.write_u32(rect.y);
+        buffer.write_u32(rect.width);
+        buffer.write_u32(rect.height);
+    }
+    buffer.into_vec()
+}
+
+pub fn read_atlas(data: &[u8]) -> io::Result<CookedAtlas> {
+    let mut buffer = read_buffer(data, ATLAS_MAGIC)?;
+    let options = AtlasOptions {
+        padding: buffer.read_u32()?,
+        extrude: buffer.read_u32()?,
+        page_size: buffer.read_u32()?,
+        max_page_size: buffer.read_u32()?,
+        color_space: match buffer.read_u8()? {
+            0 => ColorSpace::Srgb,
+            1 => ColorSpace::Linear,
+            color_space => return Err(invalid(format!("Unknown atlas color space {color_space}"))),
+        },
+    };
+    let pages = (0..read_count(&mut buffer, STRING_SIZE)?)
+        .map(|_| buffer.read_string())
+        .collect::<io::Result<Vec<_>>>()?;
+    let sprites = (0..read_count(&mut buffer, SPRITE_SIZE)?)
+        .map(|_| {
+            let name = buffer.read_string()?;
+            let page = buffer.read_u32()? as usize;
+            if page >= pages.len() {
+                return Err(invalid(format!(
+                    "Sprite {name} is on page {page}, the atlas has {} pages",
+                    pages.len()
+                )));
+            }
+            let rect = AtlasRect {
+                x: buffer.read_u32()?,
+                y: buffer.read_u32()?,
+                width: buffer.read_u32()?,
+                height: buffer.read_u32()?,
+            };
+            Ok((name, page, rect))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(CookedAtlas {
+        options,
+        pages,
+        sprites,
+    })
+}
+
+/// Writes a model read from a glTF file in the cooked model format. All images have to be files,
+/// embedded images are cooked into their own files first.
+pub(crate) fn write_model(model: &ModelData) -> Vec<u8> {
+    let mut buffer = buffer();
+    buffer.write_bytes(MODEL_MAGIC);
+    buffer.write_u32(VERSION);
+
+    buffer.write_u32(model.images.len() as u32);
+    for image in &model.images {
+        let ImageData::File(path) = image else {
+            log::error!("Embedded images have to be cooked before the model");
+            panic!();
+        };
+        buffer.write_string(path);
+    }
+
+    buffer.write_u32(model.materials.len() as u32);
+    for material in &model.materials {
+        write_material(&mut buffer, material);
+    }
+
+    buffer.write_u32(model.meshes.len() as u32);
+    for mesh in &model.meshes {
+        write_name(&mut buffer, &mesh.name);
+        buffer.write_u32(mesh.primitives.len() as u32);
+        for primitive in &mesh.primitives {
+            buffer.write_u8(topology_id(primitive.topology));
+            write_index(&mut buffer, primitive.material);
+            buffer.write_u32(primitive.vertices.len() as u32);
+            for vertex in &primitive.vertices {
+                write_vec3(&mut buffer, vertex.position);
+                write_vec3(&mut buffer, vertex.normal);
+                buffer.write_f32(vertex.tex_coord.x);
+                buffer.write_f32(vertex.tex_coord.y);
+                write_vec4(&mut buffer, vertex.tangent);
+                write_vec4(&mut buffer, vertex.color);
+            }
+            match &primitive.indices {
+                Some(indices) => {
+                    buffer.write_u32(indices.len() as u32);
+                    for index in indices {
+                        buffer.write_u32(*index);
+                    }
+                }
+                None => buffer.write_u32(NONE),
+            }
+        }
+    }
+
+    buffer.write_u32(model.nodes.len() as u32);
+    for node in &model.nodes {
+        write_name(&mut buffer, &node.name);
+        write_index(&mut buffer, node.mesh);
+        buffer.write_u32(node.children.len() as u32);
+        for child in &node.children {
+            buffer.write_u32(*child as u32);
+        }
+        write_vec3(&mut buffer, node.translation);
+        write_vec4(
+            &mut buffer,
+            Vec4::new(
+                node.rotation.x,
+                node.rotation.y,
+                node.rotation.z,
+                node.rotation.w,
+            ),
+        );
+        write_vec3(&mut buffer, node.scale);
+    }
+
+    buffer.write_u32(model.root_nodes.len() as u32);
+    for root in &model.root_nodes {
+        buffer.write_u32(*root as u32);
+    }
+    buffer.into_vec()
+}
+
+pub(crate) fn read_model(data: &[u8]) -> io::Result<ModelData> {
+    let mut buffer = read_buffer(data, MODEL_MAGIC)?;
+
+    let images = (0..read_count(&mut buffer, STRING_SIZE)?)
+        .map(|_| buffer.read_string().map(ImageData::File))
+        .collect::<io::Result<Vec<_>>>()?;
+    let materials = (0..read_count(&mut buffer, MATERIAL_SIZE)?)
+        .map(|_| read_material(&mut buffer, images.len()))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut meshes = Vec::new();
+    for _ in 0..read_count(&mut buffer, MESH_SIZE)? {
+        let name = read_name(&mut buffer)?;
+        let mut primitives = Vec::new();
+        for _ in 0..read_count(&mut buffer, PRIMITIVE_SIZE)? {
+            let topology = topology_from_id(buffer.read_u8()?)?;
+            let material = read_index(&mut buffer, materials.len(), "material")?;
+            let vertices = (0..read_count(&mut buffer, VERTEX_SIZE)?)
+                .map(|_| {
+                    Ok(ModelVertex {
+                        position: read_vec3(&mut buffer)?,
+                        normal: read_vec3(&mut buffer)?,
+                        tex_coord: Vec2::new(buffer.read_f32()?, buffer.read_f32()?),
+                        tangent: read_vec4(&mut buffer)?,
+                        color: read_vec4(&mut buffer)?,
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            let indices = match buffer.read_u32()? {
+                NONE => None,
+                count => {
+                    check_count(&buffer, count, INDEX_SIZE)?;
+                    Some(
+                        (0..count)
+                            .map(|_| {
+                                let index = buffer.read_u32()?;
+                                check_index(index as usize, vertices.len(), "vertex")?;
+                                Ok(index)
+                            })
+                            .collect::<io::Result<Vec<_>>>()?,
+                    )
+                }
+            };
+            primitives.push(PrimitiveData {
+                topology,
+                vertices,
+                indices,
+                material,
+            });
+        }
+        meshes.push(MeshData { name, primitives });
+    }
+
+    let mut nodes = Vec::new();
+    for _ in 0..read_count(&mut buffer, NODE_SIZE)? {
+        let name = read_name(&mut buffer)?;
+        let mesh = read_index(&mut buffer, meshes.len(), "mesh")?;
+        let children = (0..read_count(&mut buffer, INDEX_SIZE)?)
+            .map(|_| buffer.read_u32().map(|child| child as usize))
+            .collect::<io::Result<Vec<_>>>()?;
+        let translation = read_vec3(&mut buffer)?;
+        let rotation = read_vec4(&mut buffer)?;
+        nodes.push(Node {
+            name,
+            parent: None,
+            children,
+            mesh,
+            translation,
+            rotation: Quat::new(rotation.x, rotation.y, rotation.z, rotation.w),
+            scale: read_vec3(&mut buffer)?,
+        });
+    }
+    for index in 0..nodes.len() {
+        for child in nodes[index].children.clone() {
+            nodes
+                .get_mut(child)
+                .ok_or_else(|| invalid(format!("Node {index} has an invalid child {child}")))?
+                .parent = Some(index);
+        }
+    }
+
+    let root_nodes = (0..read_count(&mut buffer, INDEX_SIZE)?)
+        .map(|_| {
+            buffer
+                .read_u32()
+                .and_then(|root| check_index(root as usize, nodes.len(), "root node"))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(ModelData {
+        meshes,
+        materials,
+        nodes,
+        root_nodes,
+        images,
+    })
+}
+
+fn write_material(buffer: &mut ByteBuffer, material: &Material<usize>) {
+    write_vec4(buffer, material.color);
+    write_index(buffer, material.color_tex);
+    buffer.write_f32(material.metallic);
+    write_index(buffer, material.metallic_tex);
+    buffer.write_f32(material.roughness);
+    write_index(buffer, material.roughness_tex);
+    buffer.write_f32(material.reflectance);
+    write_index(buffer, material.reflectance_tex);
+    buffer.write_f32(material.clear_coat);
+    write_index(buffer, material.clear_coat_tex);
+    buffer.write_f32(material.clear_coat_roughness);
+    write_index(buffer, material.clear_coat_roughness_tex);
+    buffer.write_f32(material.anisotropy);
+    write_index(buffer, material.anisotropy_tex);
+    write_vec4(buffer, material.anisotropy_direction);
+    buffer.write_f32(material.ambient_occlusion);
+    write_index(buffer, material.ambient_occlusion_tex);
+    write_vec4(buffer, material.normal);
+    write_index(buffer, material.normal_tex);
+    write_vec4(buffer, material.clear_coat_normal);
+    write_index(buffer, material.clear_coat_normal_tex);
+    write_vec4(buffer, material.emissive);
+    write_index(buffer, material.emissive_tex);
+    buffer.write_f32(material.ior);
+    buffer.write_f32(material.transmission);
+    buffer.write_f32(material.absorption);
+    buffer.write_f32(material.thickness);
+    write_vec4(buffer, material.sheen_color);
+}
+
+fn read_material(buffer: &mut ByteBuffer, images: usize) -> io::Result<Material<usize>> {
+    Ok(Material {
+        color: read_vec4(buffer)?,
+        color_tex: read_index(buffer, images, "image")?,
+        metallic: buffer.read_f32()?,
+        metallic_tex: read_index(buffer, images, "image")?,
+        roughness: buffer.read_f32()?,
+        roughness_tex: read_index(buffer, images, "image")?,
+        reflectance: buffer.read_f32()?,
+        reflectance_tex: read_index(buffer, images, "image")?,
+        clear_coat: buffer.read_f32()?,
+        clear_coat_tex: read_index(buffer, images, "image")?,
+        clear_coat_roughness: buffer.read_f32()?,
+        clear_coat_roughness_tex: read_index(buffer, images, "image")?,
+        anisotropy: buffer.read_f32()?,
+        anisotropy_tex: read_index(buffer, images, "image")?,
+        anisotropy_direction: read_vec4(buffer)?,
+        ambient_occlusion: buffer.read_f32()?,
+        ambient_occlusion_tex: read_index(buffer, images, "image")?,
+        normal: read_vec4(buffer)?,
+        normal_tex: read_index(buffer, images, "image")?,
+        clear_coat_normal: read_vec4(buffer)?,
+        clear_coat_normal_tex: read_index(buffer, images, "image")?,
+        emissive: read_vec4(buffer)?,
+        emissive_tex: read_index(buffer, images, "image")?,
+        ior: buffer.read_f32()?,
+        transmission: buffer.read_f32()?,
+        absorption: buffer.read_f32()?,
+        thickness: buffer.read_f32()?,
+        sheen_color: read_vec4(buffer)?,
+    })
+}
+
+fn topology_id(topology: Topology) -> u8 {
+    match topology {
+        Topology::Point => 0,
+        Topology::Line => 1,
+        Topology::LineStrip => 2,
+        Topology::Triangle => 3,
+        Topology::TriangleStrip => 4,
+    }
+}
+
+fn topology_from_id(id: u8) -> io::Result<Topology> {
+    match id {
+        0 => Ok(Topology::Point),
+        1 => Ok(Topology::Line),
+        2 => Ok(Topology::LineStrip),
+        3 => Ok(Topology::Triangle),
+        4 => Ok(Topology::TriangleStrip),
+        _ => Err(invalid(format!("Unknown topology {id}"))),
+    }
+}
+
+fn write_index(buffer: &mut ByteBuffer, index: Option<usize>) {
+    buffer.write_u32(index.map_or(NONE, |index| index as u32));
+}
+
+/// Reads an optional index into a table of `len` items, `what` names them in the error.
+fn read_index(buffer: &mut ByteBuffer, len: usize, what: &str) -> io::Result<Option<usize>> {
+    match buffer.read_u32()? {
+        NONE => Ok(None),
+        index => check_index(index as usize, len, what).map(Some),
+    }
+}
+
+fn check_index(index: usize, len: usize, what: &str) -> io::Result<usize> {
+    if index >= len {
+        return Err(invalid(format!(
+            "Invalid {what} index {index}, there are only {len}"
+        )));
+    }
+    Ok(index)
+}
+
+/// Reads the number of items that follow, which take at least `size` bytes each.
+fn read_count(buffer: &mut ByteBuffer, size: usize) -> io::Result<u32> {
+    let count = buffer.read_u32()?;
+    check_count(buffer, count, size)?;
+    Ok(count)
+}
+
+/// Counts are untrusted, so they are checked against the rest of the file before anything is
+/// allocated for them.
+fn check_count(buffer: &ByteBuffer, count: u32, size: usize) -> io::Result<()> {
+    if (count as usize).saturating_mul(size) > remaining(buffer) {
+        return Err(invalid(format!(
+            "{count} items don't fit into the rest of the file"
+        )));
+    }
+    Ok(())
+}
+
+fn remaining(buffer: &ByteBuffer) -> usize {
+    buffer.len() - buffer.get_rpos()
+}
+
+fn write_name(buffer: &mut ByteBuffer, name: &Option<String>) {
+    buffer.write_u8(name.is_some() as u8);
+    if let Some(name) = name {
+        buffer.write_string(name);
+    }
+}
+
+fn read_name(buffer: &mut ByteBuffer) -> io::Result<Option<String>> {
+    match buffer.read_u8()? {
+        0 => Ok(None),
+        _ => buffer.read_string().map(Some),
+    }
+}
+
+fn write_vec3(buffer: &mut ByteBuffer, vec: Vec3) {
+    buffer.write_f32(vec.x);
+    buffer.write_f32(vec.y);
+    buffer.write_f32(vec.z);
+}
+
+fn read_vec3(buffer: &mut ByteBuffer) -> io::Result<Vec3> {
+    Ok(Vec3::new(
+        buffer.read_f32()?,
+        buffer.read_f32()?,
+        buffer.read_f32()?,
+    ))
+}
+
+fn write_vec4(buffer: &mut ByteBuffer, vec: Vec4) {
+    buffer.write_f32(vec.x);
+    buffer.write_f32(vec.y);
+    buffer.write_f32(vec.z);
+    buffer.write_f32(vec.w);
+}
+
+fn read_vec4(buffer: &mut ByteBuffer) -> io::Result<Vec4> {
+    Ok(Vec4::new(
+        buffer.read_f32()?,
+        buffer.read_f32()?,
+        buffer.read_f32()?,
+        buffer.read_f32()?,
+    ))
+}
+
+fn buffer() -> ByteBuffer {
+    let mut buffer = ByteBuffer::new();
+    buffer.set_endian(Endian::LittleEndian);
+    buffer
+}
+
+fn read_buffer(data: &[u8], magic: &[u8; 4]) -> io::Result<ByteBuffer> {
+    let mut buffer = ByteBuffer::from_bytes(data);
+    buffer.set_endian(Endian::LittleEndian);
+    if buffer.read_bytes(4)? != magic {
+        return Err(invalid("Not a cooked asset of this kind"));
+    }
+    let version = buffer.read_u32()?;
+    if version != VERSION {
+        return Err(invalid(format!(
+            "Unsupported cooked asset version {version}, cook the assets again"
+        )));
+    }
+    Ok(buffer)
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Maps source asset paths to the cooked files that replace them, see [`AssetManager::load_cooked_manifest`](crate::asset::manager::AssetManager::load_cooked_manifest).
+/// Both paths are normalized and relative to the directory of the manifest.
+#[derive(Default, Debug)]
+pub struct CookManifest {
+    pub entries: Vec<(String, String)>,
+}
+
+impl CookManifest {
+    pub fn parse(source: &str) -> io::Result<Self> {
+        let mut lines = source.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            return Err(invalid(
+                "Not a cook manifest, or written by another version",
+            ));
+        }
+        let entries = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.split_once('\t')
+                    .map(|(source, cooked)| (source.to_string(), cooked.to_string()))
+                    .ok_or_else(|| invalid(format!("Invalid manifest line '{line}'")))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Self { entries })
+    }
+
+    pub fn write(&self) -> String {
+        let mut manifest = format!("{MANIFEST_HEADER}\n");
+        for (source, cooked) in &self.entries {
+            manifest.push_str(&format!("{source}\t{cooked}\n"));
+        }
+        manifest
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct CookReport {
+    pub cooked: usize,
+    /// Files that aren't cooked, or failed to cook, copied as they are.
+    pub copied: usize,
+    /// Paths of the files that failed to cook, with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Walks an asset directory and writes cooked versions of all textures, glTF models, shaders and
+/// texture atlases to an output directory, together with a [`CookManifest`]. Other files are copied as they are, so
+/// the output directory can be mounted in place of the input, or packed into an [`AssetArchive`](crate::asset::archive::AssetArchive).
+///
+/// Cooked textures are expanded to four channels and carry their mip chain, shaders are compiled
+/// to SPIR-V and models are stored in a binary format that needs no parsing or processing. The images
+/// listed in atlas source files are packed into cooked pages, see [`TextureAtlas::load`](crate::render::atlas::TextureAtlas::load).
+pub struct Cooker {
+    input: PathBuf,
+    output: PathBuf,
+    vfs: VirtualFileSystem,
+}
+
+impl Cooker {
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        let input = input.into();
+        let vfs = VirtualFileSystem::new();
+        vfs.mount(AssetSource::Directory(input.clone()), 0);
+        Self {
+            input,
+            output: output.into(),
+            vfs,
+        }
+    }
+
+    /// Fails if a shader doesn't compile, other assets that fail to cook are copied and listed in
+    /// the report.
+    pub fn cook(&self) -> io::Result<CookReport> {
+        let mut report = CookReport::default();
+        let mut manifest = CookManifest::default();
+        for path in self.files()? {
+            let extension = Path::new(&path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            let result = if TextureImporter.extensions().contains(&extension.as_str()) {
+                self.read(&path)
+                    .and_then(|data| cook_texture(&path, &data))
+                    .map(|cooked| (TEXTURE_EXTENSION, cooked))
+            } else if ModelImporter.extensions().contains(&extension.as_str()) {
+                self.cook_model(&path)
+                    .map(|cooked| (MODEL_EXTENSION, cooked))
+            } else if ShaderImporter::default()
+                .extensions()
+                .contains(&extension.as_str())
+                && extension != "glsl"
+            {
+                // Plain .glsl files are usually only included by other shaders, so they are copied.
+                // A copied shader that doesn't compile would only fail once it is loaded, so
+                // compile errors fail the whole cook.
+                let kind = ShaderImporter::kind_of(&path);
+                let source = self
+                    .vfs
+                    .read_to_string(&path)
+                    .map_err(|e| io::Error::new(e.kind(), format!("Failed to read {path}: {e}")))?;
+                let cooked = cook_shader(&path, &source, kind, |include| {
+                    self.vfs.read_to_string(include)
+                })
+                .map_err(|e| io::Error::other(format!("Failed to cook shader {e}")))?;
+                Ok((SHADER_EXTENSION, cooked))
+            } else if extension == ATLAS_SOURCE_EXTENSION {
+                self.cook_atlas(&path)
+                    .map(|cooked| (ATLAS_EXTENSION, cooked))
+            } else {
+                self.copy(&path)?;
+                report.copied += 1;
+                continue;
+            };
+
+            match result {
+                Ok((extension, data)) => {
+                    let cooked = format!("{path}.{extension}");
+                    self.write(&cooked, &data)?;
+                    manifest.entries.push((path, cooked));
+                    report.cooked += 1;
+                }
+                Err(e) => {
+                    log::error!("{e}, copying the source instead");
+                    self.copy(&path)?;
+                    report.copied += 1;
+                    report.failed.push((path, e));
+                }
+            }
+        }
+
+        self.write(MANIFEST_NAME, manifest.write().as_bytes())?;
+        Ok(report)
+    }
+
+    /// Cooks the model and the images embedded in it, which are written next to the cooked model.
+    fn cook_model(&self, path: &str) -> Result<Vec<u8>, String> {
+        let mut model = AssetLoader::read_gltf(path, |file| self.vfs.read(file))?;
+        let mut images = Vec::new();
+        for (index, image) in model.images.iter_mut().enumerate() {
+            if let ImageData::Embedded(embedded) = image {
+                let cooked = format!("{path}.image{index}.{TEXTURE_EXTENSION}");
+                let ImageData::Embedded(embedded) =
+                    std::mem::replace(image, ImageData::File(cooked.clone()))
+                else {
+                    unreachable!()
+                };
+                images.push((cooked, cook_image(embedded)));
+            }
+        }
+        for (cooked, data) in images {
+            self.write(&cooked, &data)
+                .map_err(|e| format!("Failed to write {cooked}: {e}"))?;
+        }
+        Ok(write_model(&model))
+    }
+
+    /// Packs the images listed in an atlas source file, the pages are written next to the cooked atlas.
+    fn cook_atlas(&self, path: &str) -> Result<Vec<u8>, String> {
+        let source = self
+            .vfs
+            .read_to_string(path)
+            .map_err(|e| format!("Failed to read {path}: {e}"))?;
+        let builder = AtlasBuilder::from_source(path, &source, |file| self.vfs.read(file))?;
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let mut pages = Vec::new();
+        for page in 0..builder.get_page_count() {
+            let cooked = format!("{path}.page{page}.{TEXTURE_EXTENSION}");
+            self.write(
+                &cooked,
+                &cook_image(DynamicImage::ImageRgba8(builder.render_page(page))),
+            )
+            .map_err(|e| format!("Failed to write {cooked}: {e}"))?;
+            pages.push(format!("{name}.page{page}.{TEXTURE_EXTENSION}"));
+        }
+        Ok(write_atlas(&builder, &pages))
+    }
+
+    fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.vfs
+            .read(path)
+            .map_err(|e| format!("Failed to read {path}: {e}"))
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        let file = self.output.join(path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file, data)
+    }
+
+    fn copy(&self, path: &str) -> io::Result<()> {
+        self.write(path, &std::fs::read(self.input.join(path))?)
+    }
+
+    /// Paths of all files below the input directory relative to it, sorted so cooking is deterministic.
+    fn files(&self) -> io::Result<Vec<String>> {
+        let mut files = Vec::new();
+        let mut directories = vec![self.input.clone()];
+        while let Some(directory) = directories.pop() {
+            for entry in std::fs::read_dir(&directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    directories.push(path);
+                } else if let Some(relative) = path
+                    .strip_prefix(&self.input)
+                    .ok()
+                    .and_then(|relative| relative.to_str())
+                {
+                    files.push(vfs::normalize(relative));
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
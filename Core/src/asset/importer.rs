@@ -1,66 +1,1051 @@
-use std::fs::OpenOptions;
-use std::io::Read;
-use image::{ColorType, DynamicImage};
+use gltf::image::Format;
+use gltf::mesh::Mode;
+use image::{
+    ColorType, DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, ImageFormat, Luma, LumaA, Rgb,
+    Rgb32FImage, RgbImage, Rgba, Rgba32FImage, RgbaImage,
+};
+use shaderc::ShaderKind;
+use std::any::{Any, TypeId};
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use crate::asset::asset::{AssetType, InnerAsset};
+use crate::asset::container::{self, ContainerTexture};
+use crate::asset::cook;
+use crate::asset::manager::{AssetHandle, AssetManager, Handle, LoadPriority};
+use crate::math::quat::Quat;
+use crate::math::vec::{Vec2, Vec3, Vec4};
 
 use crate::render::backend::buffer::MemoryProperties;
 use crate::render::backend::device::Device;
+use crate::render::backend::image::{
+    ColorSpace, Image, ImageAspect, ImageTiling, ImageType, ImageUsage, MVImageCreateInfo,
+};
+use crate::render::backend::pipeline::Topology;
+use crate::render::backend::shader::{MVShaderCreateInfo, Shader, ShaderCompileError};
 use crate::render::backend::Extent2D;
-use crate::render::backend::image::{Image, ImageAspect, ImageTiling, ImageType, ImageUsage, MVImageCreateInfo};
+use crate::render::environment::{BakedEnvironment, EnvironmentBaker, ENVIRONMENT_FORMAT};
+use crate::render::mesh::Mesh;
+use crate::render::model::{Material, Model, ModelMesh, ModelVertex, Node, Primitive};
 use crate::render::texture::Texture;
 
+/// Imports one kind of asset from files with the given extensions. Importers are registered with
+/// [`AssetManager::register_importer`] and run on the asset loader threads, the imported assets are
+/// then read from any thread.
+pub trait AssetImporter: Send + Sync + 'static {
+    type Asset: Send + Sync + 'static;
+    /// Stored in the asset when importing fails, see [`Asset::error_as`](crate::asset::asset::Asset::error_as).
+    type Error: Any + Send + Sync;
+
+    /// Extensions of the files this importer handles, without the leading dot.
+    fn extensions(&self) -> &[&str];
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Self::Asset, Self::Error>;
+
+    /// Bytes of cpu or gpu memory the asset uses, counted against the memory budget of the manager,
+    /// see [`AssetManager::set_memory_budget`]. Assets of other importers aren't included.
+    fn size_of(&self, _asset: &Self::Asset) -> u64 {
+        0
+    }
+}
+
+/// Passed to [`AssetImporter::import`], used to declare the assets the imported asset depends on.
+/// Dependencies are loaded together with the asset and kept loaded as long as it is, the asset only
+/// reports being loaded once all of its dependencies are done loading.
+pub struct ImportContext {
+    manager: Arc<AssetManager>,
+    path: String,
+    priority: LoadPriority,
+    pub(crate) dependencies: Vec<AssetHandle>,
+}
+
+impl ImportContext {
+    pub(crate) fn new(manager: Arc<AssetManager>, path: &str, priority: LoadPriority) -> Self {
+        Self {
+            manager,
+            path: path.to_string(),
+            priority,
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub fn get_manager(&self) -> &Arc<AssetManager> {
+        &self.manager
+    }
+
+    /// Path of the imported asset. Importers of cooked assets are passed the path of the cooked file
+    /// instead, settings of the asset are still looked up by this one.
+    pub fn get_asset_path(&self) -> &str {
+        &self.path
+    }
+
+    /// The priority the imported asset is loaded with, dependencies are loaded with it as well.
+    pub fn get_priority(&self) -> LoadPriority {
+        self.priority
+    }
+
+    /// Loads `handle` and keeps it loaded until the imported asset is unloaded or replaced. Assets
+    /// depending on each other in a cycle finish loading, but keep each other loaded.
+    pub fn depend_on(&mut self, handle: &AssetHandle) {
+        handle.load_with_priority(self.priority);
+        self.dependencies.push(handle.clone());
+    }
+
+    /// Gets or creates the asset at `path` and depends on it, see [`ImportContext::depend_on`].
+    pub fn load_dependency<T: Send + Sync + 'static>(&mut self, path: &str) -> Handle<T> {
+        let handle = self.manager.get_or_create::<T>(path);
+        self.depend_on(handle.untyped());
+        handle
+    }
+
+    pub fn get_dependencies(&self) -> &[AssetHandle] {
+        &self.dependencies
+    }
+
+    /// Reads a file through the [`VirtualFileSystem`](crate::asset::vfs::VirtualFileSystem) of the manager.
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        self.manager.get_file_system().read(path)
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        self.manager.get_file_system().read_to_string(path)
+    }
+}
+
+pub(crate) trait DynAssetImporter: Send + Sync {
+    fn import(&self, path: &str, context: &mut ImportContext) -> InnerAsset;
+
+    fn size_of(&self, asset: &InnerAsset) -> u64;
+
+    fn asset_type(&self) -> TypeId;
+
+    fn asset_type_name(&self) -> &'static str;
+}
+
+impl<I: AssetImporter> DynAssetImporter for I {
+    fn import(&self, path: &str, context: &mut ImportContext) -> InnerAsset {
+        match AssetImporter::import(self, path, context) {
+            Ok(asset) => InnerAsset::Loaded(Arc::new(asset)),
+            Err(err) => InnerAsset::Failed(Box::new(err)),
+        }
+    }
+
+    fn size_of(&self, asset: &InnerAsset) -> u64 {
+        match asset {
+            InnerAsset::Loaded(asset) => asset
+                .downcast_ref::<I::Asset>()
+                .map(|asset| AssetImporter::size_of(self, asset))
+                .unwrap_or_default(),
+            _ => 0,
+        }
+    }
+
+    fn asset_type(&self) -> TypeId {
+        TypeId::of::<I::Asset>()
+    }
+
+    fn asset_type_name(&self) -> &'static str {
+        std::any::type_name::<I::Asset>()
+    }
+}
+
+pub struct TextureImporter;
+
+impl AssetImporter for TextureImporter {
+    type Asset = Texture;
+    type Error = &'static str;
+
+    fn extensions(&self) -> &[&str] {
+        &[
+            "png", "jpg", "jpeg", "bmp", "tga", "gif", "webp", "tif", "tiff",
+        ]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Texture, &'static str> {
+        let data = context
+            .read(path)
+            .map_err(|_| "Failed to read texture file")?;
+        let color_space = context
+            .get_manager()
+            .get_color_space(context.get_asset_path());
+        context
+            .get_manager()
+            .get_loader()
+            .import_texture(path, &data, color_space)
+    }
+
+    fn size_of(&self, texture: &Texture) -> u64 {
+        texture.get_size()
+    }
+}
+
+pub struct ModelImporter;
+
+impl AssetImporter for ModelImporter {
+    type Asset = Model;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Model, String> {
+        context
+            .get_manager()
+            .get_loader()
+            .import_model(path, context)
+    }
+
+    fn size_of(&self, model: &Model) -> u64 {
+        model.get_size()
+    }
+}
+
+/// Compiles glsl shaders, the shader kind is taken from the extension unless it is set explicitly.
+#[derive(Default)]
+pub struct ShaderImporter {
+    kind: Option<ShaderKind>,
+}
+
+impl ShaderImporter {
+    pub fn with_kind(kind: ShaderKind) -> Self {
+        Self { kind: Some(kind) }
+    }
+
+    pub(crate) fn kind_of(path: &str) -> ShaderKind {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("vert") => ShaderKind::Vertex,
+            Some("frag") => ShaderKind::Fragment,
+            Some("comp") => ShaderKind::Compute,
+            Some("geom") => ShaderKind::Geometry,
+            Some("tesc") => ShaderKind::TessControl,
+            Some("tese") => ShaderKind::TessEvaluation,
+            _ => ShaderKind::InferFromSource,
+        }
+    }
+}
+
+impl AssetImporter for ShaderImporter {
+    type Asset = Shader;
+    type Error = ShaderCompileError;
+
+    fn extensions(&self) -> &[&str] {
+        &["vert", "frag", "comp", "geom", "tesc", "tese", "glsl"]
+    }
+
+    fn import(
+        &self,
+        path: &str,
+        context: &mut ImportContext,
+    ) -> Result<Shader, ShaderCompileError> {
+        let kind = self.kind.unwrap_or_else(|| Self::kind_of(path));
+        context
+            .get_manager()
+            .get_loader()
+            .import_shader(path, kind, context)
+    }
+
+    fn size_of(&self, shader: &Shader) -> u64 {
+        shader.get_code_size()
+    }
+}
+
+/// Imports textures written by the [`Cooker`](crate::asset::cook::Cooker) together with their
+/// cooked mip levels.
+pub struct CookedTextureImporter;
+
+impl AssetImporter for CookedTextureImporter {
+    type Asset = Texture;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &[cook::TEXTURE_EXTENSION]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Texture, String> {
+        let data = context
+            .read(path)
+            .map_err(|e| format!("Failed to read cooked texture {path}: {e}"))?;
+        let cooked =
+            cook::read_texture(&data).map_err(|e| format!("Invalid cooked texture {path}: {e}"))?;
+        let levels = cooked.levels.concat();
+        let color_space = context
+            .get_manager()
+            .get_color_space(context.get_asset_path());
+        Ok(context.get_manager().get_loader().create_texture_from_data(
+            cooked.width,
+            cooked.height,
+            cooked.color,
+            color_space,
+            levels,
+            path,
+        ))
+    }
+
+    fn size_of(&self, texture: &Texture) -> u64 {
+        texture.get_size()
+    }
+}
+
+/// Imports KTX2 and DDS textures with their mip levels and array layers. Compressed formats are
+/// uploaded as they are, importing fails if the device doesn't support the format. The color space
+/// is part of the format stored in the file, see [`container::read_texture`].
+pub struct ContainerTextureImporter;
+
+impl AssetImporter for ContainerTextureImporter {
+    type Asset = Texture;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["ktx2", "dds"]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Texture, String> {
+        let data = context
+            .read(path)
+            .map_err(|e| format!("Failed to read texture {path}: {e}"))?;
+        let limits = context.get_manager().get_device().get_image_limits();
+        let texture = container::read_texture(&data, limits)
+            .map_err(|e| format!("Invalid texture {path}: {e}"))?;
+        context
+            .get_manager()
+            .get_loader()
+            .create_texture_from_container(texture, path)
+    }
+
+    fn size_of(&self, texture: &Texture) -> u64 {
+        texture.get_size()
+    }
+}
+
+/// Imports equirectangular `.hdr` and `.exr` maps as cubemaps, baked as set with
+/// [`AssetManager::set_environment_options`]. A baked irradiance map is registered as the texture
+/// `<path>#irradiance`, which is kept loaded with the cubemap.
+pub struct EnvironmentImporter;
+
+impl AssetImporter for EnvironmentImporter {
+    type Asset = Texture;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["hdr", "exr"]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Texture, String> {
+        let data = context
+            .read(path)
+            .map_err(|e| format!("Failed to read environment map {path}: {e}"))?;
+        context
+            .get_manager()
+            .get_loader()
+            .import_environment(path, &data, context)
+    }
+
+    fn size_of(&self, texture: &Texture) -> u64 {
+        texture.get_size()
+    }
+}
+
+/// Imports cubemaps from `.cubemap` files, which list the images of the six faces ordered +x, -x, +y,
+/// -y, +z, -z, one path relative to the file per line. Empty lines and lines starting with `#` are
+/// skipped. The faces are converted to float and baked like the maps of the [`EnvironmentImporter`],
+/// 8 bit faces are read as sRGB unless their color space is set to linear.
+pub struct CubemapImporter;
+
+impl AssetImporter for CubemapImporter {
+    type Asset = Texture;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["cubemap"]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Texture, String> {
+        let source = context
+            .read_to_string(path)
+            .map_err(|e| format!("Failed to read cubemap {path}: {e}"))?;
+        context
+            .get_manager()
+            .get_loader()
+            .import_cubemap(path, &source, context)
+    }
+
+    fn size_of(&self, texture: &Texture) -> u64 {
+        texture.get_size()
+    }
+}
+
+/// Imports models written by the [`Cooker`](crate::asset::cook::Cooker).
+pub struct CookedModelImporter;
+
+impl AssetImporter for CookedModelImporter {
+    type Asset = Model;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &[cook::MODEL_EXTENSION]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Model, String> {
+        let data = context
+            .read(path)
+            .map_err(|e| format!("Failed to read cooked model {path}: {e}"))?;
+        let model =
+            cook::read_model(&data).map_err(|e| format!("Invalid cooked model {path}: {e}"))?;
+        let loader = context.get_manager().get_loader();
+        Ok(loader.build_model(path, model, context))
+    }
+
+    fn size_of(&self, model: &Model) -> u64 {
+        model.get_size()
+    }
+}
+
+/// Imports SPIR-V shaders written by the [`Cooker`](crate::asset::cook::Cooker).
+pub struct CookedShaderImporter;
+
+impl AssetImporter for CookedShaderImporter {
+    type Asset = Shader;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &[cook::SHADER_EXTENSION]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Shader, String> {
+        let data = context
+            .read(path)
+            .map_err(|e| format!("Failed to read cooked shader {path}: {e}"))?;
+        let (stage, code) =
+            cook::read_shader(&data).map_err(|e| format!("Invalid cooked shader {path}: {e}"))?;
+        Ok(Shader::new(
+            context.get_manager().get_device(),
+            MVShaderCreateInfo {
+                stage,
+                code,
+                label: Some(path.to_string()),
+            },
+        ))
+    }
+
+    fn size_of(&self, shader: &Shader) -> u64 {
+        shader.get_code_size()
+    }
+}
+
+/// A model read from a glTF or cooked file, before any gpu resources are created.
+pub(crate) struct ModelData {
+    pub(crate) meshes: Vec<MeshData>,
+    /// Textures are indices into `images`.
+    pub(crate) materials: Vec<Material<usize>>,
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) root_nodes: Vec<usize>,
+    pub(crate) images: Vec<ImageData>,
+}
+
+pub(crate) struct MeshData {
+    pub(crate) name: Option<String>,
+    pub(crate) primitives: Vec<PrimitiveData>,
+}
+
+pub(crate) struct PrimitiveData {
+    pub(crate) topology: Topology,
+    pub(crate) vertices: Vec<ModelVertex>,
+    pub(crate) indices: Option<Vec<u32>>,
+    pub(crate) material: Option<usize>,
+}
+
+pub(crate) enum ImageData {
+    /// Path of an image file, loaded as a dependency of the model.
+    File(String),
+    Embedded(DynamicImage),
+}
+
 #[derive(Clone)]
 pub struct AssetLoader {
-    device: Device
+    device: Device,
+    /// Only created once an environment map needs baking, which compiles its shaders.
+    environment_baker: Arc<OnceLock<EnvironmentBaker>>,
 }
 
 impl AssetLoader {
     pub fn new(device: Device) -> Self {
-        Self { device }
-    }
-
-    // pub(crate) fn import_model(&self, gltf: gltf::Result<Gltf>) -> Model {
-    //     let gltf = gltf.unwrap_or_else(|e| {
-    //         log::error!("Failed to load model, path:, error: {e}");
-    //         panic!();
-    //     });
-    //
-    //     // for scene in gltf.scenes() {
-    //     //     Self::process_node(gltf, scene.nodes().nth(0).unwrap_or_else(|e| {
-    //     //         log::error!("Failed to get root node? this should never happen, error: {e}");
-    //     //         panic!();
-    //     //     }));
-    //     // }
-    //
-    //     todo!();
-    // }
-    //
-    // fn process_node(&self, gltf: Gltf, node: gltf::Node) -> (Vec<Mesh>, Vec<String>, Vec<Texture>, Vec<Material>) {
-    //
-    //     let transform = node.transform();
-    //     for mesh in node.mesh() {
-    //         for primitive in mesh.primitives() {
-    //             let pos = primitive.get(&Semantic::Positions).unwrap().view().unwrap();
-    //             let data = match pos.buffer().source() {
-    //                 Source::Bin => &gltf.blob.as_ref().unwrap()[pos.offset()..pos.offset() + pos.length()],
-    //                 Source::Uri(file) => panic!(),
-    //             };
-    //         }
-    //     }
-    //
-    //     todo!();
-    // } ignore that for now, lets load in textures
-
-    pub(crate) fn import_texture(&self, path: &str) -> Result<Texture, &'static str> {
-        let image = if let Ok(image) = image::open(path) {
-            image
-        } else {
-            let mut buffer = Vec::new();
-            let mut file = OpenOptions::new().read(true).open(path).map_err(|_| "Failed to open texture file")?;
-            file.read_to_end(&mut buffer).map_err(|_| "Failed to read texture file")?;
-            image::load_from_memory(&buffer).map_err(|_| "Invalid texture format")?
+        Self {
+            device,
+            environment_baker: Arc::new(OnceLock::new()),
+        }
+    }
+
+    pub fn get_environment_baker(&self) -> &EnvironmentBaker {
+        self.environment_baker
+            .get_or_init(|| EnvironmentBaker::new(self.device.clone()))
+    }
+
+    pub(crate) fn import_model(
+        &self,
+        path: &str,
+        context: &mut ImportContext,
+    ) -> Result<Model, String> {
+        let data = Self::read_gltf(path, |file| context.read(file))?;
+        Ok(self.build_model(path, data, context))
+    }
+
+    /// Reads a glTF model without creating any gpu resources, files are read with `read`.
+    pub(crate) fn read_gltf(
+        path: &str,
+        read: impl Fn(&str) -> io::Result<Vec<u8>>,
+    ) -> Result<ModelData, String> {
+        let base = Path::new(path).parent().unwrap_or_else(|| Path::new("./"));
+        let data = read(path).map_err(|e| format!("Failed to import model {path}: {e}"))?;
+        let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice(&data)
+            .map_err(|e| format!("Failed to import model {path}: {e}"))?;
+
+        // External buffers are read through the file system of the manager, data uris are decoded by gltf
+        let buffers = document
+            .buffers()
+            .map(|buffer| {
+                let mut data = match buffer.source() {
+                    gltf::buffer::Source::Bin => blob
+                        .take()
+                        .ok_or_else(|| format!("Missing binary chunk in {path}"))?,
+                    gltf::buffer::Source::Uri(uri) => match Self::resolve_uri(base, uri) {
+                        Some(file) => read(&file)
+                            .map_err(|e| format!("Failed to read buffer {uri} of {path}: {e}"))?,
+                        None => {
+                            gltf::buffer::Data::from_source(buffer.source(), None)
+                                .map_err(|e| format!("Failed to read buffer {uri} of {path}: {e}"))?
+                                .0
+                        }
+                    },
+                };
+                while data.len() % 4 != 0 {
+                    data.push(0);
+                }
+                if data.len() < buffer.length() {
+                    return Err(format!(
+                        "Buffer {} of {path} is {} bytes, expected {}",
+                        buffer.index(),
+                        data.len(),
+                        buffer.length()
+                    ));
+                }
+                Ok(gltf::buffer::Data(data))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let images = document
+            .images()
+            .map(|image| {
+                if let gltf::image::Source::Uri { uri, .. } = image.source() {
+                    if let Some(file) = Self::resolve_uri(base, uri) {
+                        return Ok(ImageData::File(file));
+                    }
+                }
+                let data = gltf::image::Data::from_source(image.source(), None, &buffers).map_err(
+                    |e| format!("Failed to decode image {} of {path}: {e}", image.index()),
+                )?;
+                let image = Self::gltf_image_to_dynamic(data).ok_or_else(|| {
+                    format!("Invalid image data in image {} of {path}", image.index())
+                })?;
+                Ok(ImageData::Embedded(image))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let materials = document
+            .materials()
+            .map(|material| Self::import_material(&material))
+            .collect();
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| {
+                let primitives = mesh
+                    .primitives()
+                    .filter_map(|primitive| {
+                        Self::read_primitive(&primitive, &buffers, mesh.name()).transpose()
+                    })
+                    .collect::<Result<Vec<_>, String>>()
+                    .map_err(|e| format!("Invalid mesh {} of {path}: {e}", mesh.index()))?;
+                Ok(MeshData {
+                    name: mesh.name().map(str::to_string),
+                    primitives,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut nodes = document
+            .nodes()
+            .map(|node| {
+                let (translation, rotation, scale) = node.transform().decomposed();
+                Node {
+                    name: node.name().map(str::to_string),
+                    parent: None,
+                    children: node.children().map(|child| child.index()).collect(),
+                    mesh: node.mesh().map(|mesh| mesh.index()),
+                    translation: Vec3::new(translation[0], translation[1], translation[2]),
+                    rotation: Quat::new(rotation[0], rotation[1], rotation[2], rotation[3]),
+                    scale: Vec3::new(scale[0], scale[1], scale[2]),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for index in 0..nodes.len() {
+            for child in nodes[index].children.clone() {
+                nodes[child].parent = Some(index);
+            }
+        }
+
+        let root_nodes = match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+            None => (0..nodes.len())
+                .filter(|index| nodes[*index].parent.is_none())
+                .collect(),
         };
 
-        let image = match image.color() {
+        Ok(ModelData {
+            meshes,
+            materials,
+            nodes,
+            root_nodes,
+            images,
+        })
+    }
+
+    /// Creates the gpu resources of a model read from a glTF or cooked file.
+    pub(crate) fn build_model(
+        &self,
+        path: &str,
+        data: ModelData,
+        context: &mut ImportContext,
+    ) -> Model {
+        // Only base color and emissive textures hold colors, everything else is data like normals
+        let color_images = data
+            .materials
+            .iter()
+            .flat_map(|material| [material.color_tex, material.emissive_tex])
+            .flatten()
+            .collect::<Vec<_>>();
+        let color_space = |index| {
+            if color_images.contains(&index) {
+                ColorSpace::Srgb
+            } else {
+                ColorSpace::Linear
+            }
+        };
+
+        // Images in their own files are loaded like any other texture, embedded ones are registered
+        // as already loaded assets. Either way the model depends on them and materials refer to them
+        // by handle.
+        let textures = data
+            .images
+            .into_iter()
+            .enumerate()
+            .map(|(index, image)| match image {
+                ImageData::File(file) => {
+                    context
+                        .get_manager()
+                        .set_color_space(&file, color_space(index));
+                    context.load_dependency::<Texture>(&file).into_untyped()
+                }
+                ImageData::Embedded(image) => {
+                    let label = format!("{path}#image{index}");
+                    let texture = self.create_texture(image, color_space(index), &label);
+                    let handle = context.get_manager().create_loaded_asset(
+                        &label,
+                        AssetType::Texture,
+                        InnerAsset::Loaded(Arc::new(texture)),
+                    );
+                    context.depend_on(&handle);
+                    handle
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let materials = data
+            .materials
+            .into_iter()
+            .map(|material| material.map_textures(|index| textures[index].clone()))
+            .collect();
+
+        let meshes = data
+            .meshes
+            .into_iter()
+            .map(|mesh| ModelMesh {
+                primitives: mesh
+                    .primitives
+                    .into_iter()
+                    .map(|primitive| {
+                        let bytes = unsafe {
+                            std::slice::from_raw_parts(
+                                primitive.vertices.as_ptr() as *const u8,
+                                primitive.vertices.len() * mem::size_of::<ModelVertex>(),
+                            )
+                        };
+                        Primitive {
+                            mesh: Mesh::new(
+                                self.device.clone(),
+                                bytes,
+                                primitive.vertices.len() as u32,
+                                primitive.indices.as_deref(),
+                                mesh.name.clone(),
+                            ),
+                            topology: primitive.topology,
+                            material: primitive.material,
+                        }
+                    })
+                    .collect(),
+                name: mesh.name,
+            })
+            .collect();
+
+        Model {
+            meshes,
+            materials,
+            nodes: data.nodes,
+            root_nodes: data.root_nodes,
+            textures,
+        }
+    }
+
+    /// Path of the file an uri in a glTF file refers to, `None` for data uris.
+    fn resolve_uri(base: &Path, uri: &str) -> Option<String> {
+        if uri.starts_with("data:") {
+            return None;
+        }
+        let uri = uri
+            .strip_prefix("file://")
+            .or_else(|| uri.strip_prefix("file:"))
+            .unwrap_or(uri);
+
+        // Relative uris are percent encoded
+        let bytes = uri.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            let escaped = bytes
+                .get(index + 1..index + 3)
+                .filter(|_| bytes[index] == b'%')
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+            match escaped {
+                Some(escaped) => {
+                    decoded.push(escaped);
+                    index += 3;
+                }
+                None => {
+                    decoded.push(bytes[index]);
+                    index += 1;
+                }
+            }
+        }
+        let uri = String::from_utf8(decoded).ok()?;
+        base.join(uri).to_str().map(str::to_string)
+    }
+
+    fn read_primitive(
+        primitive: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        name: Option<&str>,
+    ) -> Result<Option<PrimitiveData>, String> {
+        let topology = match primitive.mode() {
+            Mode::Points => Topology::Point,
+            Mode::Lines => Topology::Line,
+            Mode::LineStrip => Topology::LineStrip,
+            Mode::Triangles => Topology::Triangle,
+            Mode::TriangleStrip => Topology::TriangleStrip,
+            mode => {
+                log::warn!(
+                    "Skipping primitive of mesh {name:?}, topology {mode:?} is not supported"
+                );
+                return Ok(None);
+            }
+        };
+
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let Some(positions) = reader.read_positions() else {
+            log::warn!("Skipping primitive of mesh {name:?}, it has no positions");
+            return Ok(None);
+        };
+
+        let mut vertices = positions
+            .map(|position| ModelVertex {
+                position: Vec3::new(position[0], position[1], position[2]),
+                color: Vec4::splat(1.0),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(tex_coords) = reader.read_tex_coords(0) {
+            for (vertex, tex_coord) in vertices.iter_mut().zip(tex_coords.into_f32()) {
+                vertex.tex_coord = Vec2::new(tex_coord[0], tex_coord[1]);
+            }
+        }
+        if let Some(tangents) = reader.read_tangents() {
+            for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+                vertex.tangent = Vec4::new(tangent[0], tangent[1], tangent[2], tangent[3]);
+            }
+        }
+        if let Some(colors) = reader.read_colors(0) {
+            for (vertex, color) in vertices.iter_mut().zip(colors.into_rgba_f32()) {
+                vertex.color = Vec4::new(color[0], color[1], color[2], color[3]);
+            }
+        }
+
+        let mut indices = reader
+            .read_indices()
+            .map(|indices| indices.into_u32().collect::<Vec<_>>());
+        if let Some(index) = indices
+            .iter()
+            .flatten()
+            .find(|index| **index as usize >= vertices.len())
+        {
+            return Err(format!(
+                "Index {index} is out of range, the primitive has {} vertices",
+                vertices.len()
+            ));
+        }
+
+        match reader.read_normals() {
+            Some(normals) => {
+                for (vertex, normal) in vertices.iter_mut().zip(normals) {
+                    vertex.normal = Vec3::new(normal[0], normal[1], normal[2]);
+                }
+            }
+            None if topology == Topology::Triangle => {
+                vertices = Self::flat_shaded(vertices, indices.take());
+            }
+            None => {}
+        }
+
+        Ok(Some(PrimitiveData {
+            topology,
+            vertices,
+            indices,
+            material: primitive.material().index(),
+        }))
+    }
+
+    /// glTF wants flat normals for triangles without normals, so every triangle gets its own vertices.
+    /// The indices have to be in range.
+    fn flat_shaded(vertices: Vec<ModelVertex>, indices: Option<Vec<u32>>) -> Vec<ModelVertex> {
+        let mut vertices = match indices {
+            Some(indices) => indices
+                .iter()
+                .map(|index| vertices[*index as usize])
+                .collect(),
+            None => vertices,
+        };
+
+        for triangle in vertices.chunks_exact_mut(3) {
+            let (a, b, c) = (
+                triangle[0].position,
+                triangle[1].position,
+                triangle[2].position,
+            );
+            let u = [b.x - a.x, b.y - a.y, b.z - a.z];
+            let v = [c.x - a.x, c.y - a.y, c.z - a.z];
+            let cross = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let length = (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+            let normal = if length > 0.0 {
+                Vec3::new(cross[0] / length, cross[1] / length, cross[2] / length)
+            } else {
+                Vec3::new(0.0, 0.0, 1.0)
+            };
+            for vertex in triangle {
+                vertex.normal = normal;
+            }
+        }
+
+        vertices
+    }
+
+    /// Textures are referenced by the index of their image.
+    fn import_material(material: &gltf::Material) -> Material<usize> {
+        let texture = |texture: gltf::Texture| texture.source().index();
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, a] = pbr.base_color_factor();
+        let [er, eg, eb] = material.emissive_factor();
+        let metallic_roughness = pbr
+            .metallic_roughness_texture()
+            .map(|info| texture(info.texture()));
+        let specular = material.specular();
+        let volume = material.volume();
+
+        // Reflectance 0.5 is a f0 of 4%, which is what the default ior of 1.5 gives
+        let ior = material.ior().unwrap_or(1.5);
+        let f0 = ((ior - 1.0) / (ior + 1.0)).powi(2)
+            * specular
+                .as_ref()
+                .map(|specular| specular.specular_factor())
+                .unwrap_or(1.0);
+
+        Material {
+            color: Vec4::new(r, g, b, a),
+            color_tex: pbr.base_color_texture().map(|info| texture(info.texture())),
+            metallic: pbr.metallic_factor(),
+            metallic_tex: metallic_roughness,
+            roughness: pbr.roughness_factor(),
+            roughness_tex: metallic_roughness,
+            reflectance: (f0 / 0.16).sqrt(),
+            reflectance_tex: specular
+                .and_then(|specular| specular.specular_texture())
+                .map(|info| texture(info.texture())),
+            ambient_occlusion: material
+                .occlusion_texture()
+                .map(|occlusion| occlusion.strength())
+                .unwrap_or(1.0),
+            ambient_occlusion_tex: material
+                .occlusion_texture()
+                .map(|occlusion| texture(occlusion.texture())),
+            normal_tex: material
+                .normal_texture()
+                .map(|normal| texture(normal.texture())),
+            emissive: Vec4::new(er, eg, eb, material.emissive_strength().unwrap_or(1.0)),
+            emissive_tex: material
+                .emissive_texture()
+                .map(|info| texture(info.texture())),
+            ior,
+            transmission: material
+                .transmission()
+                .map(|transmission| transmission.transmission_factor())
+                .unwrap_or(0.0),
+            // The default attenuation distance is infinite, which means no absorption
+            absorption: volume
+                .as_ref()
+                .map(|volume| 1.0 / volume.attenuation_distance())
+                .unwrap_or(0.0),
+            thickness: volume
+                .map(|volume| volume.thickness_factor())
+                .unwrap_or(0.0),
+            ..Default::default()
+        }
+    }
+
+    fn gltf_image_to_dynamic(data: gltf::image::Data) -> Option<DynamicImage> {
+        let (width, height, pixels) = (data.width, data.height, data.pixels);
+        let to_u16 = |pixels: &[u8]| {
+            pixels
+                .chunks_exact(2)
+                .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+                .collect::<Vec<_>>()
+        };
+        let to_f32 = |pixels: &[u8]| {
+            pixels
+                .chunks_exact(4)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect::<Vec<_>>()
+        };
+
+        match data.format {
+            Format::R8 => GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+            Format::R8G8 => {
+                GrayAlphaImage::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8)
+            }
+            Format::R8G8B8 => {
+                RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+            }
+            Format::R8G8B8A8 => {
+                RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+            }
+            Format::R16 => ImageBuffer::<Luma<u16>, _>::from_raw(width, height, to_u16(&pixels))
+                .map(DynamicImage::ImageLuma16),
+            Format::R16G16 => {
+                ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, to_u16(&pixels))
+                    .map(DynamicImage::ImageLumaA16)
+            }
+            Format::R16G16B16 => {
+                ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, to_u16(&pixels))
+                    .map(DynamicImage::ImageRgb16)
+            }
+            Format::R16G16B16A16 => {
+                ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, to_u16(&pixels))
+                    .map(DynamicImage::ImageRgba16)
+            }
+            Format::R32G32B32FLOAT => {
+                Rgb32FImage::from_raw(width, height, to_f32(&pixels)).map(DynamicImage::ImageRgb32F)
+            }
+            Format::R32G32B32A32FLOAT => Rgba32FImage::from_raw(width, height, to_f32(&pixels))
+                .map(DynamicImage::ImageRgba32F),
+        }
+    }
+
+    /// Compile errors are returned with the file and line they occurred in, includes are resolved
+    /// relative to `path`.
+    pub(crate) fn import_shader(
+        &self,
+        path: &str,
+        kind: ShaderKind,
+        context: &ImportContext,
+    ) -> Result<Shader, ShaderCompileError> {
+        let source = context
+            .read_to_string(path)
+            .map_err(|e| ShaderCompileError {
+                file: path.to_string(),
+                line: None,
+                message: format!("Failed to read shader file: {e}"),
+            })?;
+
+        Shader::try_compile_with(
+            self.device.clone(),
+            &source,
+            kind,
+            Some(path.to_string()),
+            &[],
+            |include| context.read_to_string(include),
+        )
+    }
+
+    pub(crate) fn import_texture(
+        &self,
+        path: &str,
+        data: &[u8],
+        color_space: ColorSpace,
+    ) -> Result<Texture, &'static str> {
+        let image = Self::decode_image(path, data)?;
+        Ok(self.create_texture(image, color_space, path))
+    }
+
+    /// The format is taken from the extension of `path`, and guessed from the data if that fails.
+    pub(crate) fn decode_image(path: &str, data: &[u8]) -> Result<DynamicImage, &'static str> {
+        ImageFormat::from_path(path)
+            .ok()
+            .and_then(|format| image::load_from_memory_with_format(data, format).ok())
+            .map_or_else(|| image::load_from_memory(data), Ok)
+            .map_err(|_| "Invalid texture format")
+    }
+
+    fn create_texture(&self, image: DynamicImage, color_space: ColorSpace, label: &str) -> Texture {
+        let image = Self::expand_to_rgba(image);
+
+        // TODO: check if this was an issue in the shader, remove this if it was
+        //let image = image.fliph();
+
+        let width = image.width();
+        let height = image.height();
+        let format = image.color();
+        self.create_texture_from_data(
+            width,
+            height,
+            format,
+            color_space,
+            image.into_bytes(),
+            label,
+        )
+    }
+
+    /// Gpus don't support three channel formats, so every image is expanded to four channels,
+    /// keeping its bit depth.
+    pub(crate) fn expand_to_rgba(image: DynamicImage) -> DynamicImage {
+        match image.color() {
             ColorType::L8 => DynamicImage::ImageRgba8(image.to_rgba8()),
             ColorType::La8 => DynamicImage::ImageRgba8(image.to_rgba8()),
             ColorType::Rgb8 => DynamicImage::ImageRgba8(image.to_rgba8()),
@@ -72,34 +1057,293 @@ impl AssetLoader {
             ColorType::Rgb32F => DynamicImage::ImageRgba32F(image.to_rgba32f()),
             ColorType::Rgba32F => image,
             _ => image,
+        }
+    }
+
+    /// Creates a texture with a full mip chain. `data` holds either all mip levels, or only the first
+    /// one, in which case the others are generated on the gpu. Only 8 bit images are stored as sRGB.
+    pub(crate) fn create_texture_from_data(
+        &self,
+        width: u32,
+        height: u32,
+        format: ColorType,
+        color_space: ColorSpace,
+        data: Vec<u8>,
+        label: &str,
+    ) -> Texture {
+        let size = Extent2D { width, height };
+        let format =
+            crate::render::backend::image::ImageFormat::from(format).with_color_space(color_space);
+        // sRGB formats can't be used as storage images
+        let usage = if format.is_srgb() {
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED
+        } else {
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED | ImageUsage::STORAGE
         };
+        let image = Image::new(
+            self.device.clone(),
+            MVImageCreateInfo {
+                size,
+                format,
+                usage,
+                memory_properties: MemoryProperties::DEVICE_LOCAL,
+                aspect: ImageAspect::COLOR,
+                tiling: ImageTiling::Optimal,
+                layer_count: 1,
+                mip_level_count: size.get_max_mip_levels(),
+                image_type: ImageType::Image2D,
+                cubemap: false,
+                memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                data: Some(data),
+                label: Some(label.to_string()),
+            },
+        );
 
-        // TODO: check if this was an issue in the shader, remove this if it was
-        //let image = image.fliph();
+        Texture::new(image)
+    }
 
-        let width = image.width();
-        let height = image.height();
-        let format = image.color();
-        let data = image.into_bytes();
+    /// Converts an equirectangular map to a cubemap and bakes the maps asked for in its options.
+    pub(crate) fn import_environment(
+        &self,
+        path: &str,
+        data: &[u8],
+        context: &mut ImportContext,
+    ) -> Result<Texture, String> {
+        let options = context
+            .get_manager()
+            .get_environment_options(context.get_asset_path());
+        let image = Self::decode_image(path, data)
+            .map_err(|e| format!("Failed to import environment map {path}: {e}"))?
+            .to_rgba32f();
+        let size = Extent2D {
+            width: image.width(),
+            height: image.height(),
+        };
+        let equirect = Image::new(
+            self.device.clone(),
+            MVImageCreateInfo {
+                size,
+                format: ENVIRONMENT_FORMAT,
+                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+                memory_properties: MemoryProperties::DEVICE_LOCAL,
+                aspect: ImageAspect::COLOR,
+                tiling: ImageTiling::Optimal,
+                layer_count: 1,
+                mip_level_count: 1,
+                image_type: ImageType::Image2D,
+                cubemap: false,
+                memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                data: Some(Self::to_half_floats(&image)),
+                label: Some(path.to_string()),
+            },
+        );
+
+        let face_size = options.face_size.unwrap_or(size.width / 4).max(1);
+        let baked = self
+            .get_environment_baker()
+            .bake_equirect(&equirect, face_size, &options, path);
+        Ok(Self::register_environment(baked, context))
+    }
+
+    /// Creates a cubemap from the faces listed in a `.cubemap` file, see [`CubemapImporter`].
+    pub(crate) fn import_cubemap(
+        &self,
+        path: &str,
+        source: &str,
+        context: &mut ImportContext,
+    ) -> Result<Texture, String> {
+        let options = context
+            .get_manager()
+            .get_environment_options(context.get_asset_path());
+        let base = Path::new(path).parent().unwrap_or_else(|| Path::new("./"));
+        let files = source
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        if files.len() != 6 {
+            return Err(format!(
+                "Cubemap {path} lists {} faces, expected 6",
+                files.len()
+            ));
+        }
+
+        let faces = files
+            .into_iter()
+            .map(|face| {
+                let file = base
+                    .join(face)
+                    .to_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("Invalid face {face} of cubemap {path}"))?;
+                let data = context
+                    .read(&file)
+                    .map_err(|e| format!("Failed to read face {face} of cubemap {path}: {e}"))?;
+                let image = Self::decode_image(&file, &data)
+                    .map_err(|e| format!("Failed to import face {face} of cubemap {path}: {e}"))?;
+                Ok(Self::to_linear_rgba32f(
+                    image,
+                    context.get_manager().get_color_space(&file),
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let face_size = faces[0].width();
+        if faces
+            .iter()
+            .any(|face| face.dimensions() != (face_size, face_size))
+        {
+            return Err(format!(
+                "Faces of cubemap {path} have to be square and of the same size"
+            ));
+        }
+
+        // Faces are the layers of the first mip level, the other levels are generated from it
+        let data = faces.iter().flat_map(Self::to_half_floats).collect();
+        let cubemap = EnvironmentBaker::create_cubemap(&self.device, face_size, Some(data), path);
+        let baked = if options.prefilter_specular || options.irradiance_size.is_some() {
+            self.get_environment_baker()
+                .bake_cubemap(cubemap, &options, path)
+        } else {
+            BakedEnvironment {
+                cubemap,
+                irradiance: None,
+            }
+        };
+        Ok(Self::register_environment(baked, context))
+    }
+
+    /// The irradiance map is registered as an already loaded texture the cubemap depends on.
+    fn register_environment(baked: BakedEnvironment, context: &mut ImportContext) -> Texture {
+        if let Some(irradiance) = baked.irradiance {
+            let label = format!("{}#irradiance", context.get_asset_path());
+            let handle = context.get_manager().create_loaded_asset(
+                &label,
+                AssetType::Texture,
+                InnerAsset::Loaded(Arc::new(Texture::new(irradiance))),
+            );
+            context.depend_on(&handle);
+        }
+        Texture::new(baked.cubemap)
+    }
 
-        let image = Image::new(self.device.clone(), MVImageCreateInfo {
-            size: Extent2D {
-                width,
-                height,
+    /// Float pixels of an image, 8 bit images holding colors are converted from sRGB to linear.
+    fn to_linear_rgba32f(image: DynamicImage, color_space: ColorSpace) -> Rgba32FImage {
+        let srgb = color_space == ColorSpace::Srgb
+            && matches!(
+                image.color(),
+                ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8
+            );
+        let mut image = image.to_rgba32f();
+        if srgb {
+            for pixel in image.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = if *channel <= 0.04045 {
+                        *channel / 12.92
+                    } else {
+                        ((*channel + 0.055) / 1.055).powf(2.4)
+                    };
+                }
+            }
+        }
+        image
+    }
+
+    /// Packs float pixels into the half floats environment maps are baked in.
+    fn to_half_floats(image: &Rgba32FImage) -> Vec<u8> {
+        image
+            .as_raw()
+            .iter()
+            .flat_map(|value| Self::to_half_float(*value).to_ne_bytes())
+            .collect()
+    }
+
+    /// Rounds to the nearest half float, values too large for one are clamped to the largest finite one.
+    fn to_half_float(value: f32) -> u16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+        let mantissa = bits & 0x7fffff;
+
+        if exponent == 0xff - 127 + 15 {
+            // Infinity stays infinity, NaN stays NaN
+            return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+        }
+        if exponent >= 0x1f {
+            return sign | 0x7bff;
+        }
+        if exponent <= 0 {
+            if exponent < -10 {
+                return sign;
+            }
+            // Subnormal, the implicit leading bit becomes part of the mantissa
+            let mantissa = mantissa | 0x800000;
+            let shift = (14 - exponent) as u32;
+            let half = (mantissa >> shift) + ((mantissa >> (shift - 1)) & 1);
+            return sign | half as u16;
+        }
+        let half = ((exponent as u32) << 10 | mantissa >> 13) + ((mantissa >> 12) & 1);
+        sign | half.min(0x7bff) as u16
+    }
+
+    /// Creates a texture from a KTX2 or DDS file, see [`container::read_texture`].
+    pub(crate) fn create_texture_from_container(
+        &self,
+        texture: ContainerTexture,
+        label: &str,
+    ) -> Result<Texture, String> {
+        let size = Extent2D {
+            width: texture.width,
+            height: texture.height,
+        };
+        // Compressed formats can't be rendered to, so their mipmaps can't be generated
+        let mip_level_count = if texture.generate_mipmaps && !texture.format.is_compressed() {
+            size.get_max_mip_levels()
+        } else {
+            texture.levels.len() as u32
+        };
+        // Compressed and sRGB formats can't be used as storage images
+        let usage = if texture.format.is_compressed() || texture.format.is_srgb() {
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED
+        } else {
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED | ImageUsage::STORAGE
+        };
+        let generate_mipmaps = mip_level_count > texture.levels.len() as u32;
+        if !self
+            .device
+            .supports_format(texture.format, &usage, generate_mipmaps)
+        {
+            return Err(format!(
+                "Format {:?} of {label} is not supported by the device",
+                texture.format
+            ));
+        }
+        let image_type = if texture.cubemap {
+            ImageType::Cubemap
+        } else if texture.layer_count > 1 {
+            ImageType::Image2DArray
+        } else {
+            ImageType::Image2D
+        };
+        let image = Image::new(
+            self.device.clone(),
+            MVImageCreateInfo {
+                size,
+                format: texture.format,
+                usage,
+                memory_properties: MemoryProperties::DEVICE_LOCAL,
+                aspect: ImageAspect::COLOR,
+                tiling: ImageTiling::Optimal,
+                layer_count: texture.layer_count,
+                mip_level_count,
+                image_type,
+                cubemap: texture.cubemap,
+                memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                data: Some(texture.levels.concat()),
+                label: Some(label.to_string()),
             },
-            format: format.into(),
-            usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED | ImageUsage::STORAGE,
-            memory_properties: MemoryProperties::DEVICE_LOCAL,
-            aspect: ImageAspect::COLOR,
-            tiling: ImageTiling::Optimal,
-            layer_count: 1,
-            image_type: ImageType::Image2D,
-            cubemap: false,
-            memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
-            data: Some(data),
-            label: Some(path.to_string()),
-        });
+        );
 
         Ok(Texture::new(image))
     }
-}
\ No newline at end of file
+}
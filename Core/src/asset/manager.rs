@@ -1,17 +1,33 @@
+use ahash::AHasher;
+use std::any::TypeId;
+use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::marker::PhantomData;
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread::JoinHandle;
-use ahash::AHasher;
+use std::time::Duration;
 
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use hashbrown::HashMap;
 use mvutils::hashers::U64IdentityHasher;
 use parking_lot::{Mutex, RwLock};
 
 use crate::asset::asset::{Asset, AssetType, InnerAsset};
-use crate::asset::importer::AssetLoader;
+use crate::asset::cook::CookManifest;
+use crate::asset::importer::{
+    AssetImporter, AssetLoader, ContainerTextureImporter, CookedModelImporter,
+    CookedShaderImporter, CookedTextureImporter, CubemapImporter, DynAssetImporter,
+    EnvironmentImporter, ModelImporter, ShaderImporter, TextureImporter,
+};
+use crate::asset::queue::{AssetTask, TaskQueue};
+use crate::asset::token::{LoadStatus, LoadToken};
+use crate::asset::vfs::{self, VirtualFileSystem};
+use crate::asset::watcher::AssetWatcher;
 use crate::render::backend::device::Device;
+use crate::render::backend::image::ColorSpace;
+use crate::render::environment::EnvironmentOptions;
 
 #[derive(Clone)]
 pub struct AssetHandle {
@@ -23,26 +39,69 @@ pub struct AssetHandle {
 }
 
 impl AssetHandle {
-    pub fn load(&self) {
-        if self.global { return; }
-        if self.counter.lock().fetch_add(1, Ordering::AcqRel) == 0 {
-            self.manager.push(AssetTask::Load(self.clone()));
+    /// Adds a reference to the asset and loads it if it isn't yet. The returned token completes
+    /// once the asset is loaded.
+    pub fn load(&self) -> LoadToken {
+        self.load_with_priority(LoadPriority::Normal)
+    }
+
+    /// Like [`AssetHandle::load`], but queues the load with `priority`. If the asset is already
+    /// queued with a lower priority, it is moved up. Dependencies are loaded with the same priority.
+    pub fn load_with_priority(&self, priority: LoadPriority) -> LoadToken {
+        let token = LoadToken::new(self.clone());
+        let asset = self.get();
+        let mut waiting = asset.waiting.lock();
+        if !self.global && self.counter.lock().fetch_add(1, Ordering::AcqRel) == 0 {
+            waiting.push(token.clone());
+            drop(waiting);
+            self.manager.push(AssetTask::Load(self.clone()), priority);
+            return token;
         }
+
+        match LoadStatus::of(&asset) {
+            // Still loading
+            LoadStatus::Loading | LoadStatus::Pending => {
+                waiting.push(token.clone());
+                drop(waiting);
+                self.manager.queue.promote_load(self, priority);
+            }
+            status => {
+                drop(waiting);
+                token.complete(status);
+            }
+        }
+        token
     }
 
+    /// Removes a reference, the asset is unloaded once there are none left. If its load didn't
+    /// start yet, it is cancelled and the waiting tokens complete with [`LoadStatus::Cancelled`].
     pub fn unload(&self) {
-        if self.global { return; }
+        if self.global {
+            return;
+        }
         if self.counter.lock().fetch_sub(1, Ordering::AcqRel) == 1 {
-            self.manager.push(AssetTask::Unload(self.clone()));
+            if self.manager.queue.cancel_load(self) {
+                self.get().finish_waiting();
+            }
+            // An earlier unload may have been skipped while the cancelled load was queued
+            self.manager
+                .push(AssetTask::Unload(self.clone()), LoadPriority::Normal);
         }
     }
 
+    /// Imports the asset again in the background. The current version stays usable until the new
+    /// one is done, failed reloads keep the current version.
     pub fn reload(&self) {
-        if self.counter.lock().load(Ordering::Acquire) > 0 {
-            self.manager.push(AssetTask::Load(self.clone()));
+        if self.is_referenced() {
+            self.manager
+                .push(AssetTask::Reload(self.clone()), LoadPriority::Normal);
         }
     }
 
+    pub(crate) fn is_referenced(&self) -> bool {
+        self.global || self.counter.lock().load(Ordering::Acquire) > 0
+    }
+
     pub fn is_valid(&self) -> bool {
         self.manager.is_asset_handle_valid(self)
     }
@@ -62,6 +121,14 @@ impl AssetHandle {
     pub fn get_path(&self) -> &str {
         &self.path
     }
+
+    /// Converts this into a typed handle, if the asset is imported as a `T`.
+    pub fn typed<T: Send + Sync + 'static>(self) -> Option<Handle<T>> {
+        (self.get().importer.asset_type() == TypeId::of::<T>()).then(|| Handle {
+            handle: self,
+            _marker: PhantomData,
+        })
+    }
 }
 
 impl PartialEq for AssetHandle {
@@ -78,95 +145,402 @@ impl Hash for AssetHandle {
     }
 }
 
+/// An [`AssetHandle`] that is known to hold a `T`, so the asset can be accessed directly.
+pub struct Handle<T> {
+    handle: AssetHandle,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + Sync + 'static> Handle<T> {
+    pub fn load(&self) -> LoadToken {
+        self.handle.load()
+    }
+
+    pub fn load_with_priority(&self, priority: LoadPriority) -> LoadToken {
+        self.handle.load_with_priority(priority)
+    }
+
+    pub fn unload(&self) {
+        self.handle.unload();
+    }
+
+    pub fn reload(&self) {
+        self.handle.reload();
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.handle.is_valid()
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.handle.is_loaded()
+    }
+
+    /// The asset, if it is loaded. It stays valid when the asset is unloaded or reloaded, call this
+    /// again to pick up the new version.
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.handle.get().get::<T>()
+    }
+
+    pub fn get_asset(&self) -> Arc<Asset> {
+        self.handle.get()
+    }
+
+    pub fn get_path(&self) -> &str {
+        self.handle.get_path()
+    }
+
+    pub fn untyped(&self) -> &AssetHandle {
+        &self.handle
+    }
+
+    pub fn into_untyped(self) -> AssetHandle {
+        self.handle
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.handle.hash(state);
+    }
+}
+
+/// Queued loads run in order of priority, loads with the same priority in the order they were queued.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LoadPriority {
+    /// Needed for the current frame, like UI textures.
+    Immediate,
+    #[default]
+    Normal,
+    /// Prefetching, only loaded when nothing else is queued.
+    Background,
+}
+
+type ReloadCallback = Arc<dyn Fn(&AssetHandle) + Send + Sync>;
+
 pub struct AssetManager {
     asset_map: RwLock<HashMap<AssetHandle, Arc<Asset>, U64IdentityHasher>>,
-    threads: Vec<(JoinHandle<()>, Sender<AssetTask>)>,
-    index: AtomicU64,
-    queued: Arc<AtomicU64>,
+    threads: RwLock<Vec<JoinHandle<()>>>,
+    queue: Arc<TaskQueue>,
     loader: AssetLoader,
+    importers: RwLock<HashMap<String, Arc<dyn DynAssetImporter>>>,
+    vfs: VirtualFileSystem,
+    cooked: RwLock<HashMap<String, String>>,
+    color_spaces: RwLock<HashMap<String, ColorSpace>>,
+    environment_options: RwLock<HashMap<String, EnvironmentOptions>>,
+    memory: AtomicU64,
+    memory_budget: AtomicU64,
+    /// Loaded assets without references, least recently used first.
+    cached: Mutex<VecDeque<AssetHandle>>,
+    device: Device,
+    retired: Mutex<Vec<(u64, InnerAsset)>>,
+    frame: AtomicU64,
+    frames_in_flight: AtomicU64,
+    watcher: Mutex<Option<AssetWatcher>>,
+    reload_senders: Mutex<Vec<Sender<AssetHandle>>>,
+    reload_callbacks: Mutex<Vec<ReloadCallback>>,
+    main_thread: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
 }
 
 impl AssetManager {
     pub fn new(device: Device, thread_count: u64) -> Arc<Self> {
         assert!(thread_count > 0, "Asset manager thread count cannot be 0!");
-        let mut threads = Vec::with_capacity(thread_count as usize);
-        let queued = Arc::new(AtomicU64::new(0));
-        for _ in 0..thread_count {
-            let (sender, receiver) = unbounded();
-            let queued = queued.clone();
-            let thread = std::thread::spawn(|| Self::loader_thread(receiver, queued));
-            threads.push((thread, sender));
-        }
-        
-        Self {
+        let queue = Arc::new(TaskQueue::new());
+        let threads = (0..thread_count)
+            .map(|_| {
+                let queue = queue.clone();
+                std::thread::spawn(|| Self::loader_thread(queue))
+            })
+            .collect();
+
+        let manager = Self {
             asset_map: RwLock::new(HashMap::with_hasher(U64IdentityHasher::default())),
-            threads,
-            index: AtomicU64::new(0),
-            queued,
-            loader: AssetLoader::new(device),
-        }.into()
+            threads: RwLock::new(threads),
+            queue,
+            loader: AssetLoader::new(device.clone()),
+            importers: RwLock::new(HashMap::new()),
+            vfs: VirtualFileSystem::new(),
+            cooked: RwLock::new(HashMap::new()),
+            color_spaces: RwLock::new(HashMap::new()),
+            environment_options: RwLock::new(HashMap::new()),
+            memory: AtomicU64::new(0),
+            memory_budget: AtomicU64::new(0),
+            cached: Mutex::new(VecDeque::new()),
+            device,
+            retired: Mutex::new(Vec::new()),
+            frame: AtomicU64::new(0),
+            frames_in_flight: AtomicU64::new(3),
+            watcher: Mutex::new(None),
+            reload_senders: Mutex::new(Vec::new()),
+            reload_callbacks: Mutex::new(Vec::new()),
+            main_thread: Mutex::new(Vec::new()),
+        };
+        manager.register_importer(TextureImporter);
+        manager.register_importer(ContainerTextureImporter);
+        manager.register_importer(EnvironmentImporter);
+        manager.register_importer(CubemapImporter);
+        manager.register_importer(ModelImporter);
+        manager.register_importer(ShaderImporter::default());
+        manager.register_importer(CookedTextureImporter);
+        manager.register_importer(CookedModelImporter);
+        manager.register_importer(CookedShaderImporter);
+        manager.into()
+    }
+
+    /// Registers an importer for all of its extensions, replacing the importers previously
+    /// registered for them. Importers for textures, KTX2 and DDS textures, environment maps, glTF
+    /// models, glsl shaders and their cooked versions are registered by default.
+    pub fn register_importer<I: AssetImporter>(&self, importer: I) {
+        let extensions = importer
+            .extensions()
+            .iter()
+            .map(|ext| ext.to_lowercase())
+            .collect::<Vec<_>>();
+        let importer: Arc<dyn DynAssetImporter> = Arc::new(importer);
+        let mut importers = self.importers.write();
+        for extension in extensions {
+            importers.insert(extension, importer.clone());
+        }
     }
 
-    #[allow(invalid_reference_casting)]
-    fn loader_thread(receiver: Receiver<AssetTask>, queued: Arc<AtomicU64>) {
-        while let Ok(task) = receiver.recv() {
+    fn find_importer<T: Send + Sync + 'static>(&self, path: &str) -> Arc<dyn DynAssetImporter> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let Some(importer) = self.importers.read().get(&extension).cloned() else {
+            log::error!("No asset importer registered for extension '{extension}' of '{path}'");
+            panic!();
+        };
+        if importer.asset_type() != TypeId::of::<T>() {
+            log::error!(
+                "Asset '{path}' is imported as {}, not as {}",
+                importer.asset_type_name(),
+                std::any::type_name::<T>()
+            );
+            panic!();
+        }
+        importer
+    }
+
+    /// Reads a manifest written by the [`Cooker`](crate::asset::cook::Cooker) through the file system
+    /// of the manager. Assets whose path is listed are imported from their cooked version from then
+    /// on, mount the cooked output before calling this. Returns the number of cooked assets.
+    pub fn load_cooked_manifest(&self, path: &str) -> std::io::Result<usize> {
+        let manifest = CookManifest::parse(&self.vfs.read_to_string(path)?)?;
+        let base = Path::new(path)
+            .parent()
+            .and_then(|base| base.to_str())
+            .unwrap_or_default();
+        let mut cooked = self.cooked.write();
+        for (source, output) in &manifest.entries {
+            cooked.insert(
+                vfs::normalize(&format!("{base}/{source}")),
+                vfs::normalize(&format!("{base}/{output}")),
+            );
+        }
+        Ok(manifest.entries.len())
+    }
+
+    /// The cooked version of the asset at `path` and its importer, if there is one that imports the
+    /// same type as `importer`.
+    pub(crate) fn find_cooked(
+        &self,
+        path: &str,
+        importer: &Arc<dyn DynAssetImporter>,
+    ) -> Option<(String, Arc<dyn DynAssetImporter>)> {
+        let cooked = self.get_cooked_path(path)?;
+        let extension = Path::new(&cooked)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let cooked_importer = self.importers.read().get(&extension)?.clone();
+        if cooked_importer.asset_type() != importer.asset_type() {
+            log::warn!(
+                "Cooked version '{cooked}' of '{path}' is not a {}, importing the source",
+                importer.asset_type_name()
+            );
+            return None;
+        }
+        Some((cooked, cooked_importer))
+    }
+
+    /// The path of the cooked version of `path`, if the cooked manifest lists one.
+    pub(crate) fn get_cooked_path(&self, path: &str) -> Option<String> {
+        self.cooked.read().get(&vfs::normalize(path)).cloned()
+    }
+
+    /// All loader threads take their tasks from the same queue, so a thread busy with a large asset
+    /// never holds up the tasks behind it while other threads are idle.
+    fn loader_thread(queue: Arc<TaskQueue>) {
+        while let Some((task, priority)) = queue.pop() {
+            let _running = queue.running();
             match task {
+                // Tasks of the same asset can run on different threads, so they are checked against
+                // the reference count again instead of trusting the order they arrive in
                 AssetTask::Load(handle) => {
                     let asset = handle.get();
-                    unsafe { &mut *(&*asset as *const Asset as *mut Asset) }.load();
-                    queued.fetch_sub(1, Ordering::AcqRel);
+                    let busy = asset.busy.lock();
+                    if handle.is_referenced() {
+                        // Cached assets are still loaded, so this only takes them out of the cache
+                        handle.manager.uncache(&handle);
+                        asset.load(priority);
+                    }
+                    drop(busy);
+                    asset.finish_waiting();
+                    handle.manager.evict();
                 }
                 AssetTask::Unload(handle) => {
                     let asset = handle.get();
-                    unsafe { &mut *(&*asset as *const Asset as *mut Asset) }.unload();
-                    queued.fetch_sub(1, Ordering::AcqRel);
+                    let busy = asset.busy.lock();
+                    if !handle.is_referenced() {
+                        if asset.is_imported() && handle.manager.get_memory_budget() > 0 {
+                            handle.manager.cache(&handle);
+                        } else {
+                            asset.unload();
+                        }
+                    }
+                    drop(busy);
+                    asset.finish_waiting();
+                    handle.manager.evict();
                 }
                 AssetTask::Reload(handle) => {
                     let asset = handle.get();
-                    unsafe { &mut *(&*asset as *const Asset as *mut Asset) }.reload();
-                    queued.fetch_sub(1, Ordering::AcqRel);
+                    let busy = asset.busy.lock();
+                    let reloaded = handle.is_referenced() && asset.reload(priority);
+                    drop(busy);
+                    asset.finish_waiting();
+                    if reloaded {
+                        handle.manager.notify_reloaded(&handle);
+                    }
+                    handle.manager.evict();
                 }
-                AssetTask::Close => {
-                    queued.fetch_sub(1, Ordering::AcqRel);
-                    break
-                },
             }
         }
     }
 
+    /// Creates a handle to a `T`, imported by the importer registered for the extension of `path`.
+    pub fn create<T: Send + Sync + 'static>(self: &Arc<Self>, path: &str) -> Handle<T> {
+        let importer = self.find_importer::<T>(path);
+        Handle {
+            handle: self.create_asset_inner(path, importer, false),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn create_global<T: Send + Sync + 'static>(self: &Arc<Self>, path: &str) -> Handle<T> {
+        let importer = self.find_importer::<T>(path);
+        Handle {
+            handle: self.create_asset_inner(path, importer, true),
+            _marker: PhantomData,
+        }
+    }
+
     pub fn create_asset(self: &Arc<Self>, path: &str, ty: AssetType) -> AssetHandle {
-        self.create_asset_inner(path, ty, false)
+        self.create_asset_inner(path, ty.importer(), false)
     }
 
     pub fn create_global_asset(self: &Arc<Self>, path: &str, ty: AssetType) -> AssetHandle {
-        self.create_asset_inner(path, ty, true)
+        self.create_asset_inner(path, ty.importer(), true)
     }
 
-    fn create_asset_inner(self: &Arc<Self>, path: &str, ty: AssetType, global: bool) -> AssetHandle {
+    /// Like [`AssetManager::create`], but returns the existing handle if `path` is already registered.
+    pub fn get_or_create<T: Send + Sync + 'static>(self: &Arc<Self>, path: &str) -> Handle<T> {
+        if let Some(handle) = self.find_handle(path) {
+            if let Some(handle) = handle.typed::<T>() {
+                return handle;
+            }
+            log::error!(
+                "Asset '{path}' is already registered as another type than {}",
+                std::any::type_name::<T>()
+            );
+            panic!();
+        }
+        self.create(path)
+    }
+
+    fn find_handle(self: &Arc<Self>, path: &str) -> Option<AssetHandle> {
+        let key = self.create_handle(path, false, 0);
+        self.asset_map
+            .read()
+            .get_key_value(&key)
+            .map(|(handle, _)| handle.clone())
+    }
+
+    /// Registers an asset that was already loaded as part of another asset, like a texture
+    /// embedded in a model. The caller has to reference it, usually with [`ImportContext::depend_on`](crate::asset::importer::ImportContext::depend_on).
+    /// If the path is already registered, the new version replaces the old one like a reload.
+    pub(crate) fn create_loaded_asset(
+        self: &Arc<Self>,
+        path: &str,
+        ty: AssetType,
+        inner: InnerAsset,
+    ) -> AssetHandle {
+        if let Some(handle) = self.find_handle(path) {
+            let old = handle.get().replace(inner);
+            self.retire(old);
+            return handle;
+        }
+
+        let handle = self.create_handle(path, false, 0);
+        let asset = Asset::new(inner, ty.importer(), handle.clone());
+        self.asset_map.write().insert(handle.clone(), asset.into());
+        handle
+    }
+
+    fn create_asset_inner(
+        self: &Arc<Self>,
+        path: &str,
+        importer: Arc<dyn DynAssetImporter>,
+        global: bool,
+    ) -> AssetHandle {
+        let handle = self.create_handle(path, global, 0);
+        let asset = Asset::new(InnerAsset::Unloaded, importer, handle.clone());
+        self.asset_map.write().insert(handle.clone(), asset.into());
+        if global {
+            self.push(AssetTask::Load(handle.clone()), LoadPriority::Normal);
+        }
+        handle
+    }
+
+    fn create_handle(self: &Arc<Self>, path: &str, global: bool, references: u64) -> AssetHandle {
         let mut hasher = AHasher::default();
         path.hash(&mut hasher);
         let handle = hasher.finish();
-        let handle = AssetHandle {
+        AssetHandle {
             manager: self.clone(),
             path: Arc::new(path.to_string()),
             handle,
             global,
-            counter: Arc::new(AtomicU64::new(0).into()),
-        };
-        let asset = Asset {
-            inner: InnerAsset::Unloaded,
-            ty,
-            handle: handle.clone(),
-        };
-        self.asset_map.write().insert(handle.clone(), asset.into());
-        if global {
-            self.push(AssetTask::Load(handle.clone()));
+            counter: Arc::new(AtomicU64::new(references).into()),
         }
-        handle
     }
 
     pub fn get(&self, handle: &AssetHandle) -> Arc<Asset> {
-        self.asset_map.read().get(handle).expect("AssetHandle not valid").clone()
+        self.asset_map
+            .read()
+            .get(handle)
+            .expect("AssetHandle not valid")
+            .clone()
     }
 
     pub fn is_asset_handle_valid(&self, handle: &AssetHandle) -> bool {
@@ -174,58 +548,276 @@ impl AssetManager {
     }
 
     pub fn is_asset_loaded(&self, handle: &AssetHandle) -> bool {
-        self.asset_map.read().get(handle).map(|asset| asset.is_loaded()).unwrap_or_default()
+        self.asset_map
+            .read()
+            .get(handle)
+            .map(|asset| asset.is_loaded())
+            .unwrap_or_default()
     }
 
-    fn push(&self, task: AssetTask) {
-        let index = self.index.load(Ordering::Acquire);
-        self.index.store((index + 1) % self.threads.len() as u64, Ordering::Release);
-        self.queued.fetch_add(1, Ordering::AcqRel);
-        #[allow(invalid_reference_casting)]
-        if let Err(task) = self.threads[index as usize].1.send(task) {
-            // Unsafe but not error-prone code, if the thread is down, we can safely replace it like
-            // this, since we are not pushing to the Vec, so it cannot reallocate
-            log::error!("Asset loading thread has dropped receiver, this means that it was probably killed or panicked. Starting new asset loader thread!");
-            let (sender, receiver) = unbounded();
-            sender.send(task.0).expect("Failed to send upon creation");
-            let queued = self.queued.clone();
-            let thread = std::thread::spawn(|| Self::loader_thread(receiver, queued));
-            let _unsafe_mut = unsafe { &mut *(&self.threads as *const _ as *mut Vec<(JoinHandle<()>, Sender<AssetTask>)>) };
-            _unsafe_mut[index as usize] = (thread, sender);
+    fn push(&self, task: AssetTask, priority: LoadPriority) {
+        let threads = self.threads.read();
+        // The manager is shutting down, assets are unloaded directly
+        if threads.is_empty() {
+            return;
+        }
+        self.queue.push(task, priority);
+        if !threads.iter().any(JoinHandle::is_finished) {
+            return;
+        }
+        drop(threads);
+
+        let mut threads = self.threads.write();
+        for thread in threads.iter_mut().filter(|thread| thread.is_finished()) {
+            log::error!("Asset loading thread has stopped, this means that it was probably killed or panicked. Starting new asset loader thread!");
+            let queue = self.queue.clone();
+            *thread = std::thread::spawn(|| Self::loader_thread(queue));
         }
     }
 
+    /// Number of queued and running tasks.
     pub fn get_queued(&self) -> u64 {
-        self.queued.load(Ordering::Acquire)
+        self.queue.queued()
     }
 
     pub fn get_loader(&self) -> AssetLoader {
         self.loader.clone()
     }
-}
 
-impl Drop for AssetManager {
-    fn drop(&mut self) {
-        for (handle, _) in self.asset_map.read().iter() {
-            if handle.global || handle.counter.lock().load(Ordering::Acquire) > 0 {
-                self.push(AssetTask::Unload(handle.clone()));
+    pub fn get_device(&self) -> Device {
+        self.device.clone()
+    }
+
+    /// All importers read asset files through this, mount directories, embedded files or archives
+    /// on it to change where asset paths resolve to.
+    pub fn get_file_system(&self) -> &VirtualFileSystem {
+        &self.vfs
+    }
+
+    /// Marks the texture at `path` as color, which is uploaded as sRGB, or as data like normal maps,
+    /// which is uploaded as is. Textures are color by default, formats without an sRGB variant are
+    /// always linear. Takes effect the next time the texture is imported.
+    pub fn set_color_space(&self, path: &str, color_space: ColorSpace) {
+        self.color_spaces
+            .write()
+            .insert(vfs::normalize(path), color_space);
+    }
+
+    pub fn get_color_space(&self, path: &str) -> ColorSpace {
+        self.color_spaces
+            .read()
+            .get(&vfs::normalize(path))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets how the environment map or cubemap at `path` is baked, see [`EnvironmentImporter`] and
+    /// [`CubemapImporter`]. Takes effect the next time the map is imported.
+    pub fn set_environment_options(&self, path: &str, options: EnvironmentOptions) {
+        self.environment_options
+            .write()
+            .insert(vfs::normalize(path), options);
+    }
+
+    pub fn get_environment_options(&self, path: &str) -> EnvironmentOptions {
+        self.environment_options
+            .read()
+            .get(&vfs::normalize(path))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets how many bytes loaded assets may use before unreferenced ones are unloaded. Assets
+    /// without references stay loaded as long as everything fits into the budget, so loading them
+    /// again is free, once it is exceeded the least recently used ones are unloaded first. Assets
+    /// that are referenced are never unloaded, even if they alone exceed the budget. Defaults to
+    /// 0, which unloads assets as soon as their last reference is gone.
+    pub fn set_memory_budget(&self, bytes: u64) {
+        self.memory_budget.store(bytes, Ordering::Release);
+        self.evict();
+    }
+
+    pub fn get_memory_budget(&self) -> u64 {
+        self.memory_budget.load(Ordering::Acquire)
+    }
+
+    /// Bytes used by all loaded assets, including cached ones, see [`AssetImporter::size_of`].
+    pub fn get_memory_usage(&self) -> u64 {
+        self.memory.load(Ordering::Acquire)
+    }
+
+    /// Number of loaded assets that are kept only because they fit into the memory budget.
+    pub fn get_cached_count(&self) -> usize {
+        self.cached.lock().len()
+    }
+
+    pub(crate) fn track_memory(&self, old: u64, new: u64) {
+        self.memory.fetch_add(new, Ordering::AcqRel);
+        self.memory.fetch_sub(old, Ordering::AcqRel);
+    }
+
+    /// Keeps an asset whose last reference is gone loaded, as the most recently used one.
+    fn cache(&self, handle: &AssetHandle) {
+        let mut cached = self.cached.lock();
+        cached.retain(|cached| cached != handle);
+        cached.push_back(handle.clone());
+    }
+
+    fn uncache(&self, handle: &AssetHandle) {
+        self.cached.lock().retain(|cached| cached != handle);
+    }
+
+    /// Unloads cached assets, least recently used first, until the loaded assets fit into the budget.
+    fn evict(&self) {
+        while self.get_memory_usage() > self.get_memory_budget() {
+            let Some(handle) = self.cached.lock().pop_front() else {
+                return;
+            };
+            let asset = handle.get();
+            let busy = asset.busy.lock();
+            if !handle.is_referenced() {
+                asset.unload();
             }
+            drop(busy);
+            asset.finish_waiting();
         }
-        for (_, sender) in &self.threads {
-            let _ = sender.send(AssetTask::Close);
+    }
+
+    /// Sets how many frames the renderer can have in flight, unloaded and replaced gpu resources are
+    /// kept alive for this many frames. Defaults to 3, [`Renderer::set_asset_manager`] sets it to the
+    /// frames in flight of its swapchain.
+    ///
+    /// [`Renderer::set_asset_manager`]: crate::render::renderer::Renderer::set_asset_manager
+    pub fn set_max_frames_in_flight(&self, frames: u32) {
+        self.frames_in_flight
+            .store(frames as u64, Ordering::Release);
+    }
+
+    /// Runs the load callbacks of [`LoadToken`]s that completed since the last call. Has to be
+    /// called regularly from the main thread, [`Window::run`] calls it before every update if the
+    /// manager was set with [`Window::set_asset_manager`].
+    ///
+    /// [`Window::run`]: crate::render::window::Window::run
+    /// [`Window::set_asset_manager`]: crate::render::window::Window::set_asset_manager
+    pub fn update(&self) {
+        let callbacks = std::mem::take(&mut *self.main_thread.lock());
+        for callback in callbacks {
+            callback();
         }
+    }
 
-        for (thread, _) in self.threads.drain(..) {
-            let _ = thread.join();
+    pub(crate) fn run_on_main_thread(&self, callback: impl FnOnce() + Send + 'static) {
+        self.main_thread.lock().push(Box::new(callback));
+    }
+
+    /// Has to be called once per frame after submitting, destroys the resources of unloaded and
+    /// replaced assets that are no longer used by any frame in flight. [`Renderer::end_frame`] calls
+    /// it if the manager was set with [`Renderer::set_asset_manager`].
+    ///
+    /// [`Renderer::end_frame`]: crate::render::renderer::Renderer::end_frame
+    /// [`Renderer::set_asset_manager`]: crate::render::renderer::Renderer::set_asset_manager
+    pub fn end_frame(&self) {
+        let frame = self.frame.fetch_add(1, Ordering::AcqRel) + 1;
+        let frames_in_flight = self.frames_in_flight.load(Ordering::Acquire);
+        let expired = {
+            let mut retired = self.retired.lock();
+            let (expired, kept) = retired
+                .drain(..)
+                .partition::<Vec<_>, _>(|(retired_at, _)| retired_at + frames_in_flight <= frame);
+            *retired = kept;
+            expired
+        };
+        drop(expired);
+    }
+
+    /// Number of unloaded or replaced assets still waiting to be destroyed.
+    pub fn get_retired(&self) -> usize {
+        self.retired.lock().len()
+    }
+
+    /// Starts watching the files of all loaded and failed assets, checking them every `interval`.
+    /// Changed files are reloaded automatically, subscribers are notified once the new version is in.
+    pub fn enable_hot_reload(self: &Arc<Self>, interval: Duration) {
+        *self.watcher.lock() = Some(AssetWatcher::new(Arc::downgrade(self), interval));
+    }
+
+    pub fn disable_hot_reload(&self) {
+        let watcher = self.watcher.lock().take();
+        drop(watcher);
+    }
+
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.watcher.lock().is_some()
+    }
+
+    /// Returns a channel that receives the handle of every asset that was successfully reloaded.
+    pub fn subscribe_reloads(&self) -> Receiver<AssetHandle> {
+        let (sender, receiver) = unbounded();
+        self.reload_senders.lock().push(sender);
+        receiver
+    }
+
+    /// Registers a callback for every asset that was successfully reloaded. It is called on the
+    /// asset loader thread, use [`AssetManager::subscribe_reloads`] to handle reloads elsewhere.
+    pub fn on_reload(&self, callback: impl Fn(&AssetHandle) + Send + Sync + 'static) {
+        self.reload_callbacks.lock().push(Arc::new(callback));
+    }
+
+    /// Assets depending on the reloaded one are reported as reloaded as well.
+    pub(crate) fn notify_reloaded(&self, handle: &AssetHandle) {
+        let mut reloaded = vec![handle.clone()];
+        let mut index = 0;
+        while index < reloaded.len() {
+            for dependent in reloaded[index].get().get_dependents() {
+                if !reloaded.contains(&dependent) {
+                    reloaded.push(dependent);
+                }
+            }
+            index += 1;
+        }
+
+        let callbacks = self.reload_callbacks.lock().clone();
+        for handle in &reloaded {
+            self.reload_senders
+                .lock()
+                .retain(|sender| sender.send(handle.clone()).is_ok());
+            for callback in &callbacks {
+                callback(handle);
+            }
         }
     }
-}
 
-enum AssetTask {
-    Load(AssetHandle),
-    Unload(AssetHandle),
-    Reload(AssetHandle),
-    Close,
+    pub(crate) fn get_watched_handles(&self) -> Vec<AssetHandle> {
+        self.asset_map
+            .read()
+            .iter()
+            .filter(|(_, asset)| asset.is_imported() || asset.failed())
+            .map(|(handle, _)| handle.clone())
+            .collect()
+    }
+
+    pub(crate) fn retire(&self, inner: InnerAsset) {
+        if matches!(inner, InnerAsset::Unloaded | InnerAsset::Failed(_)) {
+            return;
+        }
+        let frame = self.frame.load(Ordering::Acquire);
+        self.retired.lock().push((frame, inner));
+    }
 }
 
-unsafe impl Send for AssetTask {}
\ No newline at end of file
+impl Drop for AssetManager {
+    fn drop(&mut self) {
+        self.watcher.get_mut().take();
+        self.queue.close();
+        for thread in self.threads.get_mut().drain(..) {
+            let _ = thread.join();
+        }
+
+        for asset in self.asset_map.get_mut().values() {
+            let _busy = asset.busy.lock();
+            asset.unload();
+        }
+        self.device.wait_idle();
+        self.retired.get_mut().clear();
+    }
+}
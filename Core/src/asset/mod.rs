@@ -1,3 +1,10 @@
+pub mod archive;
 pub mod asset;
+pub mod container;
+pub mod cook;
+pub mod importer;
 pub mod manager;
-mod importer;
\ No newline at end of file
+mod queue;
+pub mod token;
+pub mod vfs;
+mod watcher;
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::asset::manager::{AssetHandle, LoadPriority};
+
+pub(crate) enum AssetTask {
+    Load(AssetHandle),
+    Unload(AssetHandle),
+    Reload(AssetHandle),
+}
+
+struct QueueState {
+    /// One queue per [`LoadPriority`], in order of priority.
+    levels: [VecDeque<AssetTask>; 3],
+    closed: bool,
+}
+
+/// The work queue shared by all asset loader threads. Every idle thread takes the next task with
+/// the highest priority, so one thread stuck on a large asset doesn't hold up the tasks behind it.
+pub(crate) struct TaskQueue {
+    state: Mutex<QueueState>,
+    available: Condvar,
+    /// Tasks queued or running.
+    queued: AtomicU64,
+}
+
+impl TaskQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                levels: Default::default(),
+                closed: false,
+            }),
+            available: Condvar::new(),
+            queued: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn push(&self, task: AssetTask, priority: LoadPriority) {
+        let mut state = self.state.lock();
+        if state.closed {
+            return;
+        }
+        self.queued.fetch_add(1, Ordering::AcqRel);
+        state.levels[priority as usize].push_back(task);
+        drop(state);
+        self.available.notify_one();
+    }
+
+    /// Blocks until there is a task, returns `None` once the queue is closed.
+    pub(crate) fn pop(&self) -> Option<(AssetTask, LoadPriority)> {
+        let mut state = self.state.lock();
+        loop {
+            if state.closed {
+                return None;
+            }
+            for (level, priority) in [
+                LoadPriority::Immediate,
+                LoadPriority::Normal,
+                LoadPriority::Background,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                if let Some(task) = state.levels[level].pop_front() {
+                    return Some((task, priority));
+                }
+            }
+            self.available.wait(&mut state);
+        }
+    }
+
+    /// Has to be held while a task returned by [`TaskQueue::pop`] runs. The task is done once it is
+    /// dropped, which also happens if the task panics, so the queue still drains.
+    pub(crate) fn running(&self) -> RunningTask<'_> {
+        RunningTask(self)
+    }
+
+    /// Removes the queued load of `handle`, returns false if there was none, in which case it may
+    /// already be running.
+    pub(crate) fn cancel_load(&self, handle: &AssetHandle) -> bool {
+        let mut state = self.state.lock();
+        for level in &mut state.levels {
+            if let Some(index) = level
+                .iter()
+                .position(|task| matches!(task, AssetTask::Load(queued) if queued == handle))
+            {
+                level.remove(index);
+                self.queued.fetch_sub(1, Ordering::AcqRel);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Moves the queued load of `handle` up to `priority`, if it is queued with a lower one.
+    pub(crate) fn promote_load(&self, handle: &AssetHandle, priority: LoadPriority) {
+        let mut state = self.state.lock();
+        for level in priority as usize + 1..state.levels.len() {
+            if let Some(index) = state.levels[level]
+                .iter()
+                .position(|task| matches!(task, AssetTask::Load(queued) if queued == handle))
+            {
+                let task = state.levels[level]
+                    .remove(index)
+                    .expect("Queued task disappeared");
+                state.levels[priority as usize].push_back(task);
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn queued(&self) -> u64 {
+        self.queued.load(Ordering::Acquire)
+    }
+
+    /// Wakes up all loader threads and makes them exit, tasks still queued are dropped.
+    pub(crate) fn close(&self) {
+        let mut state = self.state.lock();
+        state.closed = true;
+        for level in &mut state.levels {
+            level.clear();
+        }
+        drop(state);
+        self.available.notify_all();
+    }
+}
+
+pub(crate) struct RunningTask<'a>(&'a TaskQueue);
+
+impl Drop for RunningTask<'_> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::AcqRel);
+    }
+}
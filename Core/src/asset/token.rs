@@ -0,0 +1,175 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::asset::asset::{Asset, InnerAsset};
+use crate::asset::manager::AssetHandle;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadStatus {
+    /// The token hasn't completed yet, or the asset is imported but its dependencies aren't.
+    Pending,
+    /// The asset is referenced and its import is still queued or running. Tokens never complete
+    /// with this status.
+    Loading,
+    Loaded,
+    Failed,
+    /// The asset was unloaded before it finished loading.
+    Cancelled,
+}
+
+impl LoadStatus {
+    pub(crate) fn of(asset: &Asset) -> Self {
+        let status = match &*asset.read() {
+            InnerAsset::Loaded(_) => LoadStatus::Loaded,
+            InnerAsset::Failed(_) => LoadStatus::Failed,
+            InnerAsset::Unloaded if asset.handle.is_referenced() => LoadStatus::Loading,
+            InnerAsset::Unloaded => LoadStatus::Cancelled,
+        };
+        // Checked without holding the lock, the dependencies lock other assets which may depend on
+        // this one
+        if status == LoadStatus::Loaded && !asset.dependencies_ready() {
+            LoadStatus::Pending
+        } else {
+            status
+        }
+    }
+}
+
+type Callback = Box<dyn FnOnce(AssetHandle) + Send>;
+
+struct LoadState {
+    status: LoadStatus,
+    wakers: Vec<Waker>,
+    on_loaded: Vec<Callback>,
+    on_failed: Vec<Callback>,
+}
+
+/// Returned by [`AssetHandle::load`], completes once the asset is loaded or failed to load. It can
+/// be polled, waited on or awaited, for example in an mvsync task.
+#[derive(Clone)]
+pub struct LoadToken {
+    handle: AssetHandle,
+    state: Arc<(Mutex<LoadState>, Condvar)>,
+}
+
+impl LoadToken {
+    pub(crate) fn new(handle: AssetHandle) -> Self {
+        Self {
+            handle,
+            state: Arc::new((
+                Mutex::new(LoadState {
+                    status: LoadStatus::Pending,
+                    wakers: Vec::new(),
+                    on_loaded: Vec::new(),
+                    on_failed: Vec::new(),
+                }),
+                Condvar::new(),
+            )),
+        }
+    }
+
+    pub(crate) fn complete(&self, status: LoadStatus) {
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock();
+        if state.status != LoadStatus::Pending {
+            return;
+        }
+        state.status = status;
+        let on_loaded = std::mem::take(&mut state.on_loaded);
+        let on_failed = std::mem::take(&mut state.on_failed);
+        let wakers = std::mem::take(&mut state.wakers);
+
+        // Queued before anyone waiting is woken up, so the callbacks run in the next update
+        let callbacks = match status {
+            LoadStatus::Loaded => on_loaded,
+            LoadStatus::Failed => on_failed,
+            _ => Vec::new(),
+        };
+        for callback in callbacks {
+            self.queue(callback);
+        }
+        drop(state);
+
+        condvar.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn queue(&self, callback: Callback) {
+        let handle = self.handle.clone();
+        self.handle
+            .get_manager()
+            .run_on_main_thread(move || callback(handle));
+    }
+
+    pub fn get_handle(&self) -> &AssetHandle {
+        &self.handle
+    }
+
+    pub fn status(&self) -> LoadStatus {
+        self.state.0.lock().status
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status() != LoadStatus::Pending
+    }
+
+    /// Blocks until the asset is done loading.
+    pub fn wait(&self) -> LoadStatus {
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock();
+        while state.status == LoadStatus::Pending {
+            condvar.wait(&mut state);
+        }
+        state.status
+    }
+
+    /// Runs `callback` during the next [`AssetManager::update`](crate::asset::manager::AssetManager::update)
+    /// after the asset has loaded.
+    pub fn on_loaded(&self, callback: impl FnOnce(AssetHandle) + Send + 'static) -> &Self {
+        let mut state = self.state.0.lock();
+        match state.status {
+            LoadStatus::Pending => state.on_loaded.push(Box::new(callback)),
+            LoadStatus::Loaded => {
+                drop(state);
+                self.queue(Box::new(callback));
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Runs `callback` during the next [`AssetManager::update`](crate::asset::manager::AssetManager::update)
+    /// after the asset has failed to load.
+    pub fn on_failed(&self, callback: impl FnOnce(AssetHandle) + Send + 'static) -> &Self {
+        let mut state = self.state.0.lock();
+        match state.status {
+            LoadStatus::Pending => state.on_failed.push(Box::new(callback)),
+            LoadStatus::Failed => {
+                drop(state);
+                self.queue(Box::new(callback));
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+impl Future for LoadToken {
+    type Output = LoadStatus;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<LoadStatus> {
+        let mut state = self.state.0.lock();
+        if state.status == LoadStatus::Pending {
+            state.wakers.push(cx.waker().clone());
+            Poll::Pending
+        } else {
+            Poll::Ready(state.status)
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use include_dir::Dir;
+use parking_lot::RwLock;
+
+use crate::asset::archive::AssetArchive;
+
+/// Something the [`VirtualFileSystem`] can read asset files from.
+pub enum AssetSource {
+    /// Loose files below a directory on disk, used during development.
+    Directory(PathBuf),
+    /// Files embedded into the binary with `include_dir!`.
+    Embedded(&'static Dir<'static>),
+    /// A packed asset archive, used by shipped builds.
+    Archive(AssetArchive),
+}
+
+impl AssetSource {
+    fn read(&self, path: &str) -> Option<io::Result<Vec<u8>>> {
+        match self {
+            AssetSource::Directory(root) => {
+                let file = root.join(path);
+                file.is_file().then(|| std::fs::read(file))
+            }
+            AssetSource::Embedded(dir) => dir
+                .get_file(dir.path().join(path))
+                .map(|file| Ok(file.contents().to_vec())),
+            AssetSource::Archive(archive) => archive.contains(path).then(|| archive.read(path)),
+        }
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        match self {
+            AssetSource::Directory(root) => root.join(path).is_file(),
+            AssetSource::Embedded(dir) => dir.get_file(dir.path().join(path)).is_some(),
+            AssetSource::Archive(archive) => archive.contains(path),
+        }
+    }
+
+    /// Only loose files can change while running.
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        match self {
+            AssetSource::Directory(root) => std::fs::metadata(root.join(path))
+                .and_then(|meta| meta.modified())
+                .ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MountId(u64);
+
+struct Mount {
+    id: MountId,
+    priority: i32,
+    source: Arc<AssetSource>,
+}
+
+/// Resolves asset paths through mounted [`AssetSource`]s. Mounts are searched from the highest
+/// priority to the lowest, mounts with the same priority from the most recently mounted one.
+/// Paths that no mount contains are read from the OS file system as they are, so absolute paths
+/// and paths relative to the working directory keep working without any mounts.
+pub struct VirtualFileSystem {
+    mounts: RwLock<Vec<Mount>>,
+    next_id: AtomicU64,
+}
+
+impl VirtualFileSystem {
+    pub fn new() -> Self {
+        Self {
+            mounts: RwLock::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn mount(&self, source: AssetSource, priority: i32) -> MountId {
+        let id = MountId(self.next_id.fetch_add(1, Ordering::AcqRel));
+        let mut mounts = self.mounts.write();
+        let index = mounts
+            .iter()
+            .position(|mount| mount.priority <= priority)
+            .unwrap_or(mounts.len());
+        mounts.insert(
+            index,
+            Mount {
+                id,
+                priority,
+                source: Arc::new(source),
+            },
+        );
+        id
+    }
+
+    /// Returns false if the mount was already removed.
+    pub fn unmount(&self, id: MountId) -> bool {
+        let mut mounts = self.mounts.write();
+        let len = mounts.len();
+        mounts.retain(|mount| mount.id != id);
+        mounts.len() != len
+    }
+
+    pub fn get_mount_count(&self) -> usize {
+        self.mounts.read().len()
+    }
+
+    fn sources(&self) -> Vec<Arc<AssetSource>> {
+        self.mounts
+            .read()
+            .iter()
+            .map(|mount| mount.source.clone())
+            .collect()
+    }
+
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let normalized = normalize(path);
+        for source in self.sources() {
+            if let Some(result) = source.read(&normalized) {
+                return result;
+            }
+        }
+        std::fs::read(path)
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        let normalized = normalize(path);
+        self.sources()
+            .iter()
+            .any(|source| source.contains(&normalized))
+            || std::path::Path::new(path).is_file()
+    }
+
+    /// Modification time of the file `path` resolves to, `None` if it is packed and can't change.
+    pub fn modified(&self, path: &str) -> Option<SystemTime> {
+        let normalized = normalize(path);
+        match self
+            .sources()
+            .iter()
+            .find(|source| source.contains(&normalized))
+        {
+            Some(source) => source.modified(&normalized),
+            None => std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok(),
+        }
+    }
+}
+
+impl Default for VirtualFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Turns a path into the form mounts are searched with, forward slashes without leading slashes,
+/// `.` or `..` components, so `./textures\..\models/a.gltf` becomes `models/a.gltf`.
+pub fn normalize(path: &str) -> String {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    components.join("/")
+}
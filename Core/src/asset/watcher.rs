@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use hashbrown::HashMap;
+
+use crate::asset::manager::AssetManager;
+
+/// Polls the files behind all loaded assets and reloads the ones that changed on disk. Only the
+/// file at the asset path is watched, files it pulls in, like shader includes, are not. Files
+/// resolved from archives or embedded directories can't change and are skipped.
+pub(crate) struct AssetWatcher {
+    thread: Option<JoinHandle<()>>,
+    running: Arc<AtomicBool>,
+}
+
+impl AssetWatcher {
+    pub(crate) fn new(manager: Weak<AssetManager>, interval: Duration) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        let thread = std::thread::spawn(move || Self::watcher_thread(manager, interval, flag));
+        Self {
+            thread: Some(thread),
+            running,
+        }
+    }
+
+    fn watcher_thread(manager: Weak<AssetManager>, interval: Duration, running: Arc<AtomicBool>) {
+        let mut modified: HashMap<String, SystemTime> = HashMap::new();
+        while running.load(Ordering::Acquire) {
+            // Don't keep the manager alive while sleeping
+            let Some(manager) = manager.upgrade() else {
+                break;
+            };
+            for handle in manager.get_watched_handles() {
+                let Some(time) = manager.get_file_system().modified(handle.get_path()) else {
+                    continue;
+                };
+                match modified.insert(handle.get_path().to_string(), time) {
+                    Some(previous) if previous != time => {
+                        log::info!("Asset '{}' changed on disk, reloading", handle.get_path());
+                        handle.reload();
+                    }
+                    _ => {}
+                }
+            }
+            drop(manager);
+            std::thread::park_timeout(interval);
+        }
+    }
+}
+
+impl Drop for AssetWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            // The watcher thread can hold the last reference to the manager, in which case the
+            // manager is dropped on that thread and it can't join itself.
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use std::io;
+use std::path::Path;
+
+use crate::input::raw::{Input, State};
+use crate::input::{
+    KEY_LEFT_ALT, KEY_LEFT_CTRL, KEY_LEFT_SHIFT, KEY_RIGHT_ALT, KEY_RIGHT_CTRL, KEY_RIGHT_SHIFT,
+    MAX_KEYS, MAX_MOUSE,
+};
+
+/// A single key, mouse button or scroll direction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    /// Access with the constants KEY_...
+    Key(usize),
+    /// Access with the constants MOUSE_...
+    Mouse(usize),
+    /// Access with the constants MOUSE_SCROLL_...
+    Scroll(usize),
+}
+
+impl Trigger {
+    /// Parses the names used by [`Input::key_from_str`], [`Input::mouse_from_string`] and
+    /// [`Input::scroll_from_string`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        let key = Input::key_from_str(name);
+        if key != usize::MAX {
+            return Some(Trigger::Key(key));
+        }
+        let mouse = Input::mouse_from_string(name);
+        if mouse != usize::MAX {
+            return Some(Trigger::Mouse(mouse));
+        }
+        let scroll = Input::scroll_from_string(name);
+        (scroll != usize::MAX).then_some(Trigger::Scroll(scroll))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Trigger::Key(key) => Input::string_from_key(*key),
+            Trigger::Mouse(button) => Input::string_from_mouse(*button),
+            Trigger::Scroll(direction) => Input::string_from_scroll(*direction),
+        }
+        .to_lowercase()
+    }
+
+    fn is_held(&self, input: &Input) -> bool {
+        match *self {
+            Trigger::Key(key) => input.keys[key],
+            Trigger::Mouse(button) => input.mouse[button],
+            Trigger::Scroll(direction) => input.scroll[direction],
+        }
+    }
+
+    /// Scroll only happens for a single update, so it counts as just pressed whenever it is held.
+    fn is_just_pressed(&self, input: &Input) -> bool {
+        match *self {
+            Trigger::Key(key) => input.keystates[key] == State::JustPressed,
+            Trigger::Mouse(button) => input.mousestates[button] == State::JustPressed,
+            Trigger::Scroll(direction) => input.scroll[direction],
+        }
+    }
+
+    fn is_just_released(&self, input: &Input) -> bool {
+        match *self {
+            Trigger::Key(key) => input.keystates[key] == State::JustReleased,
+            Trigger::Mouse(button) => input.mousestates[button] == State::JustReleased,
+            Trigger::Scroll(_) => false,
+        }
+    }
+
+    /// 1 while held, scroll directions report how far they were scrolled.
+    fn value(&self, input: &Input) -> f32 {
+        match *self {
+            Trigger::Scroll(direction) => input.scrollstates[direction].abs(),
+            _ => self.is_held(input) as u8 as f32,
+        }
+    }
+}
+
+/// Modifier keys a [`Binding`] can require, either the left or the right key counts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+}
+
+impl Modifier {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ctrl" => Some(Modifier::Ctrl),
+            "shift" => Some(Modifier::Shift),
+            "alt" => Some(Modifier::Alt),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Modifier::Ctrl => "ctrl",
+            Modifier::Shift => "shift",
+            Modifier::Alt => "alt",
+        }
+    }
+
+    pub fn is_held(&self, input: &Input) -> bool {
+        let (left, right) = self.keys();
+        input.keys[left] || input.keys[right]
+    }
+
+    fn keys(&self) -> (usize, usize) {
+        match self {
+            Modifier::Ctrl => (KEY_LEFT_CTRL, KEY_RIGHT_CTRL),
+            Modifier::Shift => (KEY_LEFT_SHIFT, KEY_RIGHT_SHIFT),
+            Modifier::Alt => (KEY_LEFT_ALT, KEY_RIGHT_ALT),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    Button(Trigger),
+    /// An axis from -1 while the first trigger is held to 1 while the second one is.
+    Axis(Trigger, Trigger),
+}
+
+/// A trigger or an axis of two, which only counts while all of its modifiers are held.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Binding {
+    /// Sorted and without duplicates.
+    modifiers: Vec<Modifier>,
+    kind: BindingKind,
+}
+
+impl Binding {
+    pub fn button(trigger: Trigger) -> Self {
+        Self {
+            modifiers: Vec::new(),
+            kind: BindingKind::Button(trigger),
+        }
+    }
+
+    pub fn axis(negative: Trigger, positive: Trigger) -> Self {
+        Self {
+            modifiers: Vec::new(),
+            kind: BindingKind::Axis(negative, positive),
+        }
+    }
+
+    pub fn with_modifier(mut self, modifier: Modifier) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+            self.modifiers.sort();
+        }
+        self
+    }
+
+    pub fn get_modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn get_kind(&self) -> &BindingKind {
+        &self.kind
+    }
+
+    /// The first key, mouse button or scroll direction pressed this update, together with the modifiers
+    /// held with it, or `None` if nothing was pressed. Used to let players pick a binding by pressing it.
+    /// Modifier keys only count on their own while no other trigger is pressed.
+    pub fn capture(input: &Input) -> Option<Self> {
+        let modifiers = [Modifier::Ctrl, Modifier::Shift, Modifier::Alt];
+        let is_modifier = |key: usize| {
+            modifiers.iter().any(|modifier| {
+                let (left, right) = modifier.keys();
+                key == left || key == right
+            })
+        };
+        let keys = (0..MAX_KEYS).map(Trigger::Key);
+        let buttons = (0..MAX_MOUSE).map(Trigger::Mouse);
+        let scroll = (0..input.scroll.len()).map(Trigger::Scroll);
+        let mut pressed = keys
+            .chain(buttons)
+            .chain(scroll)
+            .filter(|trigger| trigger.is_just_pressed(input));
+        let first = pressed.next()?;
+        let trigger = std::iter::once(first)
+            .chain(pressed)
+            .find(|trigger| !matches!(trigger, Trigger::Key(key) if is_modifier(*key)));
+        let Some(trigger) = trigger else {
+            return Some(Self::button(first));
+        };
+        Some(
+            modifiers
+                .into_iter()
+                .filter(|modifier| modifier.is_held(input))
+                .fold(Self::button(trigger), Self::with_modifier),
+        )
+    }
+
+    /// Parses bindings written like `ctrl+s`, `mouse_left` or `a/d` for an axis, see [`ActionMap::parse`].
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parts = source.split('+').map(str::trim).collect::<Vec<_>>();
+        let last = parts.pop().unwrap_or_default();
+        let trigger = |name: &str| {
+            Trigger::from_name(name)
+                .ok_or_else(|| format!("Unknown input '{name}' in binding '{source}'"))
+        };
+        let binding = match last.split_once('/') {
+            Some((negative, positive)) => {
+                Self::axis(trigger(negative.trim())?, trigger(positive.trim())?)
+            }
+            None => Self::button(trigger(last)?),
+        };
+        parts.into_iter().try_fold(binding, |binding, name| {
+            Modifier::from_name(name)
+                .map(|modifier| binding.with_modifier(modifier))
+                .ok_or_else(|| format!("Unknown modifier '{name}' in binding '{source}'"))
+        })
+    }
+
+    pub fn write(&self) -> String {
+        let mut binding = self
+            .modifiers
+            .iter()
+            .map(|modifier| format!("{}+", modifier.name()))
+            .collect::<String>();
+        match &self.kind {
+            BindingKind::Button(trigger) => binding.push_str(&trigger.name()),
+            BindingKind::Axis(negative, positive) => {
+                binding.push_str(&format!("{}/{}", negative.name(), positive.name()))
+            }
+        }
+        binding
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        match self.kind {
+            BindingKind::Button(trigger) => vec![trigger],
+            BindingKind::Axis(negative, positive) => vec![negative, positive],
+        }
+    }
+
+    fn value(&self, input: &Input) -> f32 {
+        match self.kind {
+            BindingKind::Button(trigger) => trigger.value(input),
+            BindingKind::Axis(negative, positive) => positive.value(input) - negative.value(input),
+        }
+    }
+
+    fn modifiers_held(&self, input: &Input) -> bool {
+        self.modifiers
+            .iter()
+            .all(|modifier| modifier.is_held(input))
+    }
+}
+
+/// Maps named actions like "jump" or "move_x" to bindings, so games query actions instead of keys and
+/// players can rebind them. Every action can have any number of bindings, it is active while any of
+/// them is.
+///
+/// A binding with modifiers shadows the bindings of the same trigger with fewer modifiers while they
+/// are held, so `ctrl+s` doesn't trigger an action bound to `s` at the same time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionMap {
+    actions: Vec<(String, Vec<Binding>)>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a binding to the action, creating the action if it doesn't exist yet.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.bindings_mut(action);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: &Binding) -> bool {
+        let bindings = self.bindings_mut(action);
+        let count = bindings.len();
+        bindings.retain(|bound| bound != binding);
+        bindings.len() != count
+    }
+
+    /// Replaces all bindings of the action.
+    pub fn set_bindings(&mut self, action: &str, bindings: Vec<Binding>) {
+        *self.bindings_mut(action) = bindings;
+    }
+
+    pub fn get_bindings(&self, action: &str) -> &[Binding] {
+        self.actions
+            .iter()
+            .find(|(name, _)| name == action)
+            .map_or(&[], |(_, bindings)| bindings)
+    }
+
+    /// Names of all actions, in the order they were added.
+    pub fn get_actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Replaces the bindings of every action that is listed in `other`, like a config file of a player
+    /// over the defaults of the game. Actions that aren't listed keep their bindings.
+    pub fn apply(&mut self, other: &ActionMap) {
+        for (action, bindings) in &other.actions {
+            self.set_bindings(action, bindings.clone());
+        }
+    }
+
+    /// Whether any binding of the action is held. Axes count while they aren't 0.
+    pub fn is_pressed(&self, action: &str, input: &Input) -> bool {
+        self.active(action, input)
+            .any(|binding| match binding.kind {
+                BindingKind::Button(trigger) => trigger.is_held(input),
+                BindingKind::Axis(..) => binding.value(input) != 0.0,
+            })
+    }
+
+    /// Whether a binding of the action was pressed since the last update.
+    pub fn is_just_pressed(&self, action: &str, input: &Input) -> bool {
+        self.active(action, input).any(|binding| {
+            binding
+                .triggers()
+                .iter()
+                .any(|trigger| trigger.is_just_pressed(input))
+        })
+    }
+
+    /// Whether a binding of the action was released since the last update, and no other binding of it is held.
+    pub fn is_just_released(&self, action: &str, input: &Input) -> bool {
+        let released = self.get_bindings(action).iter().any(|binding| {
+            binding
+                .triggers()
+                .iter()
+                .any(|trigger| trigger.is_just_released(input))
+        });
+        released && !self.is_pressed(action, input)
+    }
+
+    /// The value of the action, the one with the largest magnitude if several bindings are active.
+    /// Buttons are 1 while held, axes go from -1 to 1, and scrolling reports how far was scrolled.
+    pub fn get_value(&self, action: &str, input: &Input) -> f32 {
+        self.active(action, input)
+            .map(|binding| binding.value(input))
+            .fold(0.0, |value: f32, other| {
+                if other.abs() > value.abs() {
+                    other
+                } else {
+                    value
+                }
+            })
+    }
+
+    /// Reads a config with one action per line, `action = binding, binding`. Bindings are a key, mouse
+    /// button or scroll direction named like [`Input::key_from_str`], `mouse_left` or `mouse_scroll_up`,
+    /// an axis of two of them as `negative/positive`, and can be prefixed with the modifiers `ctrl+`,
+    /// `shift+` and `alt+`. Lines starting with `#` are comments.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut map = Self::new();
+        for (line_number, line) in source
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+        {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (action, bindings) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Expected 'action = bindings' in line {line_number}"))?;
+            let action = action.trim();
+            if action.is_empty() {
+                return Err(format!("Missing action name in line {line_number}"));
+            }
+            let bindings = bindings
+                .split(',')
+                .map(str::trim)
+                .filter(|binding| !binding.is_empty())
+                .map(|binding| {
+                    Binding::parse(binding).map_err(|e| format!("{e} in line {line_number}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            map.set_bindings(action, bindings);
+        }
+        Ok(map)
+    }
+
+    pub fn write(&self) -> String {
+        let mut config = String::new();
+        for (action, bindings) in &self.actions {
+            let bindings = bindings
+                .iter()
+                .map(Binding::write)
+                .collect::<Vec<_>>()
+                .join(", ");
+            config.push_str(&format!("{action} = {bindings}\n"));
+        }
+        config
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::parse(&source).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.write())
+    }
+
+    fn bindings_mut(&mut self, action: &str) -> &mut Vec<Binding> {
+        let index = match self.actions.iter().position(|(name, _)| name == action) {
+            Some(index) => index,
+            None => {
+                self.actions.push((action.to_string(), Vec::new()));
+                self.actions.len() - 1
+            }
+        };
+        &mut self.actions[index].1
+    }
+
+    /// Bindings of the action whose modifiers are held and that aren't shadowed by a chord.
+    fn active<'a>(&'a self, action: &str, input: &'a Input) -> impl Iterator<Item = &'a Binding> {
+        self.get_bindings(action).iter().filter(move |binding| {
+            binding.modifiers_held(input) && !self.is_shadowed(binding, input)
+        })
+    }
+
+    /// Whether a binding with more modifiers that are all held uses one of the triggers of `binding`.
+    fn is_shadowed(&self, binding: &Binding, input: &Input) -> bool {
+        let triggers = binding.triggers();
+        self.actions
+            .iter()
+            .flat_map(|(_, bindings)| bindings)
+            .any(|other| {
+                other.modifiers.len() > binding.modifiers.len()
+                    && other.modifiers_held(input)
+                    && other
+                        .triggers()
+                        .iter()
+                        .any(|trigger| triggers.contains(trigger))
+            })
+    }
+}
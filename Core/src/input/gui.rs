@@ -0,0 +1,311 @@
+use crate::input::raw::Input;
+use crate::input::{InputProcessor, KeyboardAction, MouseAction, MAX_KEYS, MAX_MOUSE};
+use std::sync::{Arc, RwLock};
+
+pub type WidgetId = u64;
+
+/// An area of the window in pixels with the origin at the top left, like the cursor position of [`Input`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WidgetRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl WidgetRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+/// What happened to a widget, collected until the end of the next update.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GuiEvent {
+    Enter(WidgetId),
+    Leave(WidgetId),
+    Press(WidgetId, usize),
+    /// The button that pressed the widget was released, wherever the cursor is.
+    Release(WidgetId, usize),
+    /// The button was pressed and released on the widget.
+    Click(WidgetId, usize),
+    Scroll(WidgetId, f32, f32),
+    Focus(WidgetId),
+    Unfocus(WidgetId),
+    /// A key press, release or typed character while the widget has focus.
+    Key(WidgetId, KeyboardAction),
+}
+
+struct Widget {
+    id: WidgetId,
+    rect: WidgetRect,
+    depth: i32,
+    focusable: bool,
+}
+
+/// Hit-tests the cursor against the registered widgets and consumes the input meant for them, so
+/// it doesn't reach the default and custom processors.
+///
+/// Presses and scrolling over a widget are consumed, as are key events while a widget has focus.
+/// Releases are consumed if their press was. Cursor moves are never consumed, the cursor position
+/// of [`Input`] always stays up to date.
+pub struct GuiInputProcessor {
+    input: Arc<RwLock<Input>>,
+    enabled: bool,
+    widgets: Vec<Widget>,
+    cursor: Option<(i32, i32)>,
+    hovered: Option<WidgetId>,
+    focused: Option<WidgetId>,
+    captured: Option<(WidgetId, usize)>,
+    /// Keys and buttons whose press was consumed.
+    keys: [bool; MAX_KEYS],
+    buttons: [bool; MAX_MOUSE],
+    consumed: bool,
+    events: Vec<GuiEvent>,
+}
+
+impl GuiInputProcessor {
+    /// Registers a widget or moves an existing one. Widgets with a higher depth are on top, and of
+    /// the ones with the same depth the one registered last. Focusable widgets get focus when they
+    /// are pressed and receive the keyboard input until another widget or nothing is pressed.
+    pub fn set_widget(&mut self, id: WidgetId, rect: WidgetRect, depth: i32, focusable: bool) {
+        if let Some(widget) = self.widgets.iter_mut().find(|widget| widget.id == id) {
+            widget.rect = rect;
+            widget.depth = depth;
+            widget.focusable = focusable;
+        } else {
+            self.widgets.push(Widget {
+                id,
+                rect,
+                depth,
+                focusable,
+            });
+        }
+        if !focusable && self.focused == Some(id) {
+            self.set_focus(None);
+        }
+        self.update_hover();
+    }
+
+    pub fn remove_widget(&mut self, id: WidgetId) -> bool {
+        let Some(index) = self.widgets.iter().position(|widget| widget.id == id) else {
+            return false;
+        };
+        self.widgets.remove(index);
+        if self.focused == Some(id) {
+            self.set_focus(None);
+        }
+        // The release of the button is still consumed, it was pressed on the gui
+        if self.get_captured() == Some(id) {
+            self.captured = None;
+        }
+        self.update_hover();
+        true
+    }
+
+    pub fn clear_widgets(&mut self) {
+        let ids = self
+            .widgets
+            .iter()
+            .map(|widget| widget.id)
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.remove_widget(id);
+        }
+    }
+
+    /// The topmost widget at a position.
+    pub fn widget_at(&self, x: i32, y: i32) -> Option<WidgetId> {
+        self.widgets
+            .iter()
+            .filter(|widget| widget.rect.contains(x, y))
+            .max_by_key(|widget| widget.depth)
+            .map(|widget| widget.id)
+    }
+
+    pub fn get_hovered(&self) -> Option<WidgetId> {
+        self.hovered
+    }
+
+    pub fn get_focused(&self) -> Option<WidgetId> {
+        self.focused
+    }
+
+    /// The widget being pressed, which keeps receiving the release of its button even if the cursor
+    /// leaves it.
+    pub fn get_captured(&self) -> Option<WidgetId> {
+        self.captured.map(|(id, _)| id)
+    }
+
+    /// Gives a focusable widget focus or takes it away, like a chat box that opens on a key press.
+    pub fn set_focus(&mut self, id: Option<WidgetId>) {
+        let id = id.filter(|id| {
+            self.widgets
+                .iter()
+                .any(|widget| widget.id == *id && widget.focusable)
+        });
+        if id == self.focused {
+            return;
+        }
+        if let Some(old) = self.focused {
+            self.events.push(GuiEvent::Unfocus(old));
+        }
+        if let Some(new) = id {
+            self.events.push(GuiEvent::Focus(new));
+        }
+        self.focused = id;
+    }
+
+    /// Whether the last event was consumed and not passed on to the other processors.
+    pub fn is_consumed(&self) -> bool {
+        self.consumed
+    }
+
+    pub fn take_events(&mut self) -> Vec<GuiEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub(crate) fn end_tick(&mut self) {
+        self.events.clear();
+    }
+
+    pub(crate) fn cursor_left(&mut self) {
+        self.cursor = None;
+        self.update_hover();
+    }
+
+    /// Forgets the held keys and buttons, their releases never arrive while the window is unfocused.
+    pub(crate) fn release_all(&mut self) {
+        if let Some((id, button)) = self.captured.take() {
+            self.events.push(GuiEvent::Release(id, button));
+        }
+        self.keys = [false; MAX_KEYS];
+        self.buttons = [false; MAX_MOUSE];
+    }
+
+    fn update_hover(&mut self) {
+        let hovered = self.cursor.and_then(|(x, y)| self.widget_at(x, y));
+        if hovered == self.hovered {
+            return;
+        }
+        if let Some(old) = self.hovered {
+            self.events.push(GuiEvent::Leave(old));
+        }
+        if let Some(new) = hovered {
+            self.events.push(GuiEvent::Enter(new));
+        }
+        self.hovered = hovered;
+    }
+}
+
+impl InputProcessor for GuiInputProcessor {
+    fn new(input: Arc<RwLock<Input>>) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            input,
+            enabled: true,
+            widgets: Vec::new(),
+            cursor: None,
+            hovered: None,
+            focused: None,
+            captured: None,
+            keys: [false; MAX_KEYS],
+            buttons: [false; MAX_MOUSE],
+            consumed: false,
+            events: Vec::new(),
+        }
+    }
+
+    fn input(&self) -> Arc<RwLock<Input>> {
+        self.input.clone()
+    }
+
+    fn mouse_change(&mut self, action: MouseAction) {
+        self.consumed = false;
+        match action {
+            MouseAction::Move(x, y) => {
+                self.cursor = Some((x, y));
+                self.update_hover();
+            }
+            MouseAction::Press(button) => {
+                // Pressing outside of the gui takes the focus away
+                let Some(id) = self.hovered else {
+                    self.set_focus(None);
+                    return;
+                };
+                self.set_focus(Some(id));
+                if self.captured.is_none() {
+                    self.captured = Some((id, button));
+                }
+                self.buttons[button] = true;
+                self.events.push(GuiEvent::Press(id, button));
+                self.consumed = true;
+            }
+            MouseAction::Release(button) => {
+                if !self.buttons[button] {
+                    return;
+                }
+                self.buttons[button] = false;
+                self.consumed = true;
+                if let Some((id, captured)) = self.captured {
+                    if captured == button {
+                        self.captured = None;
+                        self.events.push(GuiEvent::Release(id, button));
+                        if self.hovered == Some(id) {
+                            self.events.push(GuiEvent::Click(id, button));
+                        }
+                    }
+                }
+            }
+            MouseAction::Wheel(x, y) => {
+                if let Some(id) = self.hovered {
+                    self.events.push(GuiEvent::Scroll(id, x, y));
+                    self.consumed = true;
+                }
+            }
+        }
+    }
+
+    fn keyboard_change(&mut self, action: KeyboardAction) {
+        self.consumed = false;
+        match action {
+            KeyboardAction::Press(key) => {
+                if self.focused.is_some() {
+                    self.keys[key] = true;
+                }
+            }
+            // Keys that were pressed before a widget got focus are released in the game world
+            KeyboardAction::Release(key) => {
+                if !self.keys[key] {
+                    return;
+                }
+                self.keys[key] = false;
+                self.consumed = true;
+            }
+            KeyboardAction::Type(_) => {}
+        }
+        if let Some(id) = self.focused {
+            self.events.push(GuiEvent::Key(id, action));
+            self.consumed = true;
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
@@ -1,22 +1,35 @@
+use crate::input::gui::GuiInputProcessor;
 use crate::input::raw::Input;
 use crate::input::InputAction::{Keyboard, Mouse};
 use mvutils::utils::Recover;
 use std::sync::{Arc, RwLock};
+use winit::event::{ElementState, Ime, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{ModifiersState, PhysicalKey};
 
 pub use consts::*;
 pub use raw::State;
 
+pub mod action;
 mod consts;
+pub mod gui;
 pub mod raw;
 
+/// Touchpads scroll in pixels, they are converted to lines of this many pixels like mouse wheels report.
+const PIXELS_PER_LINE: f64 = 20.0;
+
 pub struct InputCollector {
     default_processor: InputProcessorImpl,
     gui_processor: GuiInputProcessor,
     custom_processor: Option<Arc<RwLock<Box<dyn InputProcessor>>>>,
+    modifiers: ModifiersState,
 }
 
 impl InputCollector {
-    pub(crate) fn new(input: Arc<RwLock<Input>>) -> Self
+    /// Every window has its own collector fed with its events, see [`Window::get_input_collector`].
+    /// Creating one is only needed to drive the processors without a window.
+    ///
+    /// [`Window::get_input_collector`]: crate::render::window::Window::get_input_collector
+    pub fn new(input: Arc<RwLock<Input>>) -> Self
     where
         Self: Sized,
     {
@@ -24,6 +37,7 @@ impl InputCollector {
             default_processor: InputProcessorImpl::new(input.clone()),
             gui_processor: GuiInputProcessor::new(input),
             custom_processor: None,
+            modifiers: ModifiersState::empty(),
         }
     }
 
@@ -31,6 +45,11 @@ impl InputCollector {
         self.default_processor.input()
     }
 
+    /// The processor for the widgets of the gui, input it consumes doesn't reach the other processors.
+    pub fn get_gui_processor(&mut self) -> &mut GuiInputProcessor {
+        &mut self.gui_processor
+    }
+
     pub fn set_custom_processor(
         &mut self,
         custom_processor: Option<Arc<RwLock<Box<dyn InputProcessor>>>>,
@@ -38,7 +57,153 @@ impl InputCollector {
         self.custom_processor = custom_processor;
     }
 
+    /// Translates a window event into input actions, events that aren't input are ignored.
+    pub(crate) fn collect_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let key = match event.physical_key {
+                    PhysicalKey::Code(code) => Some(Input::key_from_winit(code)),
+                    PhysicalKey::Unidentified(_) => None,
+                };
+                self.collect_key(key, event.state, event.repeat, event.text.as_deref());
+            }
+            WindowEvent::ModifiersChanged(modifiers) => self.collect_modifiers(modifiers.state()),
+            WindowEvent::Ime(ime) => self.collect_ime(ime),
+            WindowEvent::CursorMoved { position, .. } => {
+                self.collect(Mouse(MouseAction::Move(position.x as i32, position.y as i32)));
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+                    MouseScrollDelta::PixelDelta(delta) => (
+                        (delta.x / PIXELS_PER_LINE) as f32,
+                        (delta.y / PIXELS_PER_LINE) as f32,
+                    ),
+                };
+                self.collect(Mouse(MouseAction::Wheel(x, y)));
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = Input::mouse_from_winit(*button);
+                if button >= MAX_MOUSE {
+                    return;
+                }
+                self.collect(Mouse(match state {
+                    ElementState::Pressed => MouseAction::Press(button),
+                    ElementState::Released => MouseAction::Release(button),
+                }));
+            }
+            WindowEvent::CursorLeft { .. } => self.gui_processor.cursor_left(),
+            // Releases that happen while another window has focus never arrive
+            WindowEvent::Focused(false) => self.release_all(),
+            _ => {}
+        }
+    }
+
+    /// Translates a key event of the window, split out because winit's key events can't be created
+    /// outside of winit. `key` is `None` if the physical key is unknown, `text` is what the key types.
+    pub fn collect_key(
+        &mut self,
+        key: Option<usize>,
+        state: ElementState,
+        repeat: bool,
+        text: Option<&str>,
+    ) {
+        // Held keys repeat their press, they stay pressed instead
+        if !repeat {
+            if let Some(key) = key.filter(|key| *key < MAX_KEYS) {
+                self.collect(Keyboard(match state {
+                    ElementState::Pressed => KeyboardAction::Press(key),
+                    ElementState::Released => KeyboardAction::Release(key),
+                }));
+            }
+        }
+        // Repeats type their text again, shortcuts like ctrl+c don't type at all
+        if state == ElementState::Pressed && !self.is_shortcut() {
+            if let Some(text) = text {
+                self.type_text(text);
+            }
+        }
+    }
+
+    pub fn collect_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn collect_ime(&mut self, ime: &Ime) {
+        match ime {
+            Ime::Enabled => self.get_input().write().recover().ime = true,
+            Ime::Preedit(text, cursor) => {
+                let input = self.get_input();
+                let mut input = input.write().recover();
+                input.preedit.clone_from(text);
+                input.preedit_cursor = *cursor;
+            }
+            Ime::Commit(text) => self.type_text(text),
+            Ime::Disabled => self.ime_disabled(),
+        }
+    }
+
+    /// Turns the `JustPressed` and `JustReleased` states into `Pressed` and `Released` and clears the
+    /// scroll, the typed text and the gui events. Windows call it once per update tick after the
+    /// update.
+    pub fn end_tick(&mut self) {
+        self.get_input().write().recover().loop_states();
+        self.gui_processor.end_tick();
+    }
+
+    /// Clears what the input method was composing, it is never committed.
+    pub(crate) fn ime_disabled(&mut self) {
+        let input = self.get_input();
+        let mut input = input.write().recover();
+        input.ime = false;
+        input.preedit.clear();
+        input.preedit_cursor = None;
+    }
+
+    /// Control characters like backspace or enter are only reported as key presses.
+    fn type_text(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.collect(Keyboard(KeyboardAction::Type(c)));
+        }
+    }
+
+    /// AltGr is reported as ctrl and alt on some platforms, it types the characters of its keys.
+    fn is_shortcut(&self) -> bool {
+        (self.modifiers.control_key() && !self.modifiers.alt_key()) || self.modifiers.super_key()
+    }
+
+    fn release_all(&mut self) {
+        self.modifiers = ModifiersState::empty();
+        self.gui_processor.release_all();
+        let input = self.get_input();
+        let (keys, buttons) = {
+            let input = input.read().recover();
+            let keys = (0..MAX_KEYS)
+                .filter(|key| input.keys[*key] && input.keystates[*key] != State::JustReleased)
+                .collect::<Vec<_>>();
+            let buttons = (0..MAX_MOUSE)
+                .filter(|button| input.mouse[*button] && input.mousestates[*button] != State::JustReleased)
+                .collect::<Vec<_>>();
+            (keys, buttons)
+        };
+        for key in keys {
+            self.collect(Keyboard(KeyboardAction::Release(key)));
+        }
+        for button in buttons {
+            self.collect(Mouse(MouseAction::Release(button)));
+        }
+    }
+
     pub(crate) fn collect(&mut self, action: InputAction) {
+        if self.gui_processor.is_enabled() {
+            match action {
+                Keyboard(ka) => self.gui_processor.keyboard_change(ka),
+                Mouse(ma) => self.gui_processor.mouse_change(ma),
+            }
+            if self.gui_processor.is_consumed() {
+                return;
+            }
+        }
         if let Keyboard(ka) = action {
             if self.default_processor.is_enabled() {
                 self.default_processor.keyboard_change(ka);
@@ -70,14 +235,15 @@ pub(crate) enum InputAction {
     Mouse(MouseAction),
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum KeyboardAction {
     Press(usize),
     Release(usize),
-    Type(usize),
+    /// A character typed on the keyboard or committed by the input method.
+    Type(char),
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MouseAction {
     Wheel(f32, f32),
     Move(i32, i32),
@@ -136,19 +302,19 @@ impl InputProcessor for InputProcessorImpl {
         if let MouseAction::Wheel(x, y) = action {
             if y > 0.0 {
                 input.scroll[MOUSE_SCROLL_UP] = true;
-                input.scrollstates[MOUSE_SCROLL_UP] = y;
+                input.scrollstates[MOUSE_SCROLL_UP] += y;
             }
             if y < 0.0 {
                 input.scroll[MOUSE_SCROLL_DOWN] = true;
-                input.scrollstates[MOUSE_SCROLL_DOWN] = y;
+                input.scrollstates[MOUSE_SCROLL_DOWN] += y;
             }
             if x > 0.0 {
                 input.scroll[MOUSE_SCROLL_RIGHT] = true;
-                input.scrollstates[MOUSE_SCROLL_RIGHT] = x;
+                input.scrollstates[MOUSE_SCROLL_RIGHT] += x;
             }
             if x < 0.0 {
                 input.scroll[MOUSE_SCROLL_LEFT] = true;
-                input.scrollstates[MOUSE_SCROLL_LEFT] = x;
+                input.scrollstates[MOUSE_SCROLL_LEFT] += x;
             }
         }
 
@@ -167,45 +333,11 @@ impl InputProcessor for InputProcessorImpl {
         if let KeyboardAction::Release(key) = action {
             input.keystates[key] = State::JustReleased;
         }
-    }
-
-    fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
-    }
-
-    fn is_enabled(&self) -> bool {
-        self.enabled
-    }
-}
-
-pub(crate) struct GuiInputProcessor {
-    input: Arc<RwLock<Input>>,
-    enabled: bool,
-}
-
-impl InputProcessor for GuiInputProcessor {
-    fn new(input: Arc<RwLock<Input>>) -> Self
-    where
-        Self: Sized,
-    {
-        Self {
-            input,
-            enabled: true,
+        if let KeyboardAction::Type(c) = action {
+            input.text.push(c);
         }
     }
 
-    fn input(&self) -> Arc<RwLock<Input>> {
-        todo!()
-    }
-
-    fn mouse_change(&mut self, action: MouseAction) {
-        todo!()
-    }
-
-    fn keyboard_change(&mut self, action: KeyboardAction) {
-        todo!()
-    }
-
     fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
     }
@@ -25,10 +25,24 @@ pub struct Input {
     pub scrollstates: [f32; 4],
     ///Both mouse x and y position, access with MOUSE_POS_X or MOUSE_POS_Y or simply 0 or 1.
     pub positions: [i32; 2],
+    ///The text typed since the last update, from the keyboard layout or committed by the input method.
+    pub text: String,
+    ///The text the input method is composing, which is not typed yet. Empty if nothing is composed.
+    pub preedit: String,
+    ///The selected byte range of the preedit, or the cursor if both are the same. None if the cursor should be hidden.
+    pub preedit_cursor: Option<(usize, usize)>,
+    ///Whether the input method is active, it only is while it is enabled on the window.
+    pub ime: bool,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Input {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             keys: [false; MAX_KEYS],
             keystates: [State::Released; MAX_KEYS],
@@ -37,6 +51,10 @@ impl Input {
             scroll: [false; 4],
             scrollstates: [0.0; 4],
             positions: [0, 0],
+            text: String::new(),
+            preedit: String::new(),
+            preedit_cursor: None,
+            ime: false,
         }
     }
 
@@ -63,6 +81,7 @@ impl Input {
             self.scroll[i] = false;
             self.scrollstates[i] = 0.0;
         }
+        self.text.clear();
     }
 
     pub fn key_from_str(s: &str) -> usize {
@@ -0,0 +1,698 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use image::{ColorType, DynamicImage, RgbaImage};
+
+use crate::asset::asset::{AssetType, InnerAsset};
+use crate::asset::cook;
+use crate::asset::importer::AssetLoader;
+use crate::asset::manager::{AssetManager, Handle};
+use crate::render::backend::image::ColorSpace;
+use crate::render::backend::Extent2D;
+use crate::render::texture::{Texture, TextureRegion};
+
+/// Extension of atlas source files, which list the images packed into an atlas, see [`AtlasBuilder::from_source`].
+pub const ATLAS_SOURCE_EXTENSION: &str = "atlas";
+
+/// A rectangle in pixels, with its origin in the top left corner of the page.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRect {
+    fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    fn contains(&self, other: &AtlasRect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    fn intersects(&self, other: &AtlasRect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+}
+
+/// Packs rectangles into a bin with the MaxRects algorithm, every rectangle goes into the free space
+/// that leaves the shortest side over. Inserted rectangles can't be freed again.
+#[derive(Clone, Debug)]
+pub struct AtlasPacker {
+    width: u32,
+    height: u32,
+    /// Maximal free rectangles, they overlap each other.
+    free: Vec<AtlasRect>,
+}
+
+impl AtlasPacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            free: vec![AtlasRect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Reserves space for a `width` x `height` rectangle, returns `None` if it doesn't fit.
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<AtlasRect> {
+        let rect = self
+            .free
+            .iter()
+            .filter(|free| free.width >= width && free.height >= height)
+            .min_by_key(|free| {
+                let (left_x, left_y) = (free.width - width, free.height - height);
+                (left_x.min(left_y), left_x.max(left_y))
+            })
+            .map(|free| AtlasRect {
+                x: free.x,
+                y: free.y,
+                width,
+                height,
+            })?;
+        self.place(rect);
+        Some(rect)
+    }
+
+    /// Enlarges the bin, rectangles that were already inserted keep their place.
+    pub fn grow(&mut self, width: u32, height: u32) {
+        if width < self.width || height < self.height {
+            log::error!(
+                "Atlas packer can't shrink from {}x{} to {width}x{height}",
+                self.width,
+                self.height
+            );
+            panic!();
+        }
+        for free in &mut self.free {
+            if free.right() == self.width {
+                free.width = width - free.x;
+            }
+            if free.bottom() == self.height {
+                free.height = height - free.y;
+            }
+        }
+        if width > self.width {
+            self.free.push(AtlasRect {
+                x: self.width,
+                y: 0,
+                width: width - self.width,
+                height,
+            });
+        }
+        if height > self.height {
+            self.free.push(AtlasRect {
+                x: 0,
+                y: self.height,
+                width,
+                height: height - self.height,
+            });
+        }
+        self.width = width;
+        self.height = height;
+        self.prune();
+    }
+
+    /// Splits every free rectangle overlapping `used` into the parts around it.
+    fn place(&mut self, used: AtlasRect) {
+        let mut split = Vec::new();
+        self.free.retain(|free| {
+            if !free.intersects(&used) {
+                return true;
+            }
+            if used.x > free.x {
+                split.push(AtlasRect {
+                    width: used.x - free.x,
+                    ..*free
+                });
+            }
+            if used.right() < free.right() {
+                split.push(AtlasRect {
+                    x: used.right(),
+                    width: free.right() - used.right(),
+                    ..*free
+                });
+            }
+            if used.y > free.y {
+                split.push(AtlasRect {
+                    height: used.y - free.y,
+                    ..*free
+                });
+            }
+            if used.bottom() < free.bottom() {
+                split.push(AtlasRect {
+                    y: used.bottom(),
+                    height: free.bottom() - used.bottom(),
+                    ..*free
+                });
+            }
+            false
+        });
+        self.free.extend(split);
+        self.prune();
+    }
+
+    /// Removes free rectangles that lie within another one, of equal ones the first is kept.
+    fn prune(&mut self) {
+        let mut index = 0;
+        while index < self.free.len() {
+            let rect = self.free[index];
+            let redundant = self.free.iter().enumerate().any(|(other, free)| {
+                other != index && free.contains(&rect) && (*free != rect || other < index)
+            });
+            if redundant {
+                self.free.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+/// How sprites are laid out on the pages of an atlas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AtlasOptions {
+    /// Pixels left transparent between sprites and around the border of the page.
+    pub padding: u32,
+    /// Number of times the border pixels of a sprite are repeated around it, so filtering at its edges
+    /// never picks up the padding or neighbouring sprites.
+    pub extrude: u32,
+    /// Size of new pages, they are doubled up to `max_page_size` before another page is started.
+    pub page_size: u32,
+    pub max_page_size: u32,
+    pub color_space: ColorSpace,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self {
+            padding: 2,
+            extrude: 1,
+            page_size: 256,
+            max_page_size: 4096,
+            color_space: ColorSpace::Srgb,
+        }
+    }
+}
+
+struct Sprite {
+    image: RgbaImage,
+    page: usize,
+    rect: AtlasRect,
+}
+
+/// Packs images into atlas pages on the cpu. It needs no device, so atlases can be built offline,
+/// the [`Cooker`](crate::asset::cook::Cooker) does this for atlas source files.
+pub struct AtlasBuilder {
+    options: AtlasOptions,
+    pages: Vec<AtlasPacker>,
+    dirty: Vec<bool>,
+    sprites: HashMap<String, Sprite>,
+}
+
+impl AtlasBuilder {
+    pub fn new(options: AtlasOptions) -> Self {
+        Self {
+            options,
+            pages: Vec::new(),
+            dirty: Vec::new(),
+            sprites: HashMap::new(),
+        }
+    }
+
+    /// Packs the images listed in an atlas source file, `read` reads files relative to the source.
+    /// Every line names an image relative to the source file, which is also the name of its sprite.
+    /// Lines can set the options instead, as `padding`, `extrude`, `page_size` or `max_page_size`
+    /// followed by a number, or `color_space` followed by `srgb` or `linear`. Lines starting with
+    /// `#` are comments.
+    pub fn from_source(
+        path: &str,
+        source: &str,
+        read: impl Fn(&str) -> io::Result<Vec<u8>>,
+    ) -> Result<Self, String> {
+        let mut options = AtlasOptions::default();
+        let mut files = Vec::new();
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+            let number = || {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid {key} '{value}' in atlas {path}"))
+            };
+            match key {
+                "padding" => options.padding = number()?,
+                "extrude" => options.extrude = number()?,
+                "page_size" => options.page_size = number()?,
+                "max_page_size" => options.max_page_size = number()?,
+                "color_space" => {
+                    options.color_space = match value {
+                        "srgb" => ColorSpace::Srgb,
+                        "linear" => ColorSpace::Linear,
+                        _ => return Err(format!("Unknown color space '{value}' in atlas {path}")),
+                    }
+                }
+                _ => files.push(line),
+            }
+        }
+
+        let base = Path::new(path).parent().unwrap_or_else(|| Path::new("./"));
+        let mut builder = Self::new(options);
+        for sprite in files {
+            let file = base
+                .join(sprite)
+                .to_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("Invalid sprite {sprite} of atlas {path}"))?;
+            let data = read(&file)
+                .map_err(|e| format!("Failed to read sprite {sprite} of atlas {path}: {e}"))?;
+            let image = AssetLoader::decode_image(&file, &data)
+                .map_err(|e| format!("Failed to import sprite {sprite} of atlas {path}: {e}"))?;
+            builder.add(sprite, image)?;
+        }
+        builder.repack();
+        Ok(builder)
+    }
+
+    pub fn get_options(&self) -> &AtlasOptions {
+        &self.options
+    }
+
+    /// Packs `image` as the sprite `name`, replacing the previous sprite of that name. It goes onto the
+    /// first page with enough space, pages that are too full are grown first, a new page is only
+    /// started if none of them can fit it.
+    pub fn add(&mut self, name: &str, image: DynamicImage) -> Result<(), String> {
+        let image = image.to_rgba8();
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(format!("Sprite {name} is empty"));
+        }
+        let (cell_width, cell_height) = self.cell_size(width, height);
+        let max = self
+            .options
+            .max_page_size
+            .saturating_sub(self.options.padding);
+        if cell_width > max || cell_height > max {
+            return Err(format!(
+                "Sprite {name} of {width}x{height} doesn't fit into an atlas page of {0}x{0}",
+                self.options.max_page_size
+            ));
+        }
+
+        if let Some(sprite) = self.sprites.get_mut(name) {
+            if sprite.image.dimensions() == image.dimensions() {
+                sprite.image = image;
+                self.dirty[sprite.page] = true;
+                return Ok(());
+            }
+        }
+        self.remove(name);
+        let (page, rect) = self.place(width, height);
+        self.sprites
+            .insert(name.to_string(), Sprite { image, page, rect });
+        Ok(())
+    }
+
+    /// Removes the sprite, the space it took up is only reused after the next [`AtlasBuilder::repack`].
+    pub fn remove(&mut self, name: &str) -> bool {
+        let Some(sprite) = self.sprites.remove(name) else {
+            return false;
+        };
+        self.dirty[sprite.page] = true;
+        true
+    }
+
+    /// Packs all sprites again from scratch, tallest first, which usually needs fewer and smaller pages
+    /// than adding them one after another. Every page changes.
+    pub fn repack(&mut self) {
+        self.pages.clear();
+        self.dirty.clear();
+        let mut names = self.sprites.keys().cloned().collect::<Vec<_>>();
+        names.sort_by(|a, b| {
+            let (a_width, a_height) = self.sprites[a].image.dimensions();
+            let (b_width, b_height) = self.sprites[b].image.dimensions();
+            (b_height, b_width)
+                .cmp(&(a_height, a_width))
+                .then_with(|| a.cmp(b))
+        });
+        for name in names {
+            let (width, height) = self.sprites[&name].image.dimensions();
+            let (page, rect) = self.place(width, height);
+            let sprite = self.sprites.get_mut(&name).unwrap();
+            sprite.page = page;
+            sprite.rect = rect;
+        }
+    }
+
+    /// The page of the sprite and where it is on it, without the extruded border.
+    pub fn get_rect(&self, name: &str) -> Option<(usize, AtlasRect)> {
+        self.sprites
+            .get(name)
+            .map(|sprite| (sprite.page, sprite.rect))
+    }
+
+    /// All sprites with their page and rect, sorted by name.
+    pub fn get_sprites(&self) -> Vec<(&str, usize, AtlasRect)> {
+        let mut sprites = self
+            .sprites
+            .iter()
+            .map(|(name, sprite)| (name.as_str(), sprite.page, sprite.rect))
+            .collect::<Vec<_>>();
+        sprites.sort_by(|a, b| a.0.cmp(b.0));
+        sprites
+    }
+
+    pub fn get_page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn get_page_size(&self, page: usize) -> Extent2D {
+        let packer = &self.pages[page];
+        Extent2D {
+            width: packer.get_width() + self.options.padding,
+            height: packer.get_height() + self.options.padding,
+        }
+    }
+
+    /// Draws the sprites of `page` with their extruded borders, everything else stays transparent.
+    pub fn render_page(&self, page: usize) -> RgbaImage {
+        let size = self.get_page_size(page);
+        let mut image = RgbaImage::new(size.width, size.height);
+        let extrude = self.options.extrude as i64;
+        for sprite in self.sprites.values().filter(|sprite| sprite.page == page) {
+            let (width, height) = sprite.image.dimensions();
+            for y in -extrude..height as i64 + extrude {
+                for x in -extrude..width as i64 + extrude {
+                    let source = sprite.image.get_pixel(
+                        x.clamp(0, width as i64 - 1) as u32,
+                        y.clamp(0, height as i64 - 1) as u32,
+                    );
+                    image.put_pixel(
+                        (sprite.rect.x as i64 + x) as u32,
+                        (sprite.rect.y as i64 + y) as u32,
+                        *source,
+                    );
+                }
+            }
+        }
+        image
+    }
+
+    /// Pages that changed since the last call, in ascending order.
+    pub(crate) fn take_dirty_pages(&mut self) -> Vec<usize> {
+        self.dirty
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(page, dirty)| std::mem::take(dirty).then_some(page))
+            .collect()
+    }
+
+    /// Every sprite takes up its size with the extruded border on all sides and the padding on the
+    /// top and left, the packers leave out the padding on the bottom and right of the page.
+    fn cell_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let border = 2 * self.options.extrude + self.options.padding;
+        (width + border, height + border)
+    }
+
+    /// Finds a place for a sprite that is known to fit into an empty page of the maximum size.
+    fn place(&mut self, width: u32, height: u32) -> (usize, AtlasRect) {
+        let cell = self.cell_size(width, height);
+        let padding = self.options.padding;
+        let max = self.options.max_page_size.saturating_sub(padding);
+        let existing = (0..self.pages.len()).find_map(|page| {
+            Self::fit(&mut self.pages[page], cell, padding, max).map(|cell| (page, cell))
+        });
+        let (page, cell) = existing.unwrap_or_else(|| {
+            let size = self.options.page_size.saturating_sub(padding).clamp(1, max);
+            let mut packer = AtlasPacker::new(size, size);
+            let Some(cell) = Self::fit(&mut packer, cell, padding, max) else {
+                log::error!("Sprite of {width}x{height} doesn't fit into an empty atlas page");
+                panic!();
+            };
+            self.pages.push(packer);
+            self.dirty.push(true);
+            (self.pages.len() - 1, cell)
+        });
+        self.dirty[page] = true;
+        let offset = self.options.padding + self.options.extrude;
+        (
+            page,
+            AtlasRect {
+                x: cell.x + offset,
+                y: cell.y + offset,
+                width,
+                height,
+            },
+        )
+    }
+
+    /// Inserts a cell into `packer`, doubling the shorter side of its page until it fits or both
+    /// reached `max`. Packers are smaller than their page by the padding.
+    fn fit(
+        packer: &mut AtlasPacker,
+        (width, height): (u32, u32),
+        padding: u32,
+        max: u32,
+    ) -> Option<AtlasRect> {
+        if let Some(cell) = packer.insert(width, height) {
+            return Some(cell);
+        }
+        let mut grown = packer.clone();
+        let double = |size: u32| ((size + padding) * 2).min(max + padding) - padding;
+        while grown.get_width() < max || grown.get_height() < max {
+            let (grown_width, grown_height) = (grown.get_width(), grown.get_height());
+            if (grown_width <= grown_height && grown_width < max) || grown_height >= max {
+                grown.grow(double(grown_width), grown_height);
+            } else {
+                grown.grow(grown_width, double(grown_height));
+            }
+            if let Some(cell) = grown.insert(width, height) {
+                *packer = grown;
+                return Some(cell);
+            }
+        }
+        None
+    }
+}
+
+/// An atlas packed at runtime, which hands out a [`TextureRegion`] for each of its sprites. Its pages
+/// are registered with the asset manager as loaded textures named `<name>#page<index>`, so they are
+/// used like any other texture, and are replaced like a reload whenever they change.
+///
+/// Atlases loaded from a cooked atlas keep its pages as they are, sprites added later go onto pages
+/// after them.
+pub struct TextureAtlas {
+    manager: Arc<AssetManager>,
+    name: String,
+    builder: AtlasBuilder,
+    cooked_pages: Vec<Handle<Texture>>,
+    cooked_sprites: HashMap<String, (usize, AtlasRect)>,
+    pages: Vec<Handle<Texture>>,
+}
+
+impl TextureAtlas {
+    pub fn new(manager: &Arc<AssetManager>, name: &str, options: AtlasOptions) -> Self {
+        Self::with_builder(manager, name, AtlasBuilder::new(options))
+    }
+
+    fn with_builder(manager: &Arc<AssetManager>, name: &str, builder: AtlasBuilder) -> Self {
+        Self {
+            manager: manager.clone(),
+            name: name.to_string(),
+            builder,
+            cooked_pages: Vec::new(),
+            cooked_sprites: HashMap::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    /// Loads the atlas source file at `path`, see [`AtlasBuilder::from_source`], and uploads it. If a
+    /// cooked version of it is listed in the cooked manifest of the manager, its pages are loaded in
+    /// the background instead of packing the images, [`TextureAtlas::is_loaded`] tells when they are in.
+    pub fn load(manager: &Arc<AssetManager>, path: &str) -> Result<Self, String> {
+        let vfs = manager.get_file_system();
+        let Some(cooked) = manager.get_cooked_path(path) else {
+            let source = vfs
+                .read_to_string(path)
+                .map_err(|e| format!("Failed to read atlas {path}: {e}"))?;
+            let builder = AtlasBuilder::from_source(path, &source, |file| vfs.read(file))?;
+            let mut atlas = Self::with_builder(manager, path, builder);
+            atlas.upload();
+            return Ok(atlas);
+        };
+
+        let data = vfs
+            .read(&cooked)
+            .map_err(|e| format!("Failed to read cooked atlas {cooked}: {e}"))?;
+        let layout = cook::read_atlas(&data)
+            .map_err(|e| format!("Failed to read cooked atlas {cooked}: {e}"))?;
+        let base = Path::new(&cooked)
+            .parent()
+            .unwrap_or_else(|| Path::new("./"));
+        let mut atlas = Self::new(manager, path, layout.options);
+        for page in &layout.pages {
+            let page = base
+                .join(page)
+                .to_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("Invalid page {page} of cooked atlas {cooked}"))?;
+            manager.set_color_space(&page, layout.options.color_space);
+            let handle = manager.create::<Texture>(&page);
+            handle.load();
+            atlas.cooked_pages.push(handle);
+        }
+        atlas.cooked_sprites = layout
+            .sprites
+            .into_iter()
+            .map(|(name, page, rect)| (name, (page, rect)))
+            .collect();
+        Ok(atlas)
+    }
+
+    /// Packs `image` as the sprite `name`, see [`AtlasBuilder::add`]. The sprite can be used after
+    /// the next [`TextureAtlas::upload`].
+    pub fn add(&mut self, name: &str, image: DynamicImage) -> Result<(), String> {
+        self.builder.add(name, image)?;
+        self.cooked_sprites.remove(name);
+        Ok(())
+    }
+
+    /// Reads the pixels of a loaded texture back and packs them as the sprite `name`. This blocks until
+    /// the gpu copied the texture, so prefer [`TextureAtlas::add`] with the source image.
+    pub fn add_texture(&mut self, name: &str, texture: &Texture) -> Result<(), String> {
+        let image = texture
+            .image()
+            .read_pixels()
+            .to_dynamic_image()
+            .ok_or_else(|| {
+                format!("Texture of sprite {name} has a format that can't be read back")
+            })?;
+        self.add(name, image)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.builder.remove(name) | self.cooked_sprites.remove(name).is_some()
+    }
+
+    /// Packs all sprites added at runtime again, see [`AtlasBuilder::repack`]. Every page changes
+    /// with the next [`TextureAtlas::upload`], so regions have to be fetched again after it.
+    pub fn repack(&mut self) {
+        self.builder.repack();
+    }
+
+    /// Uploads the pages that changed since the last upload. Changed pages replace the textures of
+    /// their assets, the old textures stay alive until no frame in flight can use them anymore, and
+    /// subscribers of [`AssetManager::subscribe_reloads`] are notified.
+    pub fn upload(&mut self) {
+        let count = self.builder.get_page_count();
+        for handle in self.pages.drain(count.min(self.pages.len())..) {
+            handle.unload();
+        }
+
+        let loader = self.manager.get_loader();
+        for page in self.builder.take_dirty_pages() {
+            let image = self.builder.render_page(page);
+            let label = format!("{}#page{}", self.name, self.cooked_pages.len() + page);
+            let texture = loader.create_texture_from_data(
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+                self.builder.get_options().color_space,
+                image.into_raw(),
+                &label,
+            );
+            let handle = self.manager.create_loaded_asset(
+                &label,
+                AssetType::Texture,
+                InnerAsset::Loaded(Arc::new(texture)),
+            );
+            if page < self.pages.len() {
+                self.manager.notify_reloaded(&handle);
+                continue;
+            }
+            let Some(handle) = handle.typed::<Texture>() else {
+                log::error!("Atlas page {label} is already registered as another asset type");
+                panic!();
+            };
+            handle.load();
+            self.pages.push(handle);
+        }
+    }
+
+    /// The region of the sprite on its page, `None` if there is no such sprite or its page isn't
+    /// uploaded or loaded yet. Regions belong to the current texture of the page, fetch them again
+    /// after the page changed.
+    pub fn get_region(&self, name: &str) -> Option<TextureRegion> {
+        let (page, rect) = self.get_rect(name)?;
+        let texture = self.get_page(page)?.get()?;
+        Some(texture.as_region(rect.x, rect.y, rect.width, rect.height))
+    }
+
+    /// The page of the sprite and where it is on it, in pixels from the top left corner of the page.
+    pub fn get_rect(&self, name: &str) -> Option<(usize, AtlasRect)> {
+        self.builder
+            .get_rect(name)
+            .map(|(page, rect)| (self.cooked_pages.len() + page, rect))
+            .or_else(|| self.cooked_sprites.get(name).copied())
+    }
+
+    /// The texture of a page, pages of a cooked atlas come first.
+    pub fn get_page(&self, page: usize) -> Option<Handle<Texture>> {
+        match page.checked_sub(self.cooked_pages.len()) {
+            Some(page) => self.pages.get(page).cloned(),
+            None => Some(self.cooked_pages[page].clone()),
+        }
+    }
+
+    /// Number of uploaded and cooked pages.
+    pub fn get_page_count(&self) -> usize {
+        self.cooked_pages.len() + self.pages.len()
+    }
+
+    /// Whether the pages of a cooked atlas finished loading, atlases packed at runtime always are.
+    pub fn is_loaded(&self) -> bool {
+        self.cooked_pages.iter().all(Handle::is_loaded)
+    }
+
+    pub fn get_builder(&self) -> &AtlasBuilder {
+        &self.builder
+    }
+}
+
+impl Drop for TextureAtlas {
+    fn drop(&mut self) {
+        for handle in self.cooked_pages.iter().chain(&self.pages) {
+            handle.unload();
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::render::backend::command_buffer::CommandBuffer;
 use crate::render::backend::device::Device;
+use crate::render::backend::null::buffer::NullBuffer;
 use crate::render::backend::vulkan::buffer::VkBuffer;
+use ash::vk::Handle;
 use bitflags::bitflags;
 use mvcore_proc_macro::graphics_item;
 
@@ -22,6 +24,7 @@ pub enum Buffer {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(NullBuffer),
 }
 
 impl Buffer {
@@ -32,6 +35,7 @@ impl Buffer {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => Buffer::Null(NullBuffer::new(device, create_info)),
         }
     }
 
@@ -46,6 +50,21 @@ impl Buffer {
             Buffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Buffer::DirectX => unimplemented!(),
+            Buffer::Null(buffer) => {
+                buffer.write_to_buffer(data, offset, command_buffer.map(|buffer| buffer.as_null()))
+            }
+        }
+    }
+
+    /// Reads `size` bytes starting at `offset`. Only works on host visible buffers.
+    pub fn read(&mut self, size: u64, offset: u64) -> Vec<u8> {
+        match self {
+            Buffer::Vulkan(buffer) => buffer.read_from_buffer(size, offset),
+            #[cfg(target_os = "macos")]
+            Buffer::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Buffer::DirectX => unimplemented!(),
+            Buffer::Null(buffer) => buffer.read_from_buffer(size, offset),
         }
     }
 
@@ -56,6 +75,19 @@ impl Buffer {
             Buffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Buffer::DirectX => unimplemented!(),
+            Buffer::Null(buffer) => buffer.get_size(),
+        }
+    }
+
+    /// Unique id of the buffer, on vulkan this is the raw handle.
+    pub fn get_id(&self) -> u64 {
+        match self {
+            Buffer::Vulkan(buffer) => buffer.get_buffer().as_raw(),
+            #[cfg(target_os = "macos")]
+            Buffer::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Buffer::DirectX => unimplemented!(),
+            Buffer::Null(buffer) => buffer.get_id(),
         }
     }
 
@@ -68,6 +100,7 @@ impl Buffer {
             Buffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Buffer::DirectX => unimplemented!(),
+            Buffer::Null(_) => DescriptorBufferInfo::Null,
         }
     }
 
@@ -92,7 +125,14 @@ impl Buffer {
             (Buffer::Metal, Buffer::Metal) => unimplemented!(),
             #[cfg(target_os = "windows")]
             (Buffer::DirectX, Buffer::DirectX) => unimplemented!(),
-            #[cfg(any(target_os = "windows", target_os = "macos"))]
+            (Buffer::Null(src), Buffer::Null(dst)) => NullBuffer::copy_buffer(
+                src,
+                dst,
+                size,
+                src_offset,
+                dst_offset,
+                command_buffer.map(|buffer| buffer.as_null()),
+            ),
             (_, _) => unreachable!(),
         }
     }
@@ -104,6 +144,7 @@ impl Buffer {
             Buffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Buffer::DirectX => unimplemented!(),
+            Buffer::Null(_) => {}
         }
     }
 
@@ -114,6 +155,7 @@ impl Buffer {
             Buffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Buffer::DirectX => unimplemented!(),
+            Buffer::Null(_) => {}
         }
     }
 }
@@ -124,6 +166,7 @@ pub enum DescriptorBufferInfo {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null,
 }
 
 bitflags! {
@@ -1,11 +1,14 @@
 use crate::render::backend::buffer::Buffer;
 use crate::render::backend::descriptor_set::DescriptorSet;
 use crate::render::backend::device::{CommandPool, Device};
-use crate::render::backend::image::Image;
+use crate::render::backend::framebuffer::ClearColor;
+use crate::render::backend::image::{Image, ImageLayout};
+use crate::render::backend::null::buffer::NullBuffer;
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
 use crate::render::backend::pipeline::{Pipeline, PipelineType};
 use crate::render::backend::vulkan::buffer::VkBuffer;
 use crate::render::backend::vulkan::command_buffer::VkCommandBuffer;
-use crate::render::backend::Extent3D;
+use crate::render::backend::{Extent2D, Extent3D};
 use mvcore_proc_macro::graphics_item;
 
 pub enum CommandBufferLevel {
@@ -20,6 +23,88 @@ pub struct MVCommandBufferCreateInfo {
     pub label: Option<String>,
 }
 
+/// A command as seen by the null backend. Objects are referred to by their id, see `get_id`
+/// on the respective types.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordedCommand {
+    Begin,
+    End,
+    WriteBuffer {
+        buffer: u64,
+        offset: u64,
+        size: u64,
+    },
+    CopyBuffer {
+        src: u64,
+        dst: u64,
+        size: u64,
+        src_offset: u64,
+        dst_offset: u64,
+    },
+    CopyBufferToImage {
+        buffer: u64,
+        image: u64,
+    },
+    CopyImageToBuffer {
+        image: u64,
+        buffer: u64,
+    },
+    Draw {
+        vertex_count: u32,
+        instance_count: u32,
+        first_vertex: u32,
+        first_instance: u32,
+    },
+    DrawIndexed {
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        first_instance: u32,
+    },
+    BindVertexBuffer {
+        buffer: u64,
+    },
+    BindIndexBuffer {
+        buffer: u64,
+    },
+    BindPipeline {
+        pipeline: u64,
+    },
+    BindDescriptorSet {
+        set: u64,
+        pipeline: u64,
+        set_index: u32,
+    },
+    PushConstant {
+        pipeline: u64,
+        size: u32,
+    },
+    Dispatch {
+        extent: Extent3D,
+    },
+    BlitImage {
+        src: u64,
+        dst: u64,
+    },
+    GenerateMipmaps {
+        image: u64,
+        levels: u32,
+    },
+    PipelineBarrier {
+        image: u64,
+        old_layout: ImageLayout,
+        new_layout: ImageLayout,
+    },
+    BeginRenderPass {
+        framebuffer: u64,
+        clear_color: Vec<ClearColor>,
+        extent: Extent2D,
+    },
+    EndRenderPass {
+        framebuffer: u64,
+    },
+}
+
 #[graphics_item(ref)]
 pub enum CommandBuffer {
     Vulkan(VkCommandBuffer),
@@ -27,6 +112,7 @@ pub enum CommandBuffer {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(NullCommandBuffer),
 }
 
 impl CommandBuffer {
@@ -39,6 +125,7 @@ impl CommandBuffer {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => CommandBuffer::Null(NullCommandBuffer::new(device)),
         }
     }
 
@@ -49,6 +136,7 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.begin(),
         }
     }
 
@@ -59,6 +147,7 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::End),
         }
     }
 
@@ -73,6 +162,7 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => buffer.as_null().write_to_buffer(data, offset, Some(cmd)),
         }
     }
 
@@ -97,6 +187,14 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => NullBuffer::copy_buffer(
+                src.as_null(),
+                dst.as_null(),
+                size,
+                src_offset,
+                dst_offset,
+                Some(cmd),
+            ),
         }
     }
 
@@ -107,6 +205,12 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::Draw {
+                vertex_count,
+                instance_count: 1,
+                first_vertex,
+                first_instance: 0,
+            }),
         }
     }
 
@@ -125,6 +229,12 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::Draw {
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            }),
         }
     }
 
@@ -135,6 +245,12 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::DrawIndexed {
+                index_count,
+                instance_count: 1,
+                first_index,
+                first_instance: 0,
+            }),
         }
     }
 
@@ -153,6 +269,12 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::DrawIndexed {
+                index_count,
+                instance_count,
+                first_index,
+                first_instance,
+            }),
         }
     }
 
@@ -163,6 +285,9 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::BindVertexBuffer {
+                buffer: buffer.as_null().get_id(),
+            }),
         }
     }
 
@@ -173,6 +298,9 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::BindIndexBuffer {
+                buffer: buffer.as_null().get_id(),
+            }),
         }
     }
 
@@ -183,6 +311,7 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => cmd.record(RecordedCommand::Dispatch { extent }),
         }
     }
 
@@ -195,6 +324,15 @@ impl CommandBuffer {
             CommandBuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             CommandBuffer::DirectX => unimplemented!(),
+            CommandBuffer::Null(cmd) => {
+                let (src, dst) = (src_image.as_null(), dst_image.as_null());
+                src.transition_layout(ImageLayout::TransferSrcOptimal, Some(cmd));
+                dst.transition_layout(ImageLayout::TransferDstOptimal, Some(cmd));
+                cmd.record(RecordedCommand::BlitImage {
+                    src: src.get_id(),
+                    dst: dst.get_id(),
+                });
+            }
         }
     }
 
@@ -206,4 +344,15 @@ impl CommandBuffer {
     ) {
         descriptor_set.bind(self, pipeline, set_index);
     }
+
+    /// Returns everything recorded since the last `begin`. Only available on the null backend.
+    pub fn get_recorded_commands(&self) -> Vec<RecordedCommand> {
+        match self {
+            CommandBuffer::Null(cmd) => cmd.get_commands(),
+            _ => {
+                log::error!("Only null command buffers record their commands");
+                panic!();
+            }
+        }
+    }
 }
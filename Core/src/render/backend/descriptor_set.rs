@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use ash::vk::Handle;
 use bitflags::bitflags;
 use mvcore_proc_macro::graphics_item;
 use parking_lot::Mutex;
@@ -8,6 +9,9 @@ use crate::render::backend::buffer::Buffer;
 use crate::render::backend::command_buffer::CommandBuffer;
 use crate::render::backend::device::Device;
 use crate::render::backend::image::{Image, ImageLayout};
+use crate::render::backend::null::descriptor_set::{
+    NullDescriptorPool, NullDescriptorSet, NullDescriptorSetLayout,
+};
 use crate::render::backend::pipeline::{Pipeline, PipelineType};
 use crate::render::backend::sampler::Sampler;
 use crate::render::backend::shader::ShaderStage;
@@ -46,6 +50,7 @@ pub enum DescriptorSetLayout {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(Arc<NullDescriptorSetLayout>),
 }
 
 impl DescriptorSetLayout {
@@ -58,6 +63,9 @@ impl DescriptorSetLayout {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => DescriptorSetLayout::Null(
+                NullDescriptorSetLayout::new(device, create_info.bindings.len() as u32).into(),
+            ),
         }
     }
 
@@ -70,6 +78,9 @@ impl DescriptorSetLayout {
             DescriptorSetLayout::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSetLayout::DirectX => unimplemented!(),
+            DescriptorSetLayout::Null(descriptor_set_layout) => {
+                descriptor_set_layout.get_bindings_count()
+            }
         }
     }
 }
@@ -102,6 +113,7 @@ pub enum DescriptorPool {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(Arc<NullDescriptorPool>),
 }
 
 impl DescriptorPool {
@@ -114,6 +126,7 @@ impl DescriptorPool {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => DescriptorPool::Null(NullDescriptorPool::new(device).into()),
         }
     }
 }
@@ -139,6 +152,7 @@ pub enum DescriptorSet {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(NullDescriptorSet),
 }
 
 impl DescriptorSet {
@@ -151,6 +165,11 @@ impl DescriptorSet {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => {
+                let layout =
+                    NullDescriptorSetLayout::new(device.clone(), create_info.bindings.len() as u32);
+                DescriptorSet::Null(NullDescriptorSet::new(device, layout.into()))
+            }
         }
     }
 
@@ -161,6 +180,9 @@ impl DescriptorSet {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => {
+                DescriptorSet::Null(NullDescriptorSet::new(device, create_info.layout.as_null()))
+            }
         }
     }
 
@@ -178,6 +200,7 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => descriptor_set.write(binding, buffer.get_id()),
         }
     }
 
@@ -204,6 +227,27 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => descriptor_set.write(binding, image.get_id()),
+        }
+    }
+
+    /// Adds a single mip level of `image` as a storage image, the image has to be in the general layout
+    /// when it is written.
+    pub fn add_storage_image(&mut self, binding: u32, image: &Image, level: u32) {
+        match self {
+            DescriptorSet::Vulkan(descriptor_set) => descriptor_set.add_image(
+                binding,
+                ash::vk::DescriptorImageInfo {
+                    sampler: ash::vk::Sampler::null(),
+                    image_view: image.as_vulkan().get_storage_view(level),
+                    image_layout: ash::vk::ImageLayout::GENERAL,
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            DescriptorSet::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => descriptor_set.write(binding, image.get_id()),
         }
     }
 
@@ -221,6 +265,7 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => descriptor_set.write(binding, buffer.get_id()),
         }
     }
 
@@ -247,6 +292,7 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => descriptor_set.write(binding, image.get_id()),
         }
     }
 
@@ -257,6 +303,7 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(_) => {}
         }
     }
 
@@ -269,6 +316,32 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => {
+                DescriptorSetLayout::Null(descriptor_set.get_layout())
+            }
+        }
+    }
+
+    /// Unique id of the descriptor set, on vulkan this is the raw handle.
+    pub fn get_id(&self) -> u64 {
+        match self {
+            DescriptorSet::Vulkan(descriptor_set) => descriptor_set.get_handle().as_raw(),
+            #[cfg(target_os = "macos")]
+            DescriptorSet::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => descriptor_set.get_id(),
+        }
+    }
+
+    /// Id of the object last written to `binding`. Only available on the null backend.
+    pub fn get_bound_id(&self, binding: u32) -> Option<u64> {
+        match self {
+            DescriptorSet::Null(descriptor_set) => descriptor_set.get_binding(binding),
+            _ => {
+                log::error!("Only null descriptor sets keep track of their bindings");
+                panic!();
+            }
         }
     }
 
@@ -286,6 +359,9 @@ impl DescriptorSet {
             DescriptorSet::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             DescriptorSet::DirectX => unimplemented!(),
+            DescriptorSet::Null(descriptor_set) => {
+                descriptor_set.bind(set_index, pipeline.get_id(), command_buffer.as_null())
+            }
         }
     }
 }
@@ -1,4 +1,7 @@
 use crate::render::backend::command_buffer::CommandBuffer;
+use crate::render::backend::image::{ImageFormat, ImageLimits, ImageUsage};
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
 use crate::render::backend::vulkan::command_buffer::VkCommandBuffer;
 use crate::render::backend::Backend;
 use bitflags::bitflags;
@@ -25,6 +28,7 @@ pub enum Device {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(Arc<NullDevice>),
 }
 
 impl Device {
@@ -34,14 +38,44 @@ impl Device {
         window: &winit::window::Window,
     ) -> Self {
         match backend {
-            Backend::Vulkan => Device::Vulkan(VkDevice::new(create_info.into(), window).into()),
+            Backend::Vulkan => {
+                Device::Vulkan(VkDevice::new(create_info.into(), Some(window)).into())
+            }
             #[cfg(target_os = "macos")]
             Backend::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Backend::DirectX => unimplemented!(),
+            Backend::Null => Device::Null(NullDevice::new().into()),
         }
     }
 
+    /// Creates a device without a window. A headless device has no surface, no present queue
+    /// and cannot create swapchains, but everything else (buffers, images, pipelines, command
+    /// buffers) works the same. Useful for offscreen rendering and tests.
+    pub fn new_headless(backend: Backend, create_info: MVDeviceCreateInfo) -> Self {
+        match backend {
+            Backend::Vulkan => Device::Vulkan(VkDevice::new(create_info.into(), None).into()),
+            #[cfg(target_os = "macos")]
+            Backend::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Backend::DirectX => unimplemented!(),
+            Backend::Null => Device::Null(NullDevice::new().into()),
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        match self {
+            Device::Vulkan(device) => device.is_headless(),
+            #[cfg(target_os = "macos")]
+            Device::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Device::DirectX => unimplemented!(),
+            Device::Null(_) => true,
+        }
+    }
+
+    /// Can be called from any thread, the command buffer has to be ended with
+    /// [`Device::end_single_time_command`] on the same thread.
     pub fn begin_single_time_command(&self, pool: CommandPool) -> CommandBuffer {
         match self {
             Device::Vulkan(device) => CommandBuffer::Vulkan(VkCommandBuffer::from(
@@ -52,6 +86,11 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => {
+                let buffer = NullCommandBuffer::new(device.clone());
+                buffer.begin();
+                CommandBuffer::Null(buffer)
+            }
         }
     }
 
@@ -66,6 +105,7 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => {}
         }
     }
 
@@ -76,6 +116,7 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => CommandPool::Null,
         }
     }
 
@@ -86,6 +127,7 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => CommandPool::Null,
         }
     }
 
@@ -96,6 +138,7 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => Queue::Null,
         }
     }
 
@@ -106,6 +149,7 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => Queue::Null,
         }
     }
 
@@ -116,6 +160,33 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => Queue::Null,
+        }
+    }
+
+    /// Whether images of `format` can be created with `usage`, and have their mipmaps generated if
+    /// `generate_mipmaps` is set. Check this before creating images with compressed formats, see
+    /// [`ImageFormat::is_compressed`].
+    pub fn supports_format(&self, format: ImageFormat, usage: &ImageUsage, generate_mipmaps: bool) -> bool {
+        match self {
+            Device::Vulkan(device) => device.is_format_supported(format, usage, generate_mipmaps),
+            #[cfg(target_os = "macos")]
+            Device::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Device::DirectX => unimplemented!(),
+            Device::Null(_) => true,
+        }
+    }
+
+    /// The null backend reports the limits every Vulkan device supports.
+    pub fn get_image_limits(&self) -> ImageLimits {
+        match self {
+            Device::Vulkan(device) => device.get_image_limits(),
+            #[cfg(target_os = "macos")]
+            Device::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Device::DirectX => unimplemented!(),
+            Device::Null(_) => ImageLimits::default(),
         }
     }
 
@@ -126,6 +197,7 @@ impl Device {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => {}
         }
     }
 }
@@ -138,6 +210,7 @@ pub enum CommandPool {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null,
 }
 
 #[graphics_item(copy)]
@@ -148,6 +221,7 @@ pub enum Queue {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null,
 }
 
 bitflags! {
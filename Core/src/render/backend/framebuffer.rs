@@ -1,12 +1,16 @@
 use std::sync::Arc;
 
+use ash::vk::Handle;
 use bitflags::bitflags;
 use mvcore_proc_macro::graphics_item;
 
 use crate::render::backend::command_buffer::CommandBuffer;
 use crate::render::backend::device::Device;
 use crate::render::backend::Extent2D;
-use crate::render::backend::image::{AccessFlags, Image, ImageFormat, ImageLayout, ImageUsage};
+use crate::render::backend::image::{
+    AccessFlags, Image, ImageFormat, ImageLayout, ImagePixels, ImageUsage,
+};
+use crate::render::backend::null::framebuffer::NullFramebuffer;
 use crate::render::backend::vulkan::framebuffer::VkFramebuffer;
 
 pub enum LoadOp {
@@ -46,7 +50,7 @@ pub struct MVFramebufferCreateInfo {
     pub label: Option<String>,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ClearColor {
     Color([f32; 4]),
     Depth { depth: f32, stencil: u32 },
@@ -60,6 +64,7 @@ pub enum Framebuffer {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(Arc<NullFramebuffer>),
 }
 
 impl Framebuffer {
@@ -72,6 +77,9 @@ impl Framebuffer {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => {
+                Framebuffer::Null(NullFramebuffer::new(device, create_info).into())
+            }
         }
     }
 
@@ -91,6 +99,9 @@ impl Framebuffer {
             Framebuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Framebuffer::DirectX => unimplemented!(),
+            Framebuffer::Null(framebuffer) => {
+                framebuffer.begin_render_pass(command_buffer.as_null(), clear_color, extent)
+            }
         }
     }
 
@@ -103,6 +114,7 @@ impl Framebuffer {
             Framebuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Framebuffer::DirectX => unimplemented!(),
+            Framebuffer::Null(framebuffer) => framebuffer.end_render_pass(command_buffer.as_null()),
         }
     }
 
@@ -113,8 +125,26 @@ impl Framebuffer {
             Framebuffer::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Framebuffer::DirectX => unimplemented!(),
+            Framebuffer::Null(framebuffer) => Image::Null(framebuffer.get_image(index)),
+        }
+    }
+
+    /// Unique id of the framebuffer, on vulkan this is the raw handle.
+    pub fn get_id(&self) -> u64 {
+        match self {
+            Framebuffer::Vulkan(framebuffer) => framebuffer.get_handle().as_raw(),
+            #[cfg(target_os = "macos")]
+            Framebuffer::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Framebuffer::DirectX => unimplemented!(),
+            Framebuffer::Null(framebuffer) => framebuffer.get_id(),
         }
     }
+
+    /// Reads the attachment at `index` back into host memory, see [`Image::read_pixels`].
+    pub fn read_attachment(&self, index: u32) -> ImagePixels {
+        self.get_image(index).read_pixels()
+    }
 }
 
 bitflags! {
@@ -1,13 +1,15 @@
 use std::sync::Arc;
 
+use ash::vk::Handle;
 use bitflags::bitflags;
-use image::ColorType;
+use image::{ColorType, DynamicImage};
 
 use mvcore_proc_macro::graphics_item;
 
 use crate::render::backend::buffer::{Buffer, MemoryProperties};
 use crate::render::backend::command_buffer::CommandBuffer;
 use crate::render::backend::device::Device;
+use crate::render::backend::null::image::NullImage;
 use crate::render::backend::Extent2D;
 use crate::render::backend::vulkan::image::VkImage;
 
@@ -22,11 +24,15 @@ pub enum ImageTiling {
     Linear,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ImageFormat {
     R8,
     R8G8,
     R8G8B8,
     R8G8B8A8,
+    R8G8B8A8Srgb,
+    B8G8R8A8,
+    B8G8R8A8Srgb,
     R16,
     R16G16,
     R16G16B16,
@@ -39,6 +45,195 @@ pub enum ImageFormat {
     D16S8,
     D24,
     D32,
+    // Block compressed, see [`ImageFormat::get_block_extent`]
+    Bc1,
+    Bc1Srgb,
+    Bc2,
+    Bc2Srgb,
+    Bc3,
+    Bc3Srgb,
+    Bc4,
+    Bc5,
+    Bc6H,
+    Bc7,
+    Bc7Srgb,
+    Astc4x4,
+    Astc4x4Srgb,
+    Astc5x4,
+    Astc5x4Srgb,
+    Astc5x5,
+    Astc5x5Srgb,
+    Astc6x5,
+    Astc6x5Srgb,
+    Astc6x6,
+    Astc6x6Srgb,
+    Astc8x5,
+    Astc8x5Srgb,
+    Astc8x6,
+    Astc8x6Srgb,
+    Astc8x8,
+    Astc8x8Srgb,
+    Astc10x5,
+    Astc10x5Srgb,
+    Astc10x6,
+    Astc10x6Srgb,
+    Astc10x8,
+    Astc10x8Srgb,
+    Astc10x10,
+    Astc10x10Srgb,
+    Astc12x10,
+    Astc12x10Srgb,
+    Astc12x12,
+    Astc12x12Srgb,
+}
+
+/// How the color channels of an image are encoded. Textures authored as colors are usually sRGB,
+/// data like normal maps is linear. Sampling an sRGB image returns linear values, so shaders always
+/// work in linear space.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+/// Formats with an sRGB counterpart, as (linear, sRGB).
+const SRGB_FORMATS: &[(ImageFormat, ImageFormat)] = &[
+    (ImageFormat::R8G8B8A8, ImageFormat::R8G8B8A8Srgb),
+    (ImageFormat::B8G8R8A8, ImageFormat::B8G8R8A8Srgb),
+    (ImageFormat::Bc1, ImageFormat::Bc1Srgb),
+    (ImageFormat::Bc2, ImageFormat::Bc2Srgb),
+    (ImageFormat::Bc3, ImageFormat::Bc3Srgb),
+    (ImageFormat::Bc7, ImageFormat::Bc7Srgb),
+    (ImageFormat::Astc4x4, ImageFormat::Astc4x4Srgb),
+    (ImageFormat::Astc5x4, ImageFormat::Astc5x4Srgb),
+    (ImageFormat::Astc5x5, ImageFormat::Astc5x5Srgb),
+    (ImageFormat::Astc6x5, ImageFormat::Astc6x5Srgb),
+    (ImageFormat::Astc6x6, ImageFormat::Astc6x6Srgb),
+    (ImageFormat::Astc8x5, ImageFormat::Astc8x5Srgb),
+    (ImageFormat::Astc8x6, ImageFormat::Astc8x6Srgb),
+    (ImageFormat::Astc8x8, ImageFormat::Astc8x8Srgb),
+    (ImageFormat::Astc10x5, ImageFormat::Astc10x5Srgb),
+    (ImageFormat::Astc10x6, ImageFormat::Astc10x6Srgb),
+    (ImageFormat::Astc10x8, ImageFormat::Astc10x8Srgb),
+    (ImageFormat::Astc10x10, ImageFormat::Astc10x10Srgb),
+    (ImageFormat::Astc12x10, ImageFormat::Astc12x10Srgb),
+    (ImageFormat::Astc12x12, ImageFormat::Astc12x12Srgb),
+];
+
+impl ImageFormat {
+    /// Size of one pixel in bytes. Compressed formats have no whole pixel size, use
+    /// [`ImageFormat::get_block_size`] or [`ImageFormat::get_data_size`] for them.
+    pub fn get_pixel_size(&self) -> u32 {
+        match self {
+            ImageFormat::R8 => 1,
+            ImageFormat::R8G8 => 2,
+            ImageFormat::R8G8B8 => 3,
+            ImageFormat::R8G8B8A8 | ImageFormat::R8G8B8A8Srgb => 4,
+            ImageFormat::B8G8R8A8 | ImageFormat::B8G8R8A8Srgb => 4,
+            ImageFormat::R16 => 2,
+            ImageFormat::R16G16 => 4,
+            ImageFormat::R16G16B16 => 6,
+            ImageFormat::R16G16B16A16 => 8,
+            ImageFormat::R32 => 4,
+            ImageFormat::R32G32 => 8,
+            ImageFormat::R32G32B32 => 12,
+            ImageFormat::R32G32B32A32 => 16,
+            ImageFormat::D16 => 2,
+            ImageFormat::D16S8 => 2,
+            ImageFormat::D24 => 4,
+            ImageFormat::D32 => 4,
+            _ => {
+                log::error!("Compressed format {self:?} has no pixel size");
+                panic!();
+            }
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.get_block_extent() != (1, 1)
+    }
+
+    /// Width and height in pixels of the blocks a compressed format is stored in, uncompressed
+    /// formats have single pixel blocks.
+    pub fn get_block_extent(&self) -> (u32, u32) {
+        match self {
+            ImageFormat::Bc1
+            | ImageFormat::Bc1Srgb
+            | ImageFormat::Bc2
+            | ImageFormat::Bc2Srgb
+            | ImageFormat::Bc3
+            | ImageFormat::Bc3Srgb
+            | ImageFormat::Bc4
+            | ImageFormat::Bc5
+            | ImageFormat::Bc6H
+            | ImageFormat::Bc7
+            | ImageFormat::Bc7Srgb
+            | ImageFormat::Astc4x4
+            | ImageFormat::Astc4x4Srgb => (4, 4),
+            ImageFormat::Astc5x4 | ImageFormat::Astc5x4Srgb => (5, 4),
+            ImageFormat::Astc5x5 | ImageFormat::Astc5x5Srgb => (5, 5),
+            ImageFormat::Astc6x5 | ImageFormat::Astc6x5Srgb => (6, 5),
+            ImageFormat::Astc6x6 | ImageFormat::Astc6x6Srgb => (6, 6),
+            ImageFormat::Astc8x5 | ImageFormat::Astc8x5Srgb => (8, 5),
+            ImageFormat::Astc8x6 | ImageFormat::Astc8x6Srgb => (8, 6),
+            ImageFormat::Astc8x8 | ImageFormat::Astc8x8Srgb => (8, 8),
+            ImageFormat::Astc10x5 | ImageFormat::Astc10x5Srgb => (10, 5),
+            ImageFormat::Astc10x6 | ImageFormat::Astc10x6Srgb => (10, 6),
+            ImageFormat::Astc10x8 | ImageFormat::Astc10x8Srgb => (10, 8),
+            ImageFormat::Astc10x10 | ImageFormat::Astc10x10Srgb => (10, 10),
+            ImageFormat::Astc12x10 | ImageFormat::Astc12x10Srgb => (12, 10),
+            ImageFormat::Astc12x12 | ImageFormat::Astc12x12Srgb => (12, 12),
+            _ => (1, 1),
+        }
+    }
+
+    /// Size of one block in bytes, see [`ImageFormat::get_block_extent`].
+    pub fn get_block_size(&self) -> u32 {
+        match self {
+            ImageFormat::Bc1 | ImageFormat::Bc1Srgb | ImageFormat::Bc4 => 8,
+            ImageFormat::Bc2
+            | ImageFormat::Bc2Srgb
+            | ImageFormat::Bc3
+            | ImageFormat::Bc3Srgb
+            | ImageFormat::Bc5
+            | ImageFormat::Bc6H
+            | ImageFormat::Bc7
+            | ImageFormat::Bc7Srgb => 16,
+            // Every ASTC block size is stored in 128 bits
+            format if format.is_compressed() => 16,
+            format => format.get_pixel_size(),
+        }
+    }
+
+    /// Bytes a single layer of `extent` takes up, partial blocks at the edges count as whole ones.
+    /// Saturates instead of overflowing, use [`ImageFormat::checked_data_size`] for untrusted extents.
+    pub fn get_data_size(&self, extent: Extent2D) -> u64 {
+        self.checked_data_size(extent).unwrap_or(u64::MAX)
+    }
+
+    /// [`ImageFormat::get_data_size`], or `None` if it overflows.
+    pub fn checked_data_size(&self, extent: Extent2D) -> Option<u64> {
+        let (block_width, block_height) = self.get_block_extent();
+        (extent.width.div_ceil(block_width) as u64)
+            .checked_mul(extent.height.div_ceil(block_height) as u64)?
+            .checked_mul(self.get_block_size() as u64)
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        SRGB_FORMATS.iter().any(|(_, srgb)| srgb == self)
+    }
+
+    /// The sRGB or linear counterpart of this format, formats without one are returned as they are.
+    pub fn with_color_space(self, color_space: ColorSpace) -> ImageFormat {
+        SRGB_FORMATS
+            .iter()
+            .find(|(linear, srgb)| *linear == self || *srgb == self)
+            .map_or(self, |(linear, srgb)| match color_space {
+                ColorSpace::Srgb => *srgb,
+                ColorSpace::Linear => *linear,
+            })
+    }
 }
 
 impl From<ColorType> for ImageFormat {
@@ -59,6 +254,7 @@ impl From<ColorType> for ImageFormat {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ImageLayout {
     Undefined,
     General,
@@ -72,6 +268,30 @@ pub enum ImageLayout {
     PresentSrc,
 }
 
+/// The largest images a device can create, see [`Device::get_image_limits`].
+///
+/// [`Device::get_image_limits`]: crate::render::backend::device::Device::get_image_limits
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// Width and height of 2d images and arrays.
+    pub max_dimension: u32,
+    /// Width and height of cubemaps.
+    pub max_cube_dimension: u32,
+    /// Array layers, six per cubemap.
+    pub max_array_layers: u32,
+}
+
+impl Default for ImageLimits {
+    /// The limits every Vulkan device supports.
+    fn default() -> Self {
+        Self {
+            max_dimension: 4096,
+            max_cube_dimension: 4096,
+            max_array_layers: 256,
+        }
+    }
+}
+
 bitflags! {
     pub struct ImageUsage: u8 {
         const TRANSFER_SRC = 1 << 0;
@@ -101,9 +321,13 @@ pub struct MVImageCreateInfo {
     pub aspect: ImageAspect,
     pub tiling: ImageTiling,
     pub layer_count: u32,
+    /// 1 for images without mipmaps, see [`Extent2D::get_max_mip_levels`] for a full mip chain.
+    pub mip_level_count: u32,
     pub image_type: ImageType,
     pub cubemap: bool,
     pub memory_usage_flags: gpu_alloc::UsageFlags,
+    /// Pixels of all mip levels, largest first, or only of the first one, in which case the others
+    /// are generated from it, see [`Image::generate_mipmaps`].
     pub data: Option<Vec<u8>>,
 
     pub label: Option<String>,
@@ -117,6 +341,7 @@ pub enum Image {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(Arc<NullImage>),
 }
 
 impl Image {
@@ -129,6 +354,7 @@ impl Image {
             Device::Metal => unreachable!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unreachable!(),
+            Device::Null(device) => Image::Null(NullImage::new(device, create_info).into()),
         }
     }
 
@@ -150,6 +376,9 @@ impl Image {
             Image::Metal => unreachable!(),
             #[cfg(target_os = "windows")]
             Image::DirectX => unreachable!(),
+            Image::Null(image) => {
+                image.transition_layout(new_layout, command_buffer.map(|cmd| cmd.as_null()))
+            }
         }
     }
 
@@ -163,6 +392,102 @@ impl Image {
             Image::Metal => unreachable!(),
             #[cfg(target_os = "windows")]
             Image::DirectX => unreachable!(),
+            Image::Null(image) => image
+                .copy_buffer_to_image(buffer.as_null(), command_buffer.map(|cmd| cmd.as_null())),
+        }
+    }
+
+    pub fn copy_image_to_buffer(&self, buffer: &Buffer, command_buffer: Option<&CommandBuffer>) {
+        match self {
+            Image::Vulkan(image) => image.copy_image_to_buffer(
+                buffer.as_vulkan(),
+                command_buffer.map(|cmd| cmd.as_vulkan()),
+            ),
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => image
+                .copy_image_to_buffer(buffer.as_null(), command_buffer.map(|cmd| cmd.as_null())),
+        }
+    }
+
+    /// Copies the image back into host memory. This blocks until the gpu is done with the copy,
+    /// so don't call it every frame.
+    pub fn read_pixels(&self) -> ImagePixels {
+        match self {
+            Image::Vulkan(image) => ImagePixels {
+                data: image.read_pixels(),
+                format: image.get_format(0).into(),
+                extent: image.get_extent().into(),
+            },
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => ImagePixels {
+                data: image.read_pixels(),
+                format: image.get_format(),
+                extent: image.get_extent(),
+            },
+        }
+    }
+
+    pub fn get_format(&self) -> ImageFormat {
+        match self {
+            Image::Vulkan(image) => image.get_format(0).into(),
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => image.get_format(),
+        }
+    }
+
+    /// Fills every mip level after the first one by successively downscaling the previous level.
+    /// The image has to be created with more than one mip level, it ends up ready for sampling.
+    pub fn generate_mipmaps(&self, command_buffer: Option<&CommandBuffer>) {
+        match self {
+            Image::Vulkan(image) => image.generate_mipmaps(command_buffer.map(|cmd| cmd.as_vulkan())),
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => image.generate_mipmaps(command_buffer.map(|cmd| cmd.as_null())),
+        }
+    }
+
+    pub fn get_mip_level_count(&self) -> u32 {
+        match self {
+            Image::Vulkan(image) => image.get_mip_level_count(),
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => image.get_mip_level_count(),
+        }
+    }
+
+    pub fn get_layer_count(&self) -> u32 {
+        match self {
+            Image::Vulkan(image) => image.get_layer_count(),
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => image.get_layer_count(),
+        }
+    }
+
+    /// Unique id of the image, on vulkan this is the raw handle.
+    pub fn get_id(&self) -> u64 {
+        match self {
+            Image::Vulkan(image) => image.get_handle().as_raw(),
+            #[cfg(target_os = "macos")]
+            Image::Metal => unreachable!(),
+            #[cfg(target_os = "windows")]
+            Image::DirectX => unreachable!(),
+            Image::Null(image) => image.get_id(),
         }
     }
 
@@ -173,8 +498,81 @@ impl Image {
             Image::Metal => unreachable!(),
             #[cfg(target_os = "windows")]
             Image::DirectX => unreachable!(),
+            Image::Null(image) => image.get_extent(),
+        }
+    }
+}
+
+/// Tightly packed pixels read back from an [`Image`].
+pub struct ImagePixels {
+    pub data: Vec<u8>,
+    pub format: ImageFormat,
+    pub extent: Extent2D,
+}
+
+impl ImagePixels {
+    /// Converts the pixels into an [`DynamicImage`], half float formats are widened to 32 bit
+    /// floats. Returns `None` for formats `image` can't represent, like depth or compressed formats.
+    pub fn to_dynamic_image(&self) -> Option<DynamicImage> {
+        let (width, height) = (self.extent.width, self.extent.height);
+        let data = self.data.clone();
+        match self.format {
+            ImageFormat::R8 => image::GrayImage::from_raw(width, height, data).map(Into::into),
+            ImageFormat::R8G8 => {
+                image::GrayAlphaImage::from_raw(width, height, data).map(Into::into)
+            }
+            ImageFormat::R8G8B8 => image::RgbImage::from_raw(width, height, data).map(Into::into),
+            ImageFormat::R8G8B8A8 | ImageFormat::R8G8B8A8Srgb => {
+                image::RgbaImage::from_raw(width, height, data).map(Into::into)
+            }
+            ImageFormat::B8G8R8A8 | ImageFormat::B8G8R8A8Srgb => {
+                let mut data = data;
+                data.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+                image::RgbaImage::from_raw(width, height, data).map(Into::into)
+            }
+            ImageFormat::R16G16B16 => {
+                image::Rgb32FImage::from_raw(width, height, Self::widen_f16(&data)).map(Into::into)
+            }
+            ImageFormat::R16G16B16A16 => {
+                image::Rgba32FImage::from_raw(width, height, Self::widen_f16(&data)).map(Into::into)
+            }
+            ImageFormat::R32G32B32 => {
+                image::Rgb32FImage::from_raw(width, height, Self::to_f32(&data)).map(Into::into)
+            }
+            ImageFormat::R32G32B32A32 => {
+                image::Rgba32FImage::from_raw(width, height, Self::to_f32(&data)).map(Into::into)
+            }
+            _ => None,
         }
     }
+
+    fn to_f32(data: &[u8]) -> Vec<f32> {
+        data.chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+
+    fn widen_f16(data: &[u8]) -> Vec<f32> {
+        data.chunks_exact(2)
+            .map(|b| {
+                let bits = u16::from_ne_bytes([b[0], b[1]]) as u32;
+                let sign = (bits & 0x8000) << 16;
+                let exponent = (bits >> 10) & 0x1f;
+                let mantissa = bits & 0x3ff;
+                let value = match (exponent, mantissa) {
+                    (0, 0) => sign,
+                    // Subnormal, normalize it
+                    (0, _) => {
+                        let shift = mantissa.leading_zeros() - 21;
+                        sign | ((113 - shift) << 23) | (((mantissa << shift) & 0x3ff) << 13)
+                    }
+                    (0x1f, _) => sign | 0x7f800000 | (mantissa << 13),
+                    _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+                };
+                f32::from_bits(value)
+            })
+            .collect()
+    }
 }
 
 bitflags! {
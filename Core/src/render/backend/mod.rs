@@ -12,6 +12,7 @@ pub mod sampler;
 pub mod shader;
 pub mod swapchain;
 pub(crate) mod vulkan;
+pub(crate) mod null;
 
 #[cfg(feature = "ray-tracing")]
 pub mod sbt;
@@ -24,6 +25,9 @@ pub enum Backend {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    /// Doesn't render anything, command buffers only record what was submitted to them.
+    /// Used for testing without a gpu.
+    Null,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
@@ -32,6 +36,21 @@ pub struct Extent2D {
     pub height: u32,
 }
 
+impl Extent2D {
+    /// Number of mip levels down to 1x1 for an image of this size.
+    pub fn get_max_mip_levels(&self) -> u32 {
+        32 - self.width.max(self.height).max(1).leading_zeros()
+    }
+
+    /// Size of mip level `level` of an image of this size.
+    pub fn get_mip_extent(&self, level: u32) -> Extent2D {
+        Extent2D {
+            width: (self.width >> level).max(1),
+            height: (self.height >> level).max(1),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
 pub struct Extent3D {
     pub width: u32,
@@ -0,0 +1,99 @@
+use crate::render::backend::buffer::MVBufferCreateInfo;
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+pub struct NullBuffer {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    data: Mutex<Vec<u8>>,
+}
+
+impl NullBuffer {
+    pub(crate) fn new(device: Arc<NullDevice>, create_info: MVBufferCreateInfo) -> Self {
+        let alignment = create_info.minimum_alignment.max(1);
+        let aligned_size = (create_info.instance_size + alignment - 1) & !(alignment - 1);
+        let size = aligned_size * create_info.instance_count as u64;
+
+        Self {
+            device,
+            id: super::next_id(),
+            data: Mutex::new(vec![0; size as usize]),
+        }
+    }
+
+    /// There is no gpu timeline, writes land immediately and are only recorded on `cmd`.
+    pub(crate) fn write_to_buffer(
+        &self,
+        data: &[u8],
+        offset: u64,
+        cmd: Option<&NullCommandBuffer>,
+    ) {
+        let mut buffer = self.data.lock();
+        let offset = offset as usize;
+        if offset + data.len() > buffer.len() {
+            log::error!(
+                "Buffer write out of bounds, size: {}, requested: {}",
+                buffer.len(),
+                offset + data.len()
+            );
+            panic!();
+        }
+        buffer[offset..offset + data.len()].copy_from_slice(data);
+
+        if let Some(cmd) = cmd {
+            cmd.record(RecordedCommand::WriteBuffer {
+                buffer: self.id,
+                offset: offset as u64,
+                size: data.len() as u64,
+            });
+        }
+    }
+
+    pub(crate) fn read_from_buffer(&self, size: u64, offset: u64) -> Vec<u8> {
+        let buffer = self.data.lock();
+        let (offset, size) = (offset as usize, size as usize);
+        if offset + size > buffer.len() {
+            log::error!(
+                "Buffer read out of bounds, size: {}, requested: {}",
+                buffer.len(),
+                offset + size
+            );
+            panic!();
+        }
+        buffer[offset..offset + size].to_vec()
+    }
+
+    pub(crate) fn copy_buffer(
+        src: &NullBuffer,
+        dst: &NullBuffer,
+        size: u64,
+        src_offset: u64,
+        dst_offset: u64,
+        cmd: Option<&NullCommandBuffer>,
+    ) {
+        let data = src.read_from_buffer(size, src_offset);
+        dst.write_to_buffer(&data, dst_offset, None);
+
+        if let Some(cmd) = cmd {
+            cmd.record(RecordedCommand::CopyBuffer {
+                src: src.id,
+                dst: dst.id,
+                size,
+                src_offset,
+                dst_offset,
+            });
+        }
+    }
+
+    pub(crate) fn get_size(&self) -> u64 {
+        self.data.lock().len() as u64
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
@@ -0,0 +1,40 @@
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::null::device::NullDevice;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+pub struct NullCommandBuffer {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    commands: Mutex<Vec<RecordedCommand>>,
+}
+
+impl NullCommandBuffer {
+    pub(crate) fn new(device: Arc<NullDevice>) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+            commands: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Beginning a command buffer resets it, like a one time submit buffer would.
+    pub(crate) fn begin(&self) {
+        let mut commands = self.commands.lock();
+        commands.clear();
+        commands.push(RecordedCommand::Begin);
+    }
+
+    pub(crate) fn record(&self, command: RecordedCommand) {
+        self.commands.lock().push(command);
+    }
+
+    pub(crate) fn get_commands(&self) -> Vec<RecordedCommand> {
+        self.commands.lock().clone()
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
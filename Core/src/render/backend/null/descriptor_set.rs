@@ -0,0 +1,86 @@
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
+use std::sync::Arc;
+
+pub struct NullDescriptorPool {
+    device: Arc<NullDevice>,
+    id: u64,
+}
+
+impl NullDescriptorPool {
+    pub(crate) fn new(device: Arc<NullDevice>) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+        }
+    }
+}
+
+pub struct NullDescriptorSetLayout {
+    device: Arc<NullDevice>,
+    id: u64,
+    bindings_count: u32,
+}
+
+impl NullDescriptorSetLayout {
+    pub(crate) fn new(device: Arc<NullDevice>, bindings_count: u32) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+            bindings_count,
+        }
+    }
+
+    pub(crate) fn get_bindings_count(&self) -> u32 {
+        self.bindings_count
+    }
+}
+
+pub struct NullDescriptorSet {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    layout: Arc<NullDescriptorSetLayout>,
+    /// Ids of the objects written to each binding, images are recorded with their image id.
+    bindings: Vec<(u32, u64)>,
+}
+
+impl NullDescriptorSet {
+    pub(crate) fn new(device: Arc<NullDevice>, layout: Arc<NullDescriptorSetLayout>) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+            layout,
+            bindings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn write(&mut self, binding: u32, object: u64) {
+        self.bindings.retain(|(index, _)| *index != binding);
+        self.bindings.push((binding, object));
+    }
+
+    pub(crate) fn bind(&self, set_index: u32, pipeline: u64, cmd: &NullCommandBuffer) {
+        cmd.record(RecordedCommand::BindDescriptorSet {
+            set: self.id,
+            pipeline,
+            set_index,
+        });
+    }
+
+    pub(crate) fn get_binding(&self, binding: u32) -> Option<u64> {
+        self.bindings
+            .iter()
+            .find(|(index, _)| *index == binding)
+            .map(|(_, object)| *object)
+    }
+
+    pub(crate) fn get_layout(&self) -> Arc<NullDescriptorSetLayout> {
+        self.layout.clone()
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
@@ -0,0 +1,8 @@
+pub struct NullDevice;
+
+impl NullDevice {
+    pub(crate) fn new() -> Self {
+        log::info!("Creating null device, nothing will be rendered");
+        NullDevice
+    }
+}
@@ -0,0 +1,86 @@
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::framebuffer::{ClearColor, MVFramebufferCreateInfo};
+use crate::render::backend::image::{ImageFormat, ImageLayout};
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
+use crate::render::backend::null::image::NullImage;
+use crate::render::backend::Extent2D;
+use std::sync::Arc;
+
+pub struct NullFramebuffer {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    images: Vec<Arc<NullImage>>,
+    final_layouts: Vec<ImageLayout>,
+}
+
+impl NullFramebuffer {
+    pub(crate) fn new(device: Arc<NullDevice>, create_info: MVFramebufferCreateInfo) -> Self {
+        let images = create_info
+            .attachment_formats
+            .iter()
+            .map(|format| {
+                Arc::new(NullImage::from_format(
+                    device.clone(),
+                    *format,
+                    create_info.extent,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let final_layouts = match create_info.render_pass_info {
+            Some(render_pass_info) if !render_pass_info.final_layouts.is_empty() => {
+                render_pass_info.final_layouts
+            }
+            _ => create_info
+                .attachment_formats
+                .iter()
+                .map(|format| match format {
+                    ImageFormat::D16 | ImageFormat::D16S8 | ImageFormat::D24 | ImageFormat::D32 => {
+                        ImageLayout::DepthStencilAttachmentOptimal
+                    }
+                    _ => ImageLayout::ColorAttachmentOptimal,
+                })
+                .collect(),
+        };
+
+        Self {
+            device,
+            id: super::next_id(),
+            images,
+            final_layouts,
+        }
+    }
+
+    pub(crate) fn begin_render_pass(
+        &self,
+        cmd: &NullCommandBuffer,
+        clear_color: &[ClearColor],
+        extent: Extent2D,
+    ) {
+        cmd.record(RecordedCommand::BeginRenderPass {
+            framebuffer: self.id,
+            clear_color: clear_color.to_vec(),
+            extent,
+        });
+
+        for (image, layout) in self.images.iter().zip(&self.final_layouts) {
+            image.set_layout(*layout);
+        }
+    }
+
+    pub(crate) fn end_render_pass(&self, cmd: &NullCommandBuffer) {
+        cmd.record(RecordedCommand::EndRenderPass {
+            framebuffer: self.id,
+        });
+    }
+
+    pub(crate) fn get_image(&self, index: u32) -> Arc<NullImage> {
+        self.images[index as usize].clone()
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
@@ -0,0 +1,166 @@
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::image::{ImageFormat, ImageLayout, MVImageCreateInfo};
+use crate::render::backend::null::buffer::NullBuffer;
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
+use crate::render::backend::Extent2D;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+pub struct NullImage {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    format: ImageFormat,
+    size: Extent2D,
+    layer_count: u32,
+    mip_level_count: u32,
+    layout: Mutex<ImageLayout>,
+    data: Mutex<Vec<u8>>,
+}
+
+impl NullImage {
+    pub(crate) fn new(device: Arc<NullDevice>, create_info: MVImageCreateInfo) -> Self {
+        let byte_size = (create_info.format.get_data_size(create_info.size)
+            * create_info.layer_count as u64) as usize;
+
+        // Only the first mip level is stored, the others would only ever be sampled
+        let (data, layout) = match create_info.data {
+            // Same as the vulkan backend, uploaded images end up ready for sampling
+            Some(mut data) => {
+                data.resize(byte_size, 0);
+                (data, ImageLayout::ShaderReadOnlyOptimal)
+            }
+            None => (vec![0; byte_size], ImageLayout::Undefined),
+        };
+
+        Self {
+            device,
+            id: super::next_id(),
+            format: create_info.format,
+            size: create_info.size,
+            layer_count: create_info.layer_count,
+            mip_level_count: create_info.mip_level_count,
+            layout: Mutex::new(layout),
+            data: Mutex::new(data),
+        }
+    }
+
+    pub(crate) fn from_format(
+        device: Arc<NullDevice>,
+        format: ImageFormat,
+        size: Extent2D,
+    ) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+            format,
+            size,
+            layer_count: 1,
+            mip_level_count: 1,
+            layout: Mutex::new(ImageLayout::Undefined),
+            data: Mutex::new(vec![0; format.get_data_size(size) as usize]),
+        }
+    }
+
+    pub(crate) fn transition_layout(
+        &self,
+        new_layout: ImageLayout,
+        cmd: Option<&NullCommandBuffer>,
+    ) {
+        let old_layout = std::mem::replace(&mut *self.layout.lock(), new_layout);
+
+        if let Some(cmd) = cmd {
+            cmd.record(RecordedCommand::PipelineBarrier {
+                image: self.id,
+                old_layout,
+                new_layout,
+            });
+        }
+    }
+
+    pub(crate) fn copy_buffer_to_image(
+        &self,
+        buffer: &NullBuffer,
+        cmd: Option<&NullCommandBuffer>,
+    ) {
+        let mut data = self.data.lock();
+        let size = (data.len() as u64).min(buffer.get_size());
+        data[..size as usize].copy_from_slice(&buffer.read_from_buffer(size, 0));
+
+        if let Some(cmd) = cmd {
+            cmd.record(RecordedCommand::CopyBufferToImage {
+                buffer: buffer.get_id(),
+                image: self.id,
+            });
+        }
+    }
+
+    pub(crate) fn copy_image_to_buffer(
+        &self,
+        buffer: &NullBuffer,
+        cmd: Option<&NullCommandBuffer>,
+    ) {
+        let data = self.data.lock();
+        let size = (data.len() as u64).min(buffer.get_size());
+        buffer.write_to_buffer(&data[..size as usize], 0, None);
+
+        if let Some(cmd) = cmd {
+            cmd.record(RecordedCommand::CopyImageToBuffer {
+                image: self.id,
+                buffer: buffer.get_id(),
+            });
+        }
+    }
+
+    pub(crate) fn generate_mipmaps(&self, cmd: Option<&NullCommandBuffer>) {
+        if self.mip_level_count < 2 {
+            log::error!("Cannot generate mipmaps for an image with a single mip level");
+            panic!();
+        }
+        if self.format.is_compressed() {
+            log::error!("Cannot generate mipmaps for compressed format {:?}", self.format);
+            panic!();
+        }
+        if let Some(cmd) = cmd {
+            cmd.record(RecordedCommand::GenerateMipmaps {
+                image: self.id,
+                levels: self.mip_level_count,
+            });
+        }
+        // Same as the vulkan backend, every level ends up ready for sampling
+        self.set_layout(ImageLayout::ShaderReadOnlyOptimal);
+    }
+
+    pub(crate) fn read_pixels(&self) -> Vec<u8> {
+        self.data.lock().clone()
+    }
+
+    pub(crate) fn get_format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub(crate) fn get_extent(&self) -> Extent2D {
+        self.size
+    }
+
+    pub(crate) fn get_mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    pub(crate) fn get_layer_count(&self) -> u32 {
+        self.layer_count
+    }
+
+    pub(crate) fn get_layout(&self) -> ImageLayout {
+        *self.layout.lock()
+    }
+
+    pub(crate) fn set_layout(&self, layout: ImageLayout) {
+        *self.layout.lock() = layout;
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
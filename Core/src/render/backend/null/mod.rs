@@ -0,0 +1,18 @@
+//! CPU only backend that doesn't talk to any gpu. Objects are plain records and command
+//! buffers keep a list of everything recorded into them, which makes it possible to test
+//! higher level code without a Vulkan driver.
+
+pub(crate) mod buffer;
+pub(crate) mod command_buffer;
+pub(crate) mod descriptor_set;
+pub(crate) mod device;
+pub(crate) mod framebuffer;
+pub(crate) mod image;
+pub(crate) mod pipeline;
+pub(crate) mod push_constant;
+pub(crate) mod sampler;
+pub(crate) mod shader;
+
+pub(crate) fn next_id() -> u64 {
+    mvutils::utils::next_id("MVCore::NullBackend")
+}
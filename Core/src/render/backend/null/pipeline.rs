@@ -0,0 +1,31 @@
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
+use crate::render::backend::pipeline::{Graphics, PipelineType};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+pub struct NullPipeline<Type: PipelineType = Graphics> {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    phantom: PhantomData<Type>,
+}
+
+impl<Type: PipelineType> NullPipeline<Type> {
+    pub(crate) fn new(device: Arc<NullDevice>) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn bind(&self, cmd: &NullCommandBuffer) {
+        cmd.record(RecordedCommand::BindPipeline { pipeline: self.id });
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
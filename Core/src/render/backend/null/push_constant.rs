@@ -0,0 +1,34 @@
+use crate::render::backend::command_buffer::RecordedCommand;
+use crate::render::backend::null::command_buffer::NullCommandBuffer;
+use crate::render::backend::null::device::NullDevice;
+use std::sync::Arc;
+
+pub struct NullPushConstant<T: Sized> {
+    device: Arc<NullDevice>,
+    value: T,
+}
+
+impl<T: Sized> NullPushConstant<T> {
+    pub(crate) fn new(device: Arc<NullDevice>, value: T) -> Self {
+        Self { device, value }
+    }
+
+    pub(crate) fn push(&self, cmd: &NullCommandBuffer, pipeline: u64) {
+        cmd.record(RecordedCommand::PushConstant {
+            pipeline,
+            size: std::mem::size_of::<T>() as u32,
+        });
+    }
+
+    pub(crate) fn data(&self) -> &T {
+        &self.value
+    }
+
+    pub(crate) fn data_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    pub(crate) fn replace(&mut self, data: T) {
+        self.value = data;
+    }
+}
@@ -0,0 +1,20 @@
+use crate::render::backend::null::device::NullDevice;
+use std::sync::Arc;
+
+pub struct NullSampler {
+    device: Arc<NullDevice>,
+    id: u64,
+}
+
+impl NullSampler {
+    pub(crate) fn new(device: Arc<NullDevice>) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+        }
+    }
+
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+}
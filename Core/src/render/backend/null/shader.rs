@@ -0,0 +1,30 @@
+use crate::render::backend::null::device::NullDevice;
+use crate::render::backend::shader::{MVShaderCreateInfo, ShaderStage};
+use std::sync::Arc;
+
+pub struct NullShader {
+    device: Arc<NullDevice>,
+
+    id: u64,
+    stage: ShaderStage,
+    code: Vec<u32>,
+}
+
+impl NullShader {
+    pub(crate) fn new(device: Arc<NullDevice>, create_info: MVShaderCreateInfo) -> Self {
+        Self {
+            device,
+            id: super::next_id(),
+            stage: create_info.stage,
+            code: create_info.code,
+        }
+    }
+
+    pub(crate) fn get_stage(&self) -> &ShaderStage {
+        &self.stage
+    }
+
+    pub(crate) fn get_code_size(&self) -> u64 {
+        (self.code.len() * 4) as u64
+    }
+}
@@ -5,8 +5,10 @@ use crate::render::backend::descriptor_set::DescriptorSetLayout;
 use crate::render::backend::device::Device;
 use crate::render::backend::Extent2D;
 use crate::render::backend::framebuffer::Framebuffer;
+use crate::render::backend::null::pipeline::NullPipeline;
 use crate::render::backend::shader::{Shader, ShaderStage};
 use crate::render::backend::vulkan::pipeline::VkPipeline;
+use ash::vk::Handle;
 
 pub trait PipelineType {}
 
@@ -93,6 +95,21 @@ pub enum Pipeline<Type: PipelineType = Graphics> {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(NullPipeline<Type>),
+}
+
+impl<Type: PipelineType> Pipeline<Type> {
+    /// Unique id of the pipeline, on vulkan this is the raw handle.
+    pub fn get_id(&self) -> u64 {
+        match self {
+            Pipeline::Vulkan(pipeline) => pipeline.get_handle().as_raw(),
+            #[cfg(target_os = "macos")]
+            Pipeline::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Pipeline::DirectX => unimplemented!(),
+            Pipeline::Null(pipeline) => pipeline.get_id(),
+        }
+    }
 }
 
 impl Pipeline {
@@ -105,6 +122,7 @@ impl Pipeline {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => Pipeline::Null(NullPipeline::new(device)),
         }
     }
 
@@ -115,6 +133,7 @@ impl Pipeline {
             Pipeline::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Pipeline::DirectX => unimplemented!(),
+            Pipeline::Null(pipeline) => pipeline.bind(command_buffer.as_null()),
         }
     }
 }
@@ -129,6 +148,7 @@ impl Pipeline<Compute> {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => Pipeline::Null(NullPipeline::new(device)),
         }
     }
 
@@ -139,6 +159,7 @@ impl Pipeline<Compute> {
             Pipeline::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Pipeline::DirectX => unimplemented!(),
+            Pipeline::Null(pipeline) => pipeline.bind(command_buffer.as_null()),
         }
     }
 }
@@ -154,6 +175,7 @@ impl Pipeline<RayTracing> {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => Pipeline::Null(NullPipeline::new(device)),
         }
     }
 
@@ -164,6 +186,7 @@ impl Pipeline<RayTracing> {
             Pipeline::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Pipeline::DirectX => unimplemented!(),
+            Pipeline::Null(pipeline) => pipeline.bind(command_buffer.as_null()),
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::render::backend::command_buffer::CommandBuffer;
 use crate::render::backend::device::Device;
+use crate::render::backend::null::push_constant::NullPushConstant;
 use crate::render::backend::pipeline::{Pipeline, PipelineType};
 use crate::render::backend::shader::ShaderStage;
 use crate::render::backend::vulkan::push_constant::VkPushConstant;
@@ -10,6 +11,7 @@ pub enum PushConstant<T: Sized> {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(NullPushConstant<T>),
 }
 
 pub struct MVPushConstantCreateInfo<T: Sized> {
@@ -25,10 +27,13 @@ impl<T: Sized> PushConstant<T> {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => {
+                PushConstant::Null(NullPushConstant::new(device, create_info.value))
+            }
         }
     }
 
-    pub fn push<Type: PipelineType>(&self, cmd: &CommandBuffer, pipeline: Pipeline<Type>) {
+    pub fn push<Type: PipelineType>(&self, cmd: &CommandBuffer, pipeline: &Pipeline<Type>) {
         match self {
             PushConstant::Vulkan(push_constant) => {
                 push_constant.push(cmd.as_vulkan(), pipeline.as_vulkan())
@@ -37,6 +42,9 @@ impl<T: Sized> PushConstant<T> {
             PushConstant::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             PushConstant::DirectX => unimplemented!(),
+            PushConstant::Null(push_constant) => {
+                push_constant.push(cmd.as_null(), pipeline.get_id())
+            }
         }
     }
 
@@ -47,6 +55,7 @@ impl<T: Sized> PushConstant<T> {
             PushConstant::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             PushConstant::DirectX => unimplemented!(),
+            PushConstant::Null(push_constant) => push_constant.data(),
         }
     }
 
@@ -57,6 +66,7 @@ impl<T: Sized> PushConstant<T> {
             PushConstant::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             PushConstant::DirectX => unimplemented!(),
+            PushConstant::Null(push_constant) => push_constant.data_mut(),
         }
     }
 
@@ -67,6 +77,7 @@ impl<T: Sized> PushConstant<T> {
             PushConstant::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             PushConstant::DirectX => unimplemented!(),
+            PushConstant::Null(push_constant) => push_constant.replace(new_data),
         }
     }
 }
@@ -1,4 +1,5 @@
 use crate::render::backend::device::Device;
+use crate::render::backend::null::sampler::NullSampler;
 use crate::render::backend::vulkan::sampler::VkSampler;
 use mvcore_proc_macro::graphics_item;
 
@@ -22,11 +23,19 @@ pub enum MipmapMode {
     Linear,
 }
 
+/// Use as [`MVSamplerCreateInfo::max_lod`] to allow sampling every mip level.
+pub const LOD_CLAMP_NONE: f32 = 1000.0;
+
 pub struct MVSamplerCreateInfo {
     pub address_mode: SamplerAddressMode,
     pub filter_mode: Filter,
     pub mipmap_mode: MipmapMode,
-    pub anisotropy: bool,
+    /// Maximum anisotropy, clamped to what the device supports. `None` disables anisotropic filtering.
+    pub anisotropy: Option<f32>,
+    /// Added to the mip level the gpu picks, positive values select smaller levels.
+    pub lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
 
     pub label: Option<String>,
 }
@@ -38,6 +47,7 @@ pub enum Sampler {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(NullSampler),
 }
 
 impl Sampler {
@@ -48,6 +58,7 @@ impl Sampler {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => Sampler::Null(NullSampler::new(device)),
         }
     }
 }
@@ -1,8 +1,11 @@
 use crate::render::backend::device::Device;
+use crate::render::backend::null::shader::NullShader;
 use crate::render::backend::vulkan::shader::VkShader;
 use bitflags::bitflags;
 use mvcore_proc_macro::graphics_item;
-use shaderc::ShaderKind;
+use shaderc::{OptimizationLevel, ResolvedInclude, ShaderKind, TargetEnv};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
 use std::sync::Arc;
 
 pub struct MVShaderCreateInfo {
@@ -75,6 +78,7 @@ pub enum Shader {
     Metal,
     #[cfg(target_os = "windows")]
     DirectX,
+    Null(Arc<NullShader>),
 }
 
 impl Shader {
@@ -87,6 +91,170 @@ impl Shader {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(device) => Shader::Null(NullShader::new(device, create_info).into()),
         }
     }
+
+    /// Compiles glsl source into a shader module, doesn't need a [`Renderer`] so it can be used
+    /// with headless devices. Panics if the source doesn't compile, see [`Shader::try_compile`].
+    ///
+    /// [`Renderer`]: crate::render::renderer::Renderer
+    pub fn compile(
+        device: Device,
+        data: &str,
+        kind: ShaderKind,
+        name: Option<String>,
+        defines: &[String],
+    ) -> Shader {
+        Self::try_compile(device, data, kind, name, defines).unwrap_or_else(|e| {
+            log::error!("Failed to compile shader, error: {e}");
+            panic!();
+        })
+    }
+
+    /// Compiles glsl source into a shader module. `#include "file"` directives are resolved
+    /// relative to the file that contains them, so `name` should be the path of the source
+    /// when it was loaded from disk.
+    pub fn try_compile(
+        device: Device,
+        data: &str,
+        kind: ShaderKind,
+        name: Option<String>,
+        defines: &[String],
+    ) -> Result<Shader, ShaderCompileError> {
+        Self::try_compile_with(device, data, kind, name, defines, |path| {
+            std::fs::read_to_string(path)
+        })
+    }
+
+    /// Like [`Shader::try_compile`], but included files are read with `read`, for example from
+    /// the asset [`VirtualFileSystem`](crate::asset::vfs::VirtualFileSystem).
+    pub fn try_compile_with(
+        device: Device,
+        data: &str,
+        kind: ShaderKind,
+        name: Option<String>,
+        defines: &[String],
+        read: impl Fn(&str) -> std::io::Result<String>,
+    ) -> Result<Shader, ShaderCompileError> {
+        let code = Self::compile_spirv(data, kind, name.as_deref().unwrap_or_default(), defines, read)?;
+
+        Ok(Shader::new(
+            device,
+            MVShaderCreateInfo {
+                stage: kind.into(),
+                code,
+                label: name,
+            },
+        ))
+    }
+
+    /// Size of the SPIR-V code of the shader in bytes.
+    pub fn get_code_size(&self) -> u64 {
+        match self {
+            Shader::Vulkan(shader) => shader.get_code_size(),
+            #[cfg(target_os = "macos")]
+            Shader::Metal => unimplemented!(),
+            #[cfg(target_os = "windows")]
+            Shader::DirectX => unimplemented!(),
+            Shader::Null(shader) => shader.get_code_size(),
+        }
+    }
+
+    /// Compiles glsl source to SPIR-V without creating a shader module, used to cook shaders offline.
+    pub fn compile_spirv(
+        data: &str,
+        kind: ShaderKind,
+        name: &str,
+        defines: &[String],
+        read: impl Fn(&str) -> std::io::Result<String>,
+    ) -> Result<Vec<u32>, ShaderCompileError> {
+        let compiler = shaderc::Compiler::new().ok_or_else(|| ShaderCompileError {
+            file: name.to_string(),
+            line: None,
+            message: "Failed to create shader compiler".to_string(),
+        })?;
+        let mut options = shaderc::CompileOptions::new().ok_or_else(|| ShaderCompileError {
+            file: name.to_string(),
+            line: None,
+            message: "Failed to create shader compile options".to_string(),
+        })?;
+        options.set_optimization_level(OptimizationLevel::Performance);
+
+        for define in defines {
+            options.add_macro_definition(define.as_str(), None);
+        }
+        options.set_target_env(TargetEnv::Vulkan, ash::vk::API_VERSION_1_2);
+        options.set_include_callback(|requested, _, requesting, _| {
+            let path = Path::new(requesting)
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(requested);
+            let resolved_name = path.to_string_lossy().to_string();
+            read(&resolved_name)
+                .map(|content| ResolvedInclude {
+                    resolved_name,
+                    content,
+                })
+                .map_err(|e| format!("Failed to include {}: {e}", path.display()))
+        });
+
+        Ok(compiler
+            .compile_into_spirv(data, kind, name, "main", Some(&options))
+            .map_err(|e| ShaderCompileError::from_shaderc(name, e))?
+            .as_binary()
+            .to_vec())
+    }
+}
+
+/// Error of a shader that failed to compile. `file` and `line` point at the first error the
+/// compiler reported, `message` is its description.
+#[derive(Clone, Debug)]
+pub struct ShaderCompileError {
+    pub file: String,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl ShaderCompileError {
+    fn from_shaderc(name: &str, error: shaderc::Error) -> Self {
+        let shaderc::Error::CompilationError(_, log) = &error else {
+            return Self {
+                file: name.to_string(),
+                line: None,
+                message: error.to_string(),
+            };
+        };
+
+        // Errors look like "file:line: error: message", the file can contain colons itself
+        log.lines()
+            .find_map(|line| {
+                let (location, message) = line.split_once(": error: ")?;
+                let (file, line) = match location.rsplit_once(':') {
+                    Some((file, line)) if line.parse::<u32>().is_ok() => (file, line.parse().ok()),
+                    _ => (location, None),
+                };
+                Some(Self {
+                    file: file.to_string(),
+                    line,
+                    message: message.trim().to_string(),
+                })
+            })
+            .unwrap_or_else(|| Self {
+                file: name.to_string(),
+                line: None,
+                message: log.trim().to_string(),
+            })
+    }
 }
+
+impl Display for ShaderCompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ShaderCompileError {}
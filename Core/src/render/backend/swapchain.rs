@@ -31,6 +31,10 @@ impl Swapchain {
             Device::Metal => unimplemented!(),
             #[cfg(target_os = "windows")]
             Device::DirectX => unimplemented!(),
+            Device::Null(_) => {
+                log::error!("The null backend can't present, there is no swapchain");
+                panic!();
+            }
         }
     }
 
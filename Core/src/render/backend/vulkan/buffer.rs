@@ -157,6 +157,49 @@ impl VkBuffer {
         }
     }
 
+    pub(crate) fn read_from_buffer(
+        &mut self,
+        size: ash::vk::DeviceSize,
+        offset: ash::vk::DeviceSize,
+    ) -> Vec<u8> {
+        if self
+            .memory_properties
+            .contains(ash::vk::MemoryPropertyFlags::DEVICE_LOCAL)
+        {
+            log::error!("Can't read from device local buffer!");
+            panic!();
+        }
+
+        if offset + size > self.buffer_size {
+            log::error!(
+                "Buffer read out of bounds, size: {}, requested: {}",
+                self.buffer_size,
+                offset + size
+            );
+            panic!();
+        }
+
+        let need_to_map_buffer = self.mapped.is_null();
+        if need_to_map_buffer {
+            self.map();
+        }
+
+        let mut data = vec![0u8; size as usize];
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.mapped.offset(offset as isize),
+                data.as_mut_ptr(),
+                size as usize,
+            )
+        };
+
+        if need_to_map_buffer {
+            self.unmap()
+        }
+
+        data
+    }
+
     pub(crate) fn get_descriptor_info(
         &self,
         size: ash::vk::DeviceSize,
@@ -302,3 +345,8 @@ impl Drop for VkBuffer {
         );
     }
 }
+
+// SAFETY: The mapped pointer is only read and written through `&mut self`, everything else is plain
+// Vulkan handles and the memory block of the allocator.
+unsafe impl Send for VkBuffer {}
+unsafe impl Sync for VkBuffer {}
@@ -190,9 +190,9 @@ impl VkCommandBuffer {
             self.device.get_device().cmd_blit_image(
                 self.handle,
                 src_image.handle,
-                src_image.layout.get_val(),
+                src_image.get_layout(),
                 dst_image.handle,
-                dst_image.layout.get_val(),
+                dst_image.get_layout(),
                 &[blit],
                 ash::vk::Filter::LINEAR,
             );
@@ -1,10 +1,12 @@
 use crate::render::backend::device::{Extensions, MVDeviceCreateInfo};
+use crate::render::backend::image::{ImageFormat, ImageLimits, ImageUsage};
 use crate::render::backend::to_ascii_cstring;
 use gpu_alloc::Config;
-use hashbrown::HashSet;
+use hashbrown::{HashMap, HashSet};
 use mvutils::version::Version;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use std::ffi::{c_void, CStr, CString};
+use std::thread::ThreadId;
 use winit::raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 
 pub struct VkDevice {
@@ -13,12 +15,21 @@ pub struct VkDevice {
     instance: ash::Instance,
     physical_device: ash::vk::PhysicalDevice,
     surface_extension: ash::extensions::khr::Surface,
-    swapchain_extension: ash::extensions::khr::Swapchain,
-    surface: ash::vk::SurfaceKHR,
-    properties: ash::vk::PhysicalDeviceProperties2,
+    swapchain_extension: Option<ash::extensions::khr::Swapchain>,
+    surface: Option<ash::vk::SurfaceKHR>,
+    /// Without the extension chain of `PhysicalDeviceProperties2`, its pointer would keep the device from being Send and Sync.
+    properties: ash::vk::PhysicalDeviceProperties,
+    features: ash::vk::PhysicalDeviceFeatures,
     device: ash::Device,
     command_pools: CommandPools,
     queues: Queues,
+    /// Pools of the threads that record single time commands, replacing the shared pools they are
+    /// asked for. A pool may only be used by one thread at a time, and loader threads upload while
+    /// the render thread records into the shared pools. Pools of finished threads are only
+    /// destroyed with the device.
+    thread_command_pools: Mutex<HashMap<(ThreadId, ash::vk::CommandPool), ash::vk::CommandPool>>,
+    /// Held for every submission to and wait on the queues, they can be the same queue.
+    queue_lock: Mutex<()>,
 
     available_present_modes: Vec<ash::vk::PresentModeKHR>,
 
@@ -59,12 +70,13 @@ impl From<MVDeviceCreateInfo> for CreateInfo {
 struct Queues {
     graphics_queue: ash::vk::Queue,
     compute_queue: ash::vk::Queue,
-    present_queue: ash::vk::Queue,
+    present_queue: Option<ash::vk::Queue>,
 }
 
 struct CommandPools {
     graphics_command_pool: ash::vk::CommandPool,
     compute_command_pool: ash::vk::CommandPool,
+    queue_family_index: u32,
 }
 
 pub(crate) struct QueueIndices {
@@ -74,10 +86,10 @@ pub(crate) struct QueueIndices {
 }
 
 impl QueueIndices {
-    fn is_complete(&self) -> bool {
+    fn is_complete(&self, headless: bool) -> bool {
         self.graphics_queue_index.is_some()
             && self.compute_queue_index.is_some()
-            && self.present_queue_index.is_some()
+            && (headless || self.present_queue_index.is_some())
     }
 
     fn create() -> Self {
@@ -90,17 +102,18 @@ impl QueueIndices {
 }
 
 impl VkDevice {
-    pub(crate) fn new(create_info: CreateInfo, window: &winit::window::Window) -> Self {
+    pub(crate) fn new(create_info: CreateInfo, window: Option<&winit::window::Window>) -> Self {
         let entry: ash::Entry = unsafe { ash::Entry::load() }.unwrap();
+        let headless = window.is_none();
 
-        let instance = Self::create_instance(&entry, &create_info);
+        let instance = Self::create_instance(&entry, &create_info, headless);
 
         #[cfg(debug_assertions)]
         let debug_utils = ash::extensions::ext::DebugUtils::new(&entry, &instance);
         #[cfg(debug_assertions)]
         let debug_messenger = Self::create_debug_messenger(&debug_utils, &instance);
 
-        let surface = unsafe {
+        let surface = window.map(|window| unsafe {
             #[allow(deprecated)]
             use winit::raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 
@@ -109,42 +122,42 @@ impl VkDevice {
                 window.raw_display_handle().unwrap(),
                 #[allow(deprecated)]
                 window.raw_window_handle().unwrap(),
-                &Self::instance_extensions(&entry),
+                &Self::instance_extensions(&entry, headless),
                 &entry,
                 &instance,
             )
-        };
+        });
 
         let surface_khr = ash::extensions::khr::Surface::new(&entry, &instance);
 
-        let extensions = Self::get_required_extensions(&create_info.device_extensions);
+        let extensions = Self::get_required_extensions(&create_info.device_extensions, headless);
         let physical_device =
-            Self::pick_physical_device(&surface, &surface_khr, &instance, true, &extensions);
+            Self::pick_physical_device(surface, &surface_khr, &instance, true, &extensions);
 
         let properties = Self::get_physical_device_properties(&instance, &physical_device);
+        let features = Self::get_enabled_features(&instance, &physical_device);
 
         let (device, queues) = Self::create_logical_device(
             &surface_khr,
-            &surface,
+            surface,
             &instance,
             &physical_device,
             &create_info.device_extensions,
+            features,
         );
-        let command_pools = Self::create_command_pools(
-            &surface_khr,
-            &surface,
-            &instance,
-            &physical_device,
-            &device,
-        );
+        let command_pools =
+            Self::create_command_pools(&surface_khr, surface, &instance, &physical_device, &device);
 
-        let available_present_modes = unsafe {
-            surface_khr.get_physical_device_surface_present_modes(physical_device, surface)
-        }
-        .unwrap_or_else(|e| {
-            log::error!("vkGetPhysicalDeviceSurfacePresentModes failed, error: {e}");
-            panic!()
-        });
+        let available_present_modes = match surface {
+            Some(surface) => unsafe {
+                surface_khr.get_physical_device_surface_present_modes(physical_device, surface)
+            }
+            .unwrap_or_else(|e| {
+                log::error!("vkGetPhysicalDeviceSurfacePresentModes failed, error: {e}");
+                panic!()
+            }),
+            None => Vec::new(),
+        };
 
         let vsync_present_mode = [ash::vk::PresentModeKHR::FIFO]
             .into_iter()
@@ -160,7 +173,8 @@ impl VkDevice {
         .find(|mode| available_present_modes.contains(mode))
         .unwrap_or(ash::vk::PresentModeKHR::FIFO);
 
-        let swapchain_khr = ash::extensions::khr::Swapchain::new(&instance, &device);
+        let swapchain_khr =
+            (!headless).then(|| ash::extensions::khr::Swapchain::new(&instance, &device));
         let (allocator, valid_memory_types) = Self::create_allocator(&instance, physical_device);
 
         Self {
@@ -174,10 +188,13 @@ impl VkDevice {
             swapchain_extension: swapchain_khr,
             surface,
             properties,
+            features,
             command_pools,
             physical_device,
             device,
             queues,
+            thread_command_pools: Mutex::new(HashMap::new()),
+            queue_lock: Mutex::new(()),
             vsync_present_mode,
             no_vsync_present_mode,
             available_present_modes,
@@ -188,7 +205,7 @@ impl VkDevice {
 
     fn create_command_pools(
         surface_khr: &ash::extensions::khr::Surface,
-        surface: &ash::vk::SurfaceKHR,
+        surface: Option<ash::vk::SurfaceKHR>,
         instance: &ash::Instance,
         physical_device: &ash::vk::PhysicalDevice,
         device: &ash::Device,
@@ -216,10 +233,11 @@ impl VkDevice {
         CommandPools {
             graphics_command_pool: graphics_pool,
             compute_command_pool: compute_pool,
+            queue_family_index: indices.graphics_queue_index.unwrap(),
         }
     }
 
-    fn instance_extensions(entry: &ash::Entry) -> Vec<&'static CStr> {
+    fn instance_extensions(entry: &ash::Entry, headless: bool) -> Vec<&'static CStr> {
         let instance_extensions = {
             log::trace!("vkEnumerateInstanceExtensionProperties");
             entry.enumerate_instance_extension_properties(None)
@@ -229,27 +247,29 @@ impl VkDevice {
             panic!()
         });
 
-        #[cfg(not(target_os = "windows"))]
-        let mut extensions = vec![ash::extensions::khr::Surface::name()];
-        #[cfg(target_os = "windows")]
-        let mut extensions = vec![
-            ash::extensions::khr::Surface::name(),
-            ash::extensions::khr::Win32Surface::name(),
-        ];
+        let mut extensions = vec![];
 
         #[cfg(target_os = "macos")]
-        {
+        extensions.push(ash::vk::KhrPortabilityEnumerationFn::name());
+
+        // A headless device never creates a surface, so there is no need to ask for (and warn
+        // about missing) windowing extensions, which software drivers often don't expose
+        if !headless {
+            extensions.push(ash::extensions::khr::Surface::name());
+
+            #[cfg(target_os = "windows")]
+            extensions.push(ash::extensions::khr::Win32Surface::name());
+            #[cfg(target_os = "macos")]
             extensions.push(ash::extensions::ext::MetalSurface::name());
-            extensions.push(ash::vk::KhrPortabilityEnumerationFn::name());
-        }
-        #[cfg(all(unix, not(target_os = "macos")))]
-        {
-            extensions.push(ash::extensions::khr::XlibSurface::name());
-            extensions.push(ash::extensions::khr::XcbSurface::name());
-            extensions.push(ash::extensions::khr::WaylandSurface::name());
-        }
+            #[cfg(all(unix, not(target_os = "macos")))]
+            {
+                extensions.push(ash::extensions::khr::XlibSurface::name());
+                extensions.push(ash::extensions::khr::XcbSurface::name());
+                extensions.push(ash::extensions::khr::WaylandSurface::name());
+            }
 
-        extensions.push(ash::vk::ExtSwapchainColorspaceFn::name());
+            extensions.push(ash::vk::ExtSwapchainColorspaceFn::name());
+        }
 
         #[cfg(debug_assertions)]
         extensions.push(ash::extensions::ext::DebugUtils::name());
@@ -305,7 +325,11 @@ impl VkDevice {
         (allocator, valid_memory_types)
     }
 
-    fn create_instance(entry: &ash::Entry, create_info: &CreateInfo) -> ash::Instance {
+    fn create_instance(
+        entry: &ash::Entry,
+        create_info: &CreateInfo,
+        headless: bool,
+    ) -> ash::Instance {
         log::info!("Creating Instance");
         let app_create_info = ash::vk::ApplicationInfo::builder()
             .engine_name(create_info.engine_name.as_c_str())
@@ -315,7 +339,7 @@ impl VkDevice {
             .api_version(ash::vk::API_VERSION_1_2);
 
         // Instance Extensions
-        let extensions_ptr = Self::instance_extensions(entry)
+        let extensions_ptr = Self::instance_extensions(entry, headless)
             .into_iter()
             .map(|s| s.as_ptr())
             .collect::<Vec<_>>();
@@ -608,30 +632,33 @@ impl VkDevice {
     fn get_physical_device_properties(
         instance: &ash::Instance,
         physical_device: &ash::vk::PhysicalDevice,
-    ) -> ash::vk::PhysicalDeviceProperties2 {
+    ) -> ash::vk::PhysicalDeviceProperties {
         let mut properties = ash::vk::PhysicalDeviceProperties2::default();
         unsafe { instance.get_physical_device_properties2(*physical_device, &mut properties) };
 
-        properties
+        properties.properties
     }
 
     fn check_surface_support(
         surface_extension: &ash::extensions::khr::Surface,
-        surface: &ash::vk::SurfaceKHR,
+        surface: Option<ash::vk::SurfaceKHR>,
         physical_device: &ash::vk::PhysicalDevice,
     ) -> bool {
+        // Nothing to present to, so every device is good enough
+        let Some(surface) = surface else { return true };
+
         // Get Surface Capabilities
         //let capabilities = unsafe { surface_extension.get_physical_device_surface_capabilities(*physical_device, *surface) }.unwrap();
 
         // Get Surface Formats
         let formats = unsafe {
-            surface_extension.get_physical_device_surface_formats(*physical_device, *surface)
+            surface_extension.get_physical_device_surface_formats(*physical_device, surface)
         }
         .unwrap();
 
         // Get Presentation Modes
         let presentation_modes = unsafe {
-            surface_extension.get_physical_device_surface_present_modes(*physical_device, *surface)
+            surface_extension.get_physical_device_surface_present_modes(*physical_device, surface)
         }
         .unwrap();
 
@@ -640,7 +667,7 @@ impl VkDevice {
     }
 
     fn pick_physical_device(
-        surface: &ash::vk::SurfaceKHR,
+        surface: Option<ash::vk::SurfaceKHR>,
         surface_khr: &ash::extensions::khr::Surface,
         instance: &ash::Instance,
         prioritize_discrete: bool,
@@ -672,7 +699,7 @@ impl VkDevice {
         physical_device: &ash::vk::PhysicalDevice,
         instance: &ash::Instance,
         surface_khr: &ash::extensions::khr::Surface,
-        surface: &ash::vk::SurfaceKHR,
+        surface: Option<ash::vk::SurfaceKHR>,
         extensions: &[&CStr],
     ) -> bool {
         let indices = Self::get_queue_indices(surface_khr, surface, physical_device, instance);
@@ -681,12 +708,12 @@ impl VkDevice {
 
         let surface_support = Self::check_surface_support(surface_khr, surface, physical_device);
 
-        indices.is_complete() && are_extensions_supported && surface_support
+        indices.is_complete(surface.is_none()) && are_extensions_supported && surface_support
     }
 
     fn get_queue_indices(
         surface_khr: &ash::extensions::khr::Surface,
-        surface: &ash::vk::SurfaceKHR,
+        surface: Option<ash::vk::SurfaceKHR>,
         physical_device: &ash::vk::PhysicalDevice,
         instance: &ash::Instance,
     ) -> QueueIndices {
@@ -711,37 +738,48 @@ impl VkDevice {
             {
                 queue_indices.compute_queue_index = Some(queue.0 as u32);
             }
-            if unsafe {
-                surface_khr.get_physical_device_surface_support(
-                    *physical_device,
-                    queue.0 as u32,
-                    *surface,
-                )
-            }
-            .unwrap()
-            {
-                queue_indices.present_queue_index = Some(queue.0 as u32);
+            if let Some(surface) = surface {
+                if unsafe {
+                    surface_khr.get_physical_device_surface_support(
+                        *physical_device,
+                        queue.0 as u32,
+                        surface,
+                    )
+                }
+                .unwrap()
+                {
+                    queue_indices.present_queue_index = Some(queue.0 as u32);
+                }
             }
         }
 
+        // Software implementations like lavapipe only expose a single queue family,
+        // fall back to the graphics queue for compute work in that case
+        if queue_indices.compute_queue_index.is_none() {
+            queue_indices.compute_queue_index = queue_indices.graphics_queue_index;
+        }
+
         queue_indices
     }
 
     fn create_logical_device(
         surface_khr: &ash::extensions::khr::Surface,
-        surface: &ash::vk::SurfaceKHR,
+        surface: Option<ash::vk::SurfaceKHR>,
         instance: &ash::Instance,
         physical_device: &ash::vk::PhysicalDevice,
         extensions: &Extensions,
+        enabled_features: ash::vk::PhysicalDeviceFeatures,
     ) -> (ash::Device, Queues) {
         let indices = Self::get_queue_indices(surface_khr, surface, physical_device, instance);
         let mut queue_create_infos: Vec<ash::vk::DeviceQueueCreateInfo> = Vec::new();
         let mut unique_queues: Vec<u32> = Vec::new();
         let mut set: HashSet<u32> = HashSet::new();
 
-        if !set.contains(&indices.present_queue_index.unwrap()) {
-            set.insert(indices.present_queue_index.unwrap());
-            unique_queues.push(indices.present_queue_index.unwrap());
+        if let Some(present_queue_index) = indices.present_queue_index {
+            if !set.contains(&present_queue_index) {
+                set.insert(present_queue_index);
+                unique_queues.push(present_queue_index);
+            }
         }
         if !set.contains(&indices.graphics_queue_index.unwrap()) {
             set.insert(indices.graphics_queue_index.unwrap());
@@ -759,13 +797,22 @@ impl VkDevice {
             queue_create_infos.push(*info);
         }
 
-        let extensions = Self::get_required_extensions(extensions)
+        let available_extensions =
+            unsafe { instance.enumerate_device_extension_properties(*physical_device) }.unwrap();
+        let mut extensions = Self::get_required_extensions(extensions, surface.is_none());
+        for extension in Self::get_optional_extensions() {
+            if available_extensions.iter().any(|available| {
+                unsafe { CStr::from_ptr(available.extension_name.as_ptr()) }.eq(extension)
+            }) {
+                extensions.push(extension);
+            }
+        }
+        let extensions = extensions
             .into_iter()
             .map(|s| s.as_ptr())
             .collect::<Vec<_>>();
 
-        let mut features = ash::vk::PhysicalDeviceFeatures2::builder();
-        features.features.geometry_shader = true as ash::vk::Bool32;
+        let mut features = ash::vk::PhysicalDeviceFeatures2::builder().features(enabled_features);
 
         let mut device_address = ash::vk::PhysicalDeviceBufferDeviceAddressFeaturesKHR::builder()
             .buffer_device_address(true);
@@ -794,8 +841,9 @@ impl VkDevice {
             unsafe { device.get_device_queue(indices.graphics_queue_index.unwrap(), 0) };
         let compute_queue =
             unsafe { device.get_device_queue(indices.compute_queue_index.unwrap(), 0) };
-        let present_queue =
-            unsafe { device.get_device_queue(indices.present_queue_index.unwrap(), 0) };
+        let present_queue = indices
+            .present_queue_index
+            .map(|index| unsafe { device.get_device_queue(index, 0) });
 
         (
             device,
@@ -807,6 +855,21 @@ impl VkDevice {
         )
     }
 
+    // Texture compression is enabled when available, which formats can be used is checked with
+    // `is_format_supported`
+    fn get_enabled_features(
+        instance: &ash::Instance,
+        physical_device: &ash::vk::PhysicalDevice,
+    ) -> ash::vk::PhysicalDeviceFeatures {
+        let supported = unsafe { instance.get_physical_device_features(*physical_device) };
+        ash::vk::PhysicalDeviceFeatures {
+            geometry_shader: true as ash::vk::Bool32,
+            texture_compression_bc: supported.texture_compression_bc,
+            texture_compression_astc_ldr: supported.texture_compression_astc_ldr,
+            ..Default::default()
+        }
+    }
+
     fn are_device_extensions_supported(
         physical_device: &ash::vk::PhysicalDevice,
         instance: &ash::Instance,
@@ -836,19 +899,20 @@ impl VkDevice {
         true
     }
 
-    fn get_required_extensions(requested: &Extensions) -> Vec<&'static CStr> {
+    fn get_required_extensions(requested: &Extensions, headless: bool) -> Vec<&'static CStr> {
         let mut extensions = vec![
-            ash::vk::ExtMemoryPriorityFn::name(),
             ash::vk::ExtImageRobustnessFn::name(),
-            ash::vk::ExtPageableDeviceLocalMemoryFn::name(),
-            ash::vk::KhrSwapchainFn::name(),
-            ash::vk::KhrSwapchainMutableFormatFn::name(),
             ash::vk::ExtRobustness2Fn::name(),
             ash::vk::KhrSynchronization2Fn::name(),
             #[cfg(target_os = "macos")]
             ash::vk::KhrPortabilitySubsetFn::name(),
         ];
 
+        if !headless {
+            extensions.push(ash::vk::KhrSwapchainFn::name());
+            extensions.push(ash::vk::KhrSwapchainMutableFormatFn::name());
+        }
+
         if requested.contains(Extensions::DRAW_INDIRECT_COUNT) {
             extensions.push(ash::vk::KhrDrawIndirectCountFn::name());
         }
@@ -871,6 +935,14 @@ impl VkDevice {
         extensions
     }
 
+    // Enabled when available, software implementations like lavapipe don't expose these
+    fn get_optional_extensions() -> Vec<&'static CStr> {
+        vec![
+            ash::vk::ExtMemoryPriorityFn::name(),
+            ash::vk::ExtPageableDeviceLocalMemoryFn::name(),
+        ]
+    }
+
     #[cfg(debug_assertions)]
     pub fn begin_debug_label(&self, cmd: &ash::vk::CommandBuffer, name: &CStr, color: &[f32; 4]) {
         let label_info = ash::vk::DebugUtilsLabelEXT::builder()
@@ -907,7 +979,14 @@ impl VkDevice {
     }
 
     pub fn get_surface(&self) -> ash::vk::SurfaceKHR {
-        self.surface
+        self.surface.unwrap_or_else(|| {
+            log::error!("Headless device does not have a surface");
+            panic!()
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
     }
 
     pub fn get_surface_khr(&self) -> &ash::extensions::khr::Surface {
@@ -933,14 +1012,17 @@ impl VkDevice {
     pub(crate) fn get_indices(&self) -> QueueIndices {
         Self::get_queue_indices(
             &self.surface_extension,
-            &self.surface,
+            self.surface,
             &self.physical_device,
             &self.instance,
         )
     }
 
     pub fn get_swapchain_extension(&self) -> &ash::extensions::khr::Swapchain {
-        &self.swapchain_extension
+        self.swapchain_extension.as_ref().unwrap_or_else(|| {
+            log::error!("Headless device does not support swapchains");
+            panic!()
+        })
     }
 
     pub fn get_instance(&self) -> &ash::Instance {
@@ -970,6 +1052,54 @@ impl VkDevice {
         ash::vk::Format::UNDEFINED // return undefined if none are supported
     }
 
+    /// Whether images of `format` can be created with optimal tiling for `usage`, and blitted between
+    /// mip levels if `blit` is set. Compressed formats also need their texture compression feature.
+    pub fn is_format_supported(&self, format: ImageFormat, usage: &ImageUsage, blit: bool) -> bool {
+        let feature = match format {
+            ImageFormat::Bc1
+            | ImageFormat::Bc1Srgb
+            | ImageFormat::Bc2
+            | ImageFormat::Bc2Srgb
+            | ImageFormat::Bc3
+            | ImageFormat::Bc3Srgb
+            | ImageFormat::Bc4
+            | ImageFormat::Bc5
+            | ImageFormat::Bc6H
+            | ImageFormat::Bc7
+            | ImageFormat::Bc7Srgb => self.features.texture_compression_bc,
+            format if format.is_compressed() => self.features.texture_compression_astc_ldr,
+            _ => true as ash::vk::Bool32,
+        };
+        let mut features = ash::vk::FormatFeatureFlags::empty();
+        if usage.contains(ImageUsage::SAMPLED) {
+            features |= ash::vk::FormatFeatureFlags::SAMPLED_IMAGE;
+        }
+        if usage.contains(ImageUsage::STORAGE) {
+            features |= ash::vk::FormatFeatureFlags::STORAGE_IMAGE;
+        }
+        if usage.contains(ImageUsage::COLOR_ATTACHMENT) {
+            features |= ash::vk::FormatFeatureFlags::COLOR_ATTACHMENT;
+        }
+        if usage.contains(ImageUsage::DEPTH_STENCIL_ATTACHMENT) {
+            features |= ash::vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT;
+        }
+        if blit {
+            features |= ash::vk::FormatFeatureFlags::BLIT_SRC | ash::vk::FormatFeatureFlags::BLIT_DST;
+        }
+        feature != 0
+            && self.find_supported_formats(&[format.into()], ash::vk::ImageTiling::OPTIMAL, features)
+                != ash::vk::Format::UNDEFINED
+    }
+
+    pub(crate) fn get_image_limits(&self) -> ImageLimits {
+        let limits = &self.properties.limits;
+        ImageLimits {
+            max_dimension: limits.max_image_dimension2_d,
+            max_cube_dimension: limits.max_image_dimension_cube,
+            max_array_layers: limits.max_image_array_layers,
+        }
+    }
+
     pub(crate) fn allocate_buffer(
         &self,
         create_info: &ash::vk::BufferCreateInfo,
@@ -1096,12 +1226,29 @@ impl VkDevice {
         }
     }
 
+    /// The pool of the calling thread that replaces `pool` for single time commands.
+    fn thread_command_pool(&self, pool: ash::vk::CommandPool) -> ash::vk::CommandPool {
+        let key = (std::thread::current().id(), pool);
+        let mut pools = self.thread_command_pools.lock();
+        *pools.entry(key).or_insert_with(|| {
+            let pool_info = ash::vk::CommandPoolCreateInfo::builder()
+                .queue_family_index(self.command_pools.queue_family_index)
+                .flags(ash::vk::CommandPoolCreateFlags::TRANSIENT);
+            unsafe { self.device.create_command_pool(&pool_info, None) }.unwrap_or_else(|e| {
+                log::error!("Failed to create command pool, error: {e}");
+                panic!()
+            })
+        })
+    }
+
+    /// Single time commands can be recorded on any thread, but have to be ended on the thread that
+    /// began them.
     pub(crate) fn begin_single_time_command(
         &self,
         pool: ash::vk::CommandPool,
     ) -> ash::vk::CommandBuffer {
         let alloc_info = ash::vk::CommandBufferAllocateInfo::builder()
-            .command_pool(pool)
+            .command_pool(self.thread_command_pool(pool))
             .level(ash::vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(1);
 
@@ -1137,16 +1284,63 @@ impl VkDevice {
         let submit_info = ash::vk::SubmitInfo::builder().command_buffers(&cmd_vec);
 
         let vk_info = [*submit_info];
+        {
+            let _queues = self.lock_queues();
+            unsafe {
+                self.device
+                    .queue_submit(queue, &vk_info, ash::vk::Fence::null())
+            }
+            .expect("Failed to submit cmd buffer");
+
+            // Wait for GPU
+            unsafe { self.device.queue_wait_idle(queue) }.unwrap();
+        }
+
         unsafe {
             self.device
-                .queue_submit(queue, &vk_info, ash::vk::Fence::null())
+                .free_command_buffers(self.thread_command_pool(pool), &cmd_vec)
+        };
+    }
+
+    /// Same as [`VkDevice::end_single_time_command`], but only waits for this submission to
+    /// finish instead of the whole queue.
+    pub(crate) fn end_single_time_command_fenced(
+        &self,
+        command_buffer: ash::vk::CommandBuffer,
+        pool: ash::vk::CommandPool,
+        queue: ash::vk::Queue,
+    ) {
+        unsafe { self.device.end_command_buffer(command_buffer) }.unwrap_or_else(|e| {
+            log::error!("Failed to end command buffer, error: {e}");
+            panic!();
+        });
+
+        let fence_info = ash::vk::FenceCreateInfo::builder();
+        let fence = unsafe { self.device.create_fence(&fence_info, None) }.unwrap_or_else(|e| {
+            log::error!("Failed to create fence, error: {e}");
+            panic!();
+        });
+
+        let cmd_vec = vec![command_buffer];
+        let submit_info = ash::vk::SubmitInfo::builder().command_buffers(&cmd_vec);
+
+        let vk_info = [*submit_info];
+        {
+            let _queues = self.lock_queues();
+            unsafe { self.device.queue_submit(queue, &vk_info, fence) }
+                .expect("Failed to submit cmd buffer");
         }
-        .expect("Failed to submit cmd buffer");
 
-        // Wait for GPU
-        unsafe { self.device.queue_wait_idle(queue) }.unwrap();
+        unsafe { self.device.wait_for_fences(&[fence], true, u64::MAX) }.unwrap_or_else(|e| {
+            log::error!("Failed to wait for fence, error: {e}");
+            panic!();
+        });
 
-        unsafe { self.device.free_command_buffers(pool, &cmd_vec) };
+        unsafe {
+            self.device.destroy_fence(fence, None);
+            self.device
+                .free_command_buffers(self.thread_command_pool(pool), &cmd_vec);
+        }
     }
 
     pub(crate) fn get_compute_command_pool(&self) -> ash::vk::CommandPool {
@@ -1166,14 +1360,23 @@ impl VkDevice {
     }
 
     pub(crate) fn get_present_queue(&self) -> ash::vk::Queue {
-        self.queues.present_queue
+        self.queues.present_queue.unwrap_or_else(|| {
+            log::error!("Headless device does not have a present queue");
+            panic!()
+        })
+    }
+
+    /// Has to be held while submitting to, presenting on or waiting for a queue.
+    pub(crate) fn lock_queues(&self) -> MutexGuard<'_, ()> {
+        self.queue_lock.lock()
     }
 
     pub(crate) fn wait_idle(&self) {
+        let _queues = self.lock_queues();
         unsafe { self.device.device_wait_idle().unwrap() };
     }
 
-    pub(crate) fn get_properties(&self) -> ash::vk::PhysicalDeviceProperties2 {
+    pub(crate) fn get_properties(&self) -> ash::vk::PhysicalDeviceProperties {
         self.properties
     }
 }
@@ -1181,6 +1384,9 @@ impl VkDevice {
 impl Drop for VkDevice {
     fn drop(&mut self) {
         unsafe {
+            for pool in self.thread_command_pools.get_mut().values() {
+                self.device.destroy_command_pool(*pool, None);
+            }
             self.device
                 .destroy_command_pool(self.command_pools.compute_command_pool, None);
             self.device
@@ -1191,7 +1397,9 @@ impl Drop for VkDevice {
             self.debug_utils
                 .destroy_debug_utils_messenger(self.debug_messenger, None);
 
-            self.surface_extension.destroy_surface(self.surface, None);
+            if let Some(surface) = self.surface {
+                self.surface_extension.destroy_surface(surface, None);
+            }
 
             self.instance.destroy_instance(None);
         }
@@ -275,6 +275,7 @@ impl VkFramebuffer {
             aspect: ash::vk::ImageAspectFlags::COLOR,
             tiling: ash::vk::ImageTiling::OPTIMAL,
             layer_count: 1,
+            mip_level_count: 1,
             image_type: ImageType::Image2D,
             cubemap: false,
             memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
@@ -306,6 +307,7 @@ impl VkFramebuffer {
             aspect: ash::vk::ImageAspectFlags::DEPTH,
             tiling: ash::vk::ImageTiling::OPTIMAL,
             layer_count: 1,
+            mip_level_count: 1,
             image_type: ImageType::Image2D,
             cubemap: false,
             memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
@@ -515,6 +517,10 @@ impl VkFramebuffer {
         }
     }
 
+    pub(crate) fn get_handle(&self) -> ash::vk::Framebuffer {
+        self.handle
+    }
+
     pub(crate) fn get_render_pass(&self) -> ash::vk::RenderPass {
         self.render_pass
     }
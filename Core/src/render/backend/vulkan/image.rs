@@ -6,7 +6,8 @@ use crate::render::backend::vulkan::buffer;
 use crate::render::backend::vulkan::buffer::VkBuffer;
 use crate::render::backend::vulkan::command_buffer::VkCommandBuffer;
 use crate::render::backend::vulkan::device::VkDevice;
-use mvutils::unsafe_utils::DangerousCell;
+use crate::render::backend::Extent2D;
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 
 pub struct VkImage {
@@ -14,6 +15,8 @@ pub struct VkImage {
 
     pub(crate) handle: ash::vk::Image,
     pub(crate) image_views: Vec<ash::vk::ImageView>,
+    /// One view per mip level, only created for storage images.
+    pub(crate) storage_views: Vec<ash::vk::ImageView>,
     pub(crate) memory: Option<gpu_alloc::MemoryBlock<ash::vk::DeviceMemory>>,
     pub(crate) format: ash::vk::Format,
     pub(crate) aspect: ash::vk::ImageAspectFlags,
@@ -24,7 +27,8 @@ pub struct VkImage {
     pub(crate) mip_level_count: u32,
     pub(crate) usage: ash::vk::ImageUsageFlags,
     pub(crate) memory_properties: ash::vk::MemoryPropertyFlags,
-    pub(crate) layout: DangerousCell<ash::vk::ImageLayout>,
+    /// Raw `ash::vk::ImageLayout`, images are transitioned through shared references from any thread.
+    pub(crate) layout: AtomicI32,
     pub(crate) memory_usage_flags: gpu_alloc::UsageFlags,
     pub(crate) drop: bool,
 }
@@ -37,6 +41,7 @@ pub(crate) struct CreateInfo {
     pub(crate) aspect: ash::vk::ImageAspectFlags,
     pub(crate) tiling: ash::vk::ImageTiling,
     pub(crate) layer_count: u32,
+    pub(crate) mip_level_count: u32,
     pub(crate) image_type: ImageType,
     pub(crate) cubemap: bool,
     pub(crate) memory_usage_flags: gpu_alloc::UsageFlags,
@@ -61,6 +66,7 @@ impl From<MVImageCreateInfo> for CreateInfo {
                 ImageTiling::Linear => ash::vk::ImageTiling::LINEAR,
             },
             layer_count: value.layer_count,
+            mip_level_count: value.mip_level_count.max(1),
             image_type: value.image_type,
             cubemap: value.cubemap,
             memory_usage_flags: value.memory_usage_flags,
@@ -100,6 +106,9 @@ impl From<ImageFormat> for ash::vk::Format {
             ImageFormat::R8G8 => ash::vk::Format::R8G8_UNORM,
             ImageFormat::R8G8B8 => ash::vk::Format::R8G8B8_UNORM,
             ImageFormat::R8G8B8A8 => ash::vk::Format::R8G8B8A8_UNORM,
+            ImageFormat::R8G8B8A8Srgb => ash::vk::Format::R8G8B8A8_SRGB,
+            ImageFormat::B8G8R8A8 => ash::vk::Format::B8G8R8A8_UNORM,
+            ImageFormat::B8G8R8A8Srgb => ash::vk::Format::B8G8R8A8_SRGB,
             ImageFormat::R16 => ash::vk::Format::R16_SFLOAT,
             ImageFormat::R16G16 => ash::vk::Format::R16G16_SFLOAT,
             ImageFormat::R16G16B16 => ash::vk::Format::R16G16B16_SFLOAT,
@@ -112,6 +121,114 @@ impl From<ImageFormat> for ash::vk::Format {
             ImageFormat::D16S8 => ash::vk::Format::D16_UNORM_S8_UINT,
             ImageFormat::D24 => ash::vk::Format::D24_UNORM_S8_UINT,
             ImageFormat::D32 => ash::vk::Format::D32_SFLOAT,
+            ImageFormat::Bc1 => ash::vk::Format::BC1_RGBA_UNORM_BLOCK,
+            ImageFormat::Bc2 => ash::vk::Format::BC2_UNORM_BLOCK,
+            ImageFormat::Bc3 => ash::vk::Format::BC3_UNORM_BLOCK,
+            ImageFormat::Bc4 => ash::vk::Format::BC4_UNORM_BLOCK,
+            ImageFormat::Bc5 => ash::vk::Format::BC5_UNORM_BLOCK,
+            ImageFormat::Bc6H => ash::vk::Format::BC6H_UFLOAT_BLOCK,
+            ImageFormat::Bc7 => ash::vk::Format::BC7_UNORM_BLOCK,
+            ImageFormat::Astc4x4 => ash::vk::Format::ASTC_4X4_UNORM_BLOCK,
+            ImageFormat::Astc5x4 => ash::vk::Format::ASTC_5X4_UNORM_BLOCK,
+            ImageFormat::Astc5x5 => ash::vk::Format::ASTC_5X5_UNORM_BLOCK,
+            ImageFormat::Astc6x5 => ash::vk::Format::ASTC_6X5_UNORM_BLOCK,
+            ImageFormat::Astc6x6 => ash::vk::Format::ASTC_6X6_UNORM_BLOCK,
+            ImageFormat::Astc8x5 => ash::vk::Format::ASTC_8X5_UNORM_BLOCK,
+            ImageFormat::Astc8x6 => ash::vk::Format::ASTC_8X6_UNORM_BLOCK,
+            ImageFormat::Astc8x8 => ash::vk::Format::ASTC_8X8_UNORM_BLOCK,
+            ImageFormat::Astc10x5 => ash::vk::Format::ASTC_10X5_UNORM_BLOCK,
+            ImageFormat::Astc10x6 => ash::vk::Format::ASTC_10X6_UNORM_BLOCK,
+            ImageFormat::Astc10x8 => ash::vk::Format::ASTC_10X8_UNORM_BLOCK,
+            ImageFormat::Astc10x10 => ash::vk::Format::ASTC_10X10_UNORM_BLOCK,
+            ImageFormat::Astc12x10 => ash::vk::Format::ASTC_12X10_UNORM_BLOCK,
+            ImageFormat::Astc12x12 => ash::vk::Format::ASTC_12X12_UNORM_BLOCK,
+            ImageFormat::Bc1Srgb => ash::vk::Format::BC1_RGBA_SRGB_BLOCK,
+            ImageFormat::Bc2Srgb => ash::vk::Format::BC2_SRGB_BLOCK,
+            ImageFormat::Bc3Srgb => ash::vk::Format::BC3_SRGB_BLOCK,
+            ImageFormat::Bc7Srgb => ash::vk::Format::BC7_SRGB_BLOCK,
+            ImageFormat::Astc4x4Srgb => ash::vk::Format::ASTC_4X4_SRGB_BLOCK,
+            ImageFormat::Astc5x4Srgb => ash::vk::Format::ASTC_5X4_SRGB_BLOCK,
+            ImageFormat::Astc5x5Srgb => ash::vk::Format::ASTC_5X5_SRGB_BLOCK,
+            ImageFormat::Astc6x5Srgb => ash::vk::Format::ASTC_6X5_SRGB_BLOCK,
+            ImageFormat::Astc6x6Srgb => ash::vk::Format::ASTC_6X6_SRGB_BLOCK,
+            ImageFormat::Astc8x5Srgb => ash::vk::Format::ASTC_8X5_SRGB_BLOCK,
+            ImageFormat::Astc8x6Srgb => ash::vk::Format::ASTC_8X6_SRGB_BLOCK,
+            ImageFormat::Astc8x8Srgb => ash::vk::Format::ASTC_8X8_SRGB_BLOCK,
+            ImageFormat::Astc10x5Srgb => ash::vk::Format::ASTC_10X5_SRGB_BLOCK,
+            ImageFormat::Astc10x6Srgb => ash::vk::Format::ASTC_10X6_SRGB_BLOCK,
+            ImageFormat::Astc10x8Srgb => ash::vk::Format::ASTC_10X8_SRGB_BLOCK,
+            ImageFormat::Astc10x10Srgb => ash::vk::Format::ASTC_10X10_SRGB_BLOCK,
+            ImageFormat::Astc12x10Srgb => ash::vk::Format::ASTC_12X10_SRGB_BLOCK,
+            ImageFormat::Astc12x12Srgb => ash::vk::Format::ASTC_12X12_SRGB_BLOCK,
+        }
+    }
+}
+
+impl From<ash::vk::Format> for ImageFormat {
+    fn from(value: ash::vk::Format) -> Self {
+        match value {
+            ash::vk::Format::R8_UNORM => ImageFormat::R8,
+            ash::vk::Format::R8G8_UNORM => ImageFormat::R8G8,
+            ash::vk::Format::R8G8B8_UNORM => ImageFormat::R8G8B8,
+            ash::vk::Format::R8G8B8A8_UNORM => ImageFormat::R8G8B8A8,
+            ash::vk::Format::R8G8B8A8_SRGB => ImageFormat::R8G8B8A8Srgb,
+            ash::vk::Format::B8G8R8A8_UNORM => ImageFormat::B8G8R8A8,
+            ash::vk::Format::B8G8R8A8_SRGB => ImageFormat::B8G8R8A8Srgb,
+            ash::vk::Format::R16_SFLOAT => ImageFormat::R16,
+            ash::vk::Format::R16G16_SFLOAT => ImageFormat::R16G16,
+            ash::vk::Format::R16G16B16_SFLOAT => ImageFormat::R16G16B16,
+            ash::vk::Format::R16G16B16A16_SFLOAT => ImageFormat::R16G16B16A16,
+            ash::vk::Format::R32_SFLOAT => ImageFormat::R32,
+            ash::vk::Format::R32G32_SFLOAT => ImageFormat::R32G32,
+            ash::vk::Format::R32G32B32_SFLOAT => ImageFormat::R32G32B32,
+            ash::vk::Format::R32G32B32A32_SFLOAT => ImageFormat::R32G32B32A32,
+            ash::vk::Format::D16_UNORM => ImageFormat::D16,
+            ash::vk::Format::D16_UNORM_S8_UINT => ImageFormat::D16S8,
+            ash::vk::Format::D24_UNORM_S8_UINT => ImageFormat::D24,
+            ash::vk::Format::D32_SFLOAT => ImageFormat::D32,
+            ash::vk::Format::BC1_RGBA_UNORM_BLOCK => ImageFormat::Bc1,
+            ash::vk::Format::BC2_UNORM_BLOCK => ImageFormat::Bc2,
+            ash::vk::Format::BC3_UNORM_BLOCK => ImageFormat::Bc3,
+            ash::vk::Format::BC4_UNORM_BLOCK => ImageFormat::Bc4,
+            ash::vk::Format::BC5_UNORM_BLOCK => ImageFormat::Bc5,
+            ash::vk::Format::BC6H_UFLOAT_BLOCK => ImageFormat::Bc6H,
+            ash::vk::Format::BC7_UNORM_BLOCK => ImageFormat::Bc7,
+            ash::vk::Format::ASTC_4X4_UNORM_BLOCK => ImageFormat::Astc4x4,
+            ash::vk::Format::ASTC_5X4_UNORM_BLOCK => ImageFormat::Astc5x4,
+            ash::vk::Format::ASTC_5X5_UNORM_BLOCK => ImageFormat::Astc5x5,
+            ash::vk::Format::ASTC_6X5_UNORM_BLOCK => ImageFormat::Astc6x5,
+            ash::vk::Format::ASTC_6X6_UNORM_BLOCK => ImageFormat::Astc6x6,
+            ash::vk::Format::ASTC_8X5_UNORM_BLOCK => ImageFormat::Astc8x5,
+            ash::vk::Format::ASTC_8X6_UNORM_BLOCK => ImageFormat::Astc8x6,
+            ash::vk::Format::ASTC_8X8_UNORM_BLOCK => ImageFormat::Astc8x8,
+            ash::vk::Format::ASTC_10X5_UNORM_BLOCK => ImageFormat::Astc10x5,
+            ash::vk::Format::ASTC_10X6_UNORM_BLOCK => ImageFormat::Astc10x6,
+            ash::vk::Format::ASTC_10X8_UNORM_BLOCK => ImageFormat::Astc10x8,
+            ash::vk::Format::ASTC_10X10_UNORM_BLOCK => ImageFormat::Astc10x10,
+            ash::vk::Format::ASTC_12X10_UNORM_BLOCK => ImageFormat::Astc12x10,
+            ash::vk::Format::ASTC_12X12_UNORM_BLOCK => ImageFormat::Astc12x12,
+            ash::vk::Format::BC1_RGBA_SRGB_BLOCK => ImageFormat::Bc1Srgb,
+            ash::vk::Format::BC2_SRGB_BLOCK => ImageFormat::Bc2Srgb,
+            ash::vk::Format::BC3_SRGB_BLOCK => ImageFormat::Bc3Srgb,
+            ash::vk::Format::BC7_SRGB_BLOCK => ImageFormat::Bc7Srgb,
+            ash::vk::Format::ASTC_4X4_SRGB_BLOCK => ImageFormat::Astc4x4Srgb,
+            ash::vk::Format::ASTC_5X4_SRGB_BLOCK => ImageFormat::Astc5x4Srgb,
+            ash::vk::Format::ASTC_5X5_SRGB_BLOCK => ImageFormat::Astc5x5Srgb,
+            ash::vk::Format::ASTC_6X5_SRGB_BLOCK => ImageFormat::Astc6x5Srgb,
+            ash::vk::Format::ASTC_6X6_SRGB_BLOCK => ImageFormat::Astc6x6Srgb,
+            ash::vk::Format::ASTC_8X5_SRGB_BLOCK => ImageFormat::Astc8x5Srgb,
+            ash::vk::Format::ASTC_8X6_SRGB_BLOCK => ImageFormat::Astc8x6Srgb,
+            ash::vk::Format::ASTC_8X8_SRGB_BLOCK => ImageFormat::Astc8x8Srgb,
+            ash::vk::Format::ASTC_10X5_SRGB_BLOCK => ImageFormat::Astc10x5Srgb,
+            ash::vk::Format::ASTC_10X6_SRGB_BLOCK => ImageFormat::Astc10x6Srgb,
+            ash::vk::Format::ASTC_10X8_SRGB_BLOCK => ImageFormat::Astc10x8Srgb,
+            ash::vk::Format::ASTC_10X10_SRGB_BLOCK => ImageFormat::Astc10x10Srgb,
+            ash::vk::Format::ASTC_12X10_SRGB_BLOCK => ImageFormat::Astc12x10Srgb,
+            ash::vk::Format::ASTC_12X12_SRGB_BLOCK => ImageFormat::Astc12x12Srgb,
+            _ => {
+                log::error!("Format {value:?} has no ImageFormat equivalent");
+                panic!();
+            }
         }
     }
 }
@@ -128,6 +245,10 @@ impl VkImage {
         if let Some(_) = create_info.data {
             usage |= ash::vk::ImageUsageFlags::TRANSFER_DST;
         }
+        // Mipmaps are generated by blitting from one level to the next
+        if create_info.mip_level_count > 1 {
+            usage |= ash::vk::ImageUsageFlags::TRANSFER_SRC | ash::vk::ImageUsageFlags::TRANSFER_DST;
+        }
 
         let create_info_vk = ash::vk::ImageCreateInfo::builder()
             .image_type(ash::vk::ImageType::TYPE_2D)
@@ -136,7 +257,7 @@ impl VkImage {
                 height: create_info.size.height,
                 depth: 1,
             })
-            .mip_levels(1)
+            .mip_levels(create_info.mip_level_count)
             .array_layers(create_info.layer_count)
             .format(create_info.format)
             .tiling(create_info.tiling)
@@ -162,7 +283,9 @@ impl VkImage {
         let view_type = match create_info.image_type {
             ImageType::Image2D => ash::vk::ImageViewType::TYPE_2D,
             ImageType::Image2DArray => ash::vk::ImageViewType::TYPE_2D_ARRAY,
-            ImageType::Cubemap => ash::vk::ImageViewType::CUBE_ARRAY,
+            // Cube arrays need a device feature, a single cubemap doesn't
+            ImageType::Cubemap if create_info.layer_count > 6 => ash::vk::ImageViewType::CUBE_ARRAY,
+            ImageType::Cubemap => ash::vk::ImageViewType::CUBE,
         };
         let mut views = Vec::new();
         for i in 0..create_info.layer_count {
@@ -173,7 +296,7 @@ impl VkImage {
                 .subresource_range(ash::vk::ImageSubresourceRange {
                     aspect_mask: create_info.aspect,
                     base_mip_level: 0,
-                    level_count: 1,
+                    level_count: create_info.mip_level_count,
                     base_array_layer: 0,
                     layer_count: create_info.layer_count,
                 });
@@ -194,10 +317,41 @@ impl VkImage {
             );
         }
 
+        // Storage images are written one mip level at a time, cubemaps are written as arrays of their faces
+        let mut storage_views = Vec::new();
+        if usage.contains(ash::vk::ImageUsageFlags::STORAGE) {
+            let view_type = match create_info.image_type {
+                ImageType::Image2D => ash::vk::ImageViewType::TYPE_2D,
+                ImageType::Image2DArray | ImageType::Cubemap => ash::vk::ImageViewType::TYPE_2D_ARRAY,
+            };
+            for level in 0..create_info.mip_level_count {
+                let view_info = ash::vk::ImageViewCreateInfo::builder()
+                    .image(image)
+                    .view_type(view_type)
+                    .format(create_info.format)
+                    .subresource_range(ash::vk::ImageSubresourceRange {
+                        aspect_mask: create_info.aspect,
+                        base_mip_level: level,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: create_info.layer_count,
+                    });
+
+                let view = unsafe { device.get_device().create_image_view(&view_info, None) }
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to create storage image view, error: {e}");
+                        panic!();
+                    });
+
+                storage_views.push(view);
+            }
+        }
+
         let mut this = Self {
             device: device.clone(),
             handle: image,
             image_views: views,
+            storage_views,
             memory: Some(block),
             format: create_info.format,
             aspect: create_info.aspect,
@@ -205,10 +359,10 @@ impl VkImage {
             layer_count: create_info.layer_count,
             image_type: ash::vk::ImageType::TYPE_2D,
             size: create_info.size,
-            mip_level_count: 1,
-            usage: create_info.usage,
+            mip_level_count: create_info.mip_level_count,
+            usage,
             memory_properties: create_info.memory_properties,
-            layout: ash::vk::ImageLayout::UNDEFINED.into(),
+            layout: AtomicI32::new(ash::vk::ImageLayout::UNDEFINED.as_raw()),
             memory_usage_flags: create_info.memory_usage_flags,
             drop: true,
         };
@@ -220,24 +374,6 @@ impl VkImage {
         this
     }
 
-    #[allow(clippy::identity_op)]
-    fn format_to_size(format: ash::vk::Format) -> u32 {
-        match format {
-            ash::vk::Format::R32G32B32A32_SFLOAT => 4 * 4,
-            ash::vk::Format::R32G32B32_SFLOAT => 4 * 3,
-            ash::vk::Format::R32G32_SFLOAT => 4 * 2,
-            ash::vk::Format::R32_SFLOAT => 4 * 1,
-            ash::vk::Format::R8G8B8A8_UNORM => 1 * 4,
-            ash::vk::Format::R8G8B8_UNORM => 1 * 3,
-            ash::vk::Format::R8G8_UNORM => 1 * 2,
-            ash::vk::Format::R8_UNORM => 1 * 1,
-            _ => {
-                log::error!("Format unsupported!");
-                panic!();
-            }
-        }
-    }
-
     pub fn write_pixels(&mut self, pixels: &[u8], provided_cmd: Option<&VkCommandBuffer>)
     {
         let (cmd, end) = if let Some(cmd) = provided_cmd {
@@ -255,8 +391,15 @@ impl VkImage {
             handle: cmd
         };
 
-        let pixel_size = Self::format_to_size(self.format);
-        let image_byte_size: ash::vk::DeviceSize = (self.size.width * self.size.height * pixel_size) as ash::vk::DeviceSize;
+        let format = ImageFormat::from(self.format);
+        let extent = Extent2D::from(self.size);
+        let level_sizes = (0..self.mip_level_count).map(|level| {
+            format.get_data_size(extent.get_mip_extent(level)) * self.layer_count as ash::vk::DeviceSize
+        }).collect::<Vec<_>>();
+        // If only the first level is given, the others are generated from it
+        let level_count = if pixels.len() as ash::vk::DeviceSize >= level_sizes.iter().sum() { self.mip_level_count } else { 1 };
+        let image_byte_size: ash::vk::DeviceSize = level_sizes[..level_count as usize].iter().sum();
+        let pixels = &pixels[..pixels.len().min(image_byte_size as usize)];
 
         let buffer_info = buffer::CreateInfo{
             instance_size: image_byte_size,
@@ -274,8 +417,44 @@ impl VkImage {
         buffer.unmap();
 
         self.transition_layout(ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL, Some(&vk_cmd), ash::vk::AccessFlags::empty(), ash::vk::AccessFlags::empty());
-        self.copy_buffer_to_image(&buffer, Some(&vk_cmd));
-        self.transition_layout(ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, Some(&vk_cmd), ash::vk::AccessFlags::empty(), ash::vk::AccessFlags::empty());
+        let mut offset = 0;
+        let regions = (0..level_count).map(|level| {
+            let level_extent = extent.get_mip_extent(level);
+            let region = ash::vk::BufferImageCopy {
+                buffer_offset: offset,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: ash::vk::ImageSubresourceLayers {
+                    aspect_mask: self.aspect,
+                    mip_level: level,
+                    base_array_layer: 0,
+                    layer_count: self.layer_count,
+                },
+                image_offset: ash::vk::Offset3D { x: 0, y: 0, z: 0 },
+                image_extent: ash::vk::Extent3D {
+                    width: level_extent.width,
+                    height: level_extent.height,
+                    depth: 1,
+                },
+            };
+            offset += level_sizes[level as usize];
+            region
+        }).collect::<Vec<_>>();
+        unsafe {
+            self.device.get_device().cmd_copy_buffer_to_image(
+                cmd,
+                buffer.get_buffer(),
+                self.handle,
+                self.get_layout(),
+                &regions,
+            )
+        };
+
+        if level_count < self.mip_level_count {
+            self.generate_mipmaps(Some(&vk_cmd));
+        } else {
+            self.transition_layout(ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, Some(&vk_cmd), ash::vk::AccessFlags::empty(), ash::vk::AccessFlags::empty());
+        }
 
         if end {
             self.device.end_single_time_command(
@@ -303,18 +482,20 @@ impl VkImage {
             )
         };
 
+        // Swapping claims the old layout, a concurrent transition sees the new one instead
+        let old_layout = self.swap_layout(new_layout);
         let mut src_access = src_access;
         let mut dst_access = dst_access;
         let mut src_stage = ash::vk::PipelineStageFlags::empty();
         let mut dst_stage = ash::vk::PipelineStageFlags::empty();
 
-        if self.layout.get_val() != new_layout {
+        if old_layout != new_layout {
 
             // dst_access |= ash::vk::AccessFlags::TRANSFER_WRITE;
             // dst_stage |= ash::vk::PipelineStageFlags::TRANSFER;
         }
 
-        match self.layout.get_val() {
+        match old_layout {
             ash::vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => {
                 src_access |= ash::vk::AccessFlags::COLOR_ATTACHMENT_WRITE;
                 src_stage |= ash::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT;
@@ -390,13 +571,13 @@ impl VkImage {
         let subresource_range = ash::vk::ImageSubresourceRange {
             aspect_mask: self.aspect,
             base_mip_level: 0,
-            level_count: 1,
+            level_count: self.mip_level_count,
             base_array_layer: 0,
             layer_count: self.layer_count,
         };
 
         let barrier = ash::vk::ImageMemoryBarrier::builder()
-            .old_layout(self.layout.get_val())
+            .old_layout(old_layout)
             .new_layout(new_layout)
             .src_queue_family_index(ash::vk::QUEUE_FAMILY_EXTERNAL)
             .dst_queue_family_index(ash::vk::QUEUE_FAMILY_EXTERNAL)
@@ -419,7 +600,101 @@ impl VkImage {
             )
         }
 
-        self.layout.replace(new_layout);
+        if end {
+            self.device.end_single_time_command(
+                cmd,
+                self.device.get_graphics_command_pool(),
+                self.device.get_graphics_queue(),
+            );
+        }
+    }
+
+    /// Blits every mip level from the previous one, all levels end up in `SHADER_READ_ONLY_OPTIMAL`.
+    pub(crate) fn generate_mipmaps(&self, provided_cmd: Option<&VkCommandBuffer>) {
+        if self.mip_level_count < 2 {
+            log::error!("Cannot generate mipmaps for an image with a single mip level");
+            panic!();
+        }
+        // Compressed formats can't be blitted to, their mip levels have to be compressed up front
+        if ImageFormat::from(self.format).is_compressed() {
+            log::error!("Cannot generate mipmaps for compressed format {:?}", self.format);
+            panic!();
+        }
+
+        let (cmd, end) = if let Some(cmd) = provided_cmd {
+            (cmd.get_handle(), false)
+        } else {
+            (
+                self.device
+                    .begin_single_time_command(self.device.get_graphics_command_pool()),
+                true,
+            )
+        };
+
+        let vk_cmd = VkCommandBuffer {
+            device: self.device.clone(),
+            handle: cmd
+        };
+
+        let features = unsafe {
+            self.device
+                .get_instance()
+                .get_physical_device_format_properties(self.device.get_physical_device(), self.format)
+        }
+        .optimal_tiling_features;
+        let filter = if features.contains(ash::vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR) {
+            ash::vk::Filter::LINEAR
+        } else {
+            log::warn!("Format {:?} does not support linear filtering, generating mipmaps with nearest filtering", self.format);
+            ash::vk::Filter::NEAREST
+        };
+
+        let shader_stages = ash::vk::PipelineStageFlags::FRAGMENT_SHADER | ash::vk::PipelineStageFlags::COMPUTE_SHADER;
+        #[cfg(feature = "ray-tracing")]
+        let shader_stages = shader_stages | ash::vk::PipelineStageFlags::RAY_TRACING_SHADER_KHR;
+        let transfer_write = (ash::vk::AccessFlags::TRANSFER_WRITE, ash::vk::PipelineStageFlags::TRANSFER);
+        let transfer_read = (ash::vk::AccessFlags::TRANSFER_READ, ash::vk::PipelineStageFlags::TRANSFER);
+        let shader_read = (ash::vk::AccessFlags::SHADER_READ, shader_stages);
+
+        self.transition_layout(ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL, Some(&vk_cmd), ash::vk::AccessFlags::empty(), ash::vk::AccessFlags::empty());
+        let extent = Extent2D::from(self.size);
+        for level in 1..self.mip_level_count {
+            self.level_barrier(cmd, level - 1, (ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL, ash::vk::ImageLayout::TRANSFER_SRC_OPTIMAL), transfer_write, transfer_read);
+
+            let offset = |extent: Extent2D| ash::vk::Offset3D {
+                x: extent.width as i32,
+                y: extent.height as i32,
+                z: 1,
+            };
+            let subresource = |level| ash::vk::ImageSubresourceLayers {
+                aspect_mask: self.aspect,
+                mip_level: level,
+                base_array_layer: 0,
+                layer_count: self.layer_count,
+            };
+            let blit = ash::vk::ImageBlit {
+                src_subresource: subresource(level - 1),
+                src_offsets: [ash::vk::Offset3D { x: 0, y: 0, z: 0 }, offset(extent.get_mip_extent(level - 1))],
+                dst_subresource: subresource(level),
+                dst_offsets: [ash::vk::Offset3D { x: 0, y: 0, z: 0 }, offset(extent.get_mip_extent(level))],
+            };
+
+            unsafe {
+                self.device.get_device().cmd_blit_image(
+                    cmd,
+                    self.handle,
+                    ash::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    self.handle,
+                    ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[blit],
+                    filter,
+                );
+            }
+
+            self.level_barrier(cmd, level - 1, (ash::vk::ImageLayout::TRANSFER_SRC_OPTIMAL, ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL), transfer_read, shader_read);
+        }
+        self.level_barrier(cmd, self.mip_level_count - 1, (ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL, ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL), transfer_write, shader_read);
+        self.set_layout(ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
 
         if end {
             self.device.end_single_time_command(
@@ -430,6 +705,44 @@ impl VkImage {
         }
     }
 
+    /// Transitions a single mip level, `src` and `dst` are the access and stages before and after.
+    fn level_barrier(
+        &self,
+        cmd: ash::vk::CommandBuffer,
+        level: u32,
+        (old_layout, new_layout): (ash::vk::ImageLayout, ash::vk::ImageLayout),
+        src: (ash::vk::AccessFlags, ash::vk::PipelineStageFlags),
+        dst: (ash::vk::AccessFlags, ash::vk::PipelineStageFlags),
+    ) {
+        let barrier = ash::vk::ImageMemoryBarrier::builder()
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_queue_family_index(ash::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(ash::vk::QUEUE_FAMILY_IGNORED)
+            .image(self.handle)
+            .subresource_range(ash::vk::ImageSubresourceRange {
+                aspect_mask: self.aspect,
+                base_mip_level: level,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: self.layer_count,
+            })
+            .src_access_mask(src.0)
+            .dst_access_mask(dst.0);
+
+        unsafe {
+            self.device.get_device().cmd_pipeline_barrier(
+                cmd,
+                src.1,
+                dst.1,
+                ash::vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[*barrier],
+            )
+        }
+    }
+
     pub(crate) fn copy_buffer_to_image(
         &self,
         buffer: &VkBuffer,
@@ -470,7 +783,7 @@ impl VkImage {
                 cmd,
                 buffer.get_buffer(),
                 self.handle,
-                self.layout.get_val(),
+                self.get_layout(),
                 &[copy_region],
             )
         };
@@ -484,10 +797,165 @@ impl VkImage {
         }
     }
 
+    pub(crate) fn copy_image_to_buffer(
+        &self,
+        buffer: &VkBuffer,
+        provided_cmd: Option<&VkCommandBuffer>,
+    ) {
+        let (cmd, end) = if let Some(cmd) = provided_cmd {
+            (cmd.get_handle(), false)
+        } else {
+            (
+                self.device
+                    .begin_single_time_command(self.device.get_graphics_command_pool()),
+                true,
+            )
+        };
+
+        // Depth stencil images can only be copied one aspect at a time
+        let aspect_mask = if self.aspect.contains(ash::vk::ImageAspectFlags::DEPTH) {
+            ash::vk::ImageAspectFlags::DEPTH
+        } else {
+            self.aspect
+        };
+
+        let subresource_range = ash::vk::ImageSubresourceLayers {
+            aspect_mask,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: self.layer_count,
+        };
+
+        let copy_region = ash::vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: subresource_range,
+            image_offset: ash::vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: ash::vk::Extent3D {
+                width: self.size.width,
+                height: self.size.height,
+                depth: 1,
+            },
+        };
+
+        unsafe {
+            self.device.get_device().cmd_copy_image_to_buffer(
+                cmd,
+                self.handle,
+                self.get_layout(),
+                buffer.get_buffer(),
+                &[copy_region],
+            )
+        };
+
+        if end {
+            self.device.end_single_time_command(
+                cmd,
+                self.device.get_graphics_command_pool(),
+                self.device.get_graphics_queue(),
+            );
+        }
+    }
+
+    /// Copies the first mip level of every layer into host memory and blocks until the copy is
+    /// done. Layers are tightly packed one after another. The image is transitioned back to its
+    /// previous layout afterwards.
+    pub(crate) fn read_pixels(&self) -> Vec<u8> {
+        let image_byte_size = ImageFormat::from(self.format).get_data_size(self.size.into())
+            * self.layer_count as ash::vk::DeviceSize;
+
+        let buffer_info = buffer::CreateInfo {
+            instance_size: image_byte_size,
+            instance_count: 1,
+            usage_flags: ash::vk::BufferUsageFlags::TRANSFER_DST,
+            memory_properties: ash::vk::MemoryPropertyFlags::HOST_VISIBLE
+                | ash::vk::MemoryPropertyFlags::HOST_COHERENT,
+            minimum_alignment: 1,
+            memory_usage_flags: gpu_alloc::UsageFlags::HOST_ACCESS
+                | gpu_alloc::UsageFlags::DOWNLOAD
+                | gpu_alloc::UsageFlags::TRANSIENT,
+
+            #[cfg(debug_assertions)]
+            debug_name: CString::new("Readback staging buffer").unwrap(),
+        };
+
+        let mut buffer = VkBuffer::new(self.device.clone(), buffer_info);
+
+        let cmd = self
+            .device
+            .begin_single_time_command(self.device.get_graphics_command_pool());
+        let vk_cmd = VkCommandBuffer {
+            device: self.device.clone(),
+            handle: cmd,
+        };
+
+        let old_layout = self.get_layout();
+        self.transition_layout(
+            ash::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            Some(&vk_cmd),
+            ash::vk::AccessFlags::empty(),
+            ash::vk::AccessFlags::empty(),
+        );
+        self.copy_image_to_buffer(&buffer, Some(&vk_cmd));
+
+        // Waiting on the fence alone doesn't make the copied data visible to the host
+        let barrier = ash::vk::BufferMemoryBarrier::builder()
+            .src_access_mask(ash::vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(ash::vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(ash::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(ash::vk::QUEUE_FAMILY_IGNORED)
+            .buffer(buffer.get_buffer())
+            .offset(0)
+            .size(ash::vk::WHOLE_SIZE);
+
+        unsafe {
+            self.device.get_device().cmd_pipeline_barrier(
+                cmd,
+                ash::vk::PipelineStageFlags::TRANSFER,
+                ash::vk::PipelineStageFlags::HOST,
+                ash::vk::DependencyFlags::empty(),
+                &[],
+                &[*barrier],
+                &[],
+            )
+        }
+
+        if old_layout != ash::vk::ImageLayout::UNDEFINED
+            && old_layout != ash::vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+        {
+            self.transition_layout(
+                old_layout,
+                Some(&vk_cmd),
+                ash::vk::AccessFlags::empty(),
+                ash::vk::AccessFlags::empty(),
+            );
+        }
+
+        self.device.end_single_time_command_fenced(
+            cmd,
+            self.device.get_graphics_command_pool(),
+            self.device.get_graphics_queue(),
+        );
+
+        buffer.read_from_buffer(image_byte_size, 0)
+    }
+
     pub(crate) fn get_view(&self, index: u32) -> ash::vk::ImageView {
         self.image_views[index as usize]
     }
 
+    /// View of a single mip level, for writing it as a storage image.
+    pub(crate) fn get_storage_view(&self, level: u32) -> ash::vk::ImageView {
+        match self.storage_views.get(level as usize) {
+            Some(view) => *view,
+            None => {
+                log::error!("Image has no storage view for mip level {level}, it needs the STORAGE usage");
+                panic!();
+            }
+        }
+    }
+
     pub(crate) fn get_format(&self, index: u32) -> ash::vk::Format {
         self.format
     }
@@ -501,21 +969,33 @@ impl VkImage {
     }
 
     pub(crate) fn get_layout(&self) -> ash::vk::ImageLayout {
-        self.layout.get_val()
+        ash::vk::ImageLayout::from_raw(self.layout.load(Ordering::Acquire))
     }
 
     pub(crate) fn get_extent(&self) -> ash::vk::Extent2D {
         self.size
     }
 
+    pub(crate) fn get_mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    pub(crate) fn get_layer_count(&self) -> u32 {
+        self.layer_count
+    }
+
     pub(crate) fn set_layout(&self, layout: ash::vk::ImageLayout) {
-        self.layout.replace(layout);
+        self.layout.store(layout.as_raw(), Ordering::Release);
+    }
+
+    fn swap_layout(&self, layout: ash::vk::ImageLayout) -> ash::vk::ImageLayout {
+        ash::vk::ImageLayout::from_raw(self.layout.swap(layout.as_raw(), Ordering::AcqRel))
     }
 }
 
 impl Drop for VkImage {
     fn drop(&mut self) {
-        for view in &self.image_views {
+        for view in self.image_views.iter().chain(&self.storage_views) {
             unsafe { self.device.get_device().destroy_image_view(*view, None) };
         }
         if !self.drop {
@@ -13,7 +13,10 @@ pub(crate) struct CreateInfo {
     address_mode: ash::vk::SamplerAddressMode,
     filter_mode: ash::vk::Filter,
     mipmap_mode: ash::vk::SamplerMipmapMode,
-    anisotropy: bool,
+    anisotropy: Option<f32>,
+    lod_bias: f32,
+    min_lod: f32,
+    max_lod: f32,
 
     #[cfg(debug_assertions)]
     debug_name: std::ffi::CString,
@@ -55,6 +58,9 @@ impl From<MVSamplerCreateInfo> for CreateInfo {
             filter_mode: value.filter_mode.into(),
             mipmap_mode: value.mipmap_mode.into(),
             anisotropy: value.anisotropy,
+            lod_bias: value.lod_bias,
+            min_lod: value.min_lod,
+            max_lod: value.max_lod,
 
             #[cfg(debug_assertions)]
             debug_name: crate::render::backend::to_ascii_cstring(value.label.unwrap_or_default()),
@@ -64,6 +70,10 @@ impl From<MVSamplerCreateInfo> for CreateInfo {
 
 impl VkSampler {
     pub(crate) fn new(device: Arc<VkDevice>, create_info: CreateInfo) -> Self {
+        let max_anisotropy = device
+            .get_properties()
+            .limits
+            .max_sampler_anisotropy;
         let create_info_vk = ash::vk::SamplerCreateInfo::builder()
             .mag_filter(create_info.filter_mode)
             .min_filter(create_info.filter_mode)
@@ -71,19 +81,13 @@ impl VkSampler {
             .address_mode_u(create_info.address_mode)
             .address_mode_v(create_info.address_mode)
             .address_mode_w(create_info.address_mode)
-            .mip_lod_bias(0.0f32)
+            .mip_lod_bias(create_info.lod_bias)
             .compare_op(ash::vk::CompareOp::ALWAYS)
-            .min_lod(0.0f32)
-            .max_lod(ash::vk::LOD_CLAMP_NONE)
+            .min_lod(create_info.min_lod)
+            .max_lod(create_info.max_lod)
             .border_color(ash::vk::BorderColor::FLOAT_OPAQUE_BLACK)
-            .max_anisotropy(
-                device
-                    .get_properties()
-                    .properties
-                    .limits
-                    .max_sampler_anisotropy,
-            )
-            .anisotropy_enable(create_info.anisotropy)
+            .max_anisotropy(create_info.anisotropy.unwrap_or(1.0).clamp(1.0, max_anisotropy))
+            .anisotropy_enable(create_info.anisotropy.is_some())
             .unnormalized_coordinates(false)
             .compare_enable(false);
 
@@ -32,6 +32,7 @@ pub struct VkShader {
 
     stage: ash::vk::ShaderStageFlags,
     handle: ash::vk::ShaderModule,
+    code_size: u64,
 }
 
 impl VkShader {
@@ -60,9 +61,14 @@ impl VkShader {
             device: device.clone(),
             handle: module,
             stage: create_info.stage,
+            code_size: (create_info.shader_code.len() * 4) as u64,
         }
     }
 
+    pub(crate) fn get_code_size(&self) -> u64 {
+        self.code_size
+    }
+
     pub fn create_stage_create_info(&self) -> ash::vk::PipelineShaderStageCreateInfo {
         ash::vk::PipelineShaderStageCreateInfo {
             stage: self.stage,
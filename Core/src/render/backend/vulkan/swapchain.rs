@@ -7,6 +7,7 @@ use crate::render::backend::vulkan::image::VkImage;
 use crate::render::backend::{Extent2D, Extent3D};
 use ash::vk::SwapchainPresentScalingCreateInfoEXT;
 use std::ops::Not;
+use std::sync::atomic::AtomicI32;
 use std::sync::Arc;
 
 pub struct VkSwapchain {
@@ -116,6 +117,11 @@ struct SwapchainCapabilities {
 
 impl VkSwapchain {
     pub(crate) fn new(device: Arc<VkDevice>, create_info: CreateInfo) -> Self {
+        if device.is_headless() {
+            log::error!("Cannot create a swapchain on a headless device");
+            panic!()
+        }
+
         let swapchain_capabilities = Self::get_swapchain_capabilities(device.clone());
         let present_mode = Self::choose_present_mode(
             device.clone(),
@@ -224,6 +230,7 @@ impl VkSwapchain {
                     device: device.clone(),
                     handle: image,
                     image_views: vec![view],
+                    storage_views: Vec::new(),
                     memory: None,
                     format: color_format.format,
                     aspect: ash::vk::ImageAspectFlags::COLOR,
@@ -231,10 +238,10 @@ impl VkSwapchain {
                     layer_count: 1,
                     image_type: ash::vk::ImageType::TYPE_2D,
                     size: create_info.window_extent,
-                    mip_level_count: 0,
+                    mip_level_count: 1,
                     usage: ash::vk::ImageUsageFlags::SAMPLED,
                     memory_properties: ash::vk::MemoryPropertyFlags::DEVICE_LOCAL,
-                    layout: ash::vk::ImageLayout::UNDEFINED.into(),
+                    layout: AtomicI32::new(ash::vk::ImageLayout::UNDEFINED.as_raw()),
                     memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
                     drop: false,
                 }
@@ -274,17 +281,24 @@ impl VkSwapchain {
     fn choose_swapchain_color_format(
         available_formats: &[ash::vk::SurfaceFormatKHR],
     ) -> ash::vk::SurfaceFormatKHR {
-        for format in available_formats {
-            // look for UNORM in LINEAR color space
-            if format.format == ash::vk::Format::R8G8B8A8_UNORM
-                && format.color_space == ash::vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT
-            {
-                return *format;
-            }
-        }
-
-        // if the desired format isn't available just return the first one
-        available_formats[0]
+        // Shaders output linear colors, sRGB formats encode them when writing so they are displayed
+        // the same as in image editors. Fall back to UNORM, then to whatever is available.
+        let preferred = [
+            ash::vk::Format::B8G8R8A8_SRGB,
+            ash::vk::Format::R8G8B8A8_SRGB,
+            ash::vk::Format::B8G8R8A8_UNORM,
+            ash::vk::Format::R8G8B8A8_UNORM,
+        ];
+        preferred
+            .iter()
+            .find_map(|preferred| {
+                available_formats.iter().find(|format| {
+                    format.format == *preferred
+                        && format.color_space == ash::vk::ColorSpaceKHR::SRGB_NONLINEAR
+                })
+            })
+            .copied()
+            .unwrap_or(available_formats[0])
     }
 
     fn choose_swapchain_depth_format(
@@ -484,6 +498,7 @@ impl VkSwapchain {
             .wait_dst_stage_mask(&wait_stages);
 
         let vk_info = [*submit_info];
+        let _queues = self.device.lock_queues();
         unsafe {
             self.device.get_device().queue_submit(
                 self.device.get_graphics_queue(),
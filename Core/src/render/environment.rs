@@ -0,0 +1,390 @@
+use shaderc::ShaderKind;
+
+use crate::render::backend::buffer::MemoryProperties;
+use crate::render::backend::command_buffer::CommandBuffer;
+use crate::render::backend::descriptor_set::{
+    DescriptorPool, DescriptorPoolFlags, DescriptorPoolSize, DescriptorSet, DescriptorSetLayout,
+    DescriptorSetLayoutBinding, DescriptorType, MVDescriptorPoolCreateInfo,
+    MVDescriptorSetFromLayoutCreateInfo, MVDescriptorSetLayoutCreateInfo,
+};
+use crate::render::backend::device::Device;
+use crate::render::backend::image::{
+    AccessFlags, Image, ImageAspect, ImageFormat, ImageLayout, ImageTiling, ImageType, ImageUsage,
+    MVImageCreateInfo,
+};
+use crate::render::backend::pipeline::{self, Compute, MVComputePipelineCreateInfo, Pipeline};
+use crate::render::backend::push_constant::{MVPushConstantCreateInfo, PushConstant};
+use crate::render::backend::sampler::{
+    Filter, MVSamplerCreateInfo, MipmapMode, Sampler, SamplerAddressMode, LOD_CLAMP_NONE,
+};
+use crate::render::backend::shader::{Shader, ShaderStage};
+use crate::render::backend::{Extent2D, Extent3D};
+
+/// Format of baked environment maps, they are written by compute shaders and need filtering.
+pub const ENVIRONMENT_FORMAT: ImageFormat = ImageFormat::R16G16B16A16;
+
+/// How environment maps are baked when they are imported, see
+/// [`AssetManager::set_environment_options`](crate::asset::manager::AssetManager::set_environment_options).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EnvironmentOptions {
+    /// Size of the cubemap faces an equirectangular map is converted to, a quarter of its width by default.
+    /// Cubemaps imported from six faces keep the size of their faces.
+    pub face_size: Option<u32>,
+    /// Fills the mip levels with the environment prefiltered for increasing roughness, from 0 at the first
+    /// level to 1 at the last, instead of downsampling it.
+    pub prefilter_specular: bool,
+    /// Face size of the diffuse irradiance map, no irradiance map is baked if this is `None`.
+    pub irradiance_size: Option<u32>,
+}
+
+/// A cubemap baked by the [`EnvironmentBaker`] and the irradiance map of it, if one was asked for.
+pub struct BakedEnvironment {
+    pub cubemap: Image,
+    pub irradiance: Option<Image>,
+}
+
+/// Turns equirectangular maps into cubemaps and bakes the maps used for image based lighting with
+/// compute shaders. All cubemaps are [`ENVIRONMENT_FORMAT`] with faces ordered +x, -x, +y, -y, +z, -z.
+pub struct EnvironmentBaker {
+    device: Device,
+    sampler: Sampler,
+    to_cubemap: ComputePass,
+    prefilter: ComputePass,
+    irradiance: ComputePass,
+}
+
+/// A compute pipeline that reads a sampled image and writes a storage image.
+struct ComputePass {
+    layout: DescriptorSetLayout,
+    pipeline: Pipeline<Compute>,
+}
+
+impl ComputePass {
+    fn new(device: &Device, source: &str, name: &str, push_constant_size: u32) -> Self {
+        let shader = Shader::compile(
+            device.clone(),
+            source,
+            ShaderKind::Compute,
+            Some(name.to_string()),
+            &[],
+        );
+        let binding = |index, ty| DescriptorSetLayoutBinding {
+            index,
+            stages: ShaderStage::Compute,
+            ty,
+            count: 1,
+        };
+        let layout = DescriptorSetLayout::new(
+            device.clone(),
+            MVDescriptorSetLayoutCreateInfo {
+                bindings: vec![
+                    binding(0, DescriptorType::CombinedImageSampler),
+                    binding(1, DescriptorType::StorageImage),
+                ],
+                label: Some(name.to_string()),
+            },
+        );
+        let push_constants = if push_constant_size > 0 {
+            vec![pipeline::PushConstant {
+                size: push_constant_size,
+                offset: 0,
+                shader: ShaderStage::Compute,
+            }]
+        } else {
+            Vec::new()
+        };
+        let pipeline = Pipeline::<Compute>::new(
+            device.clone(),
+            MVComputePipelineCreateInfo {
+                shader,
+                descriptor_sets: vec![layout.clone()],
+                push_constants,
+                label: Some(name.to_string()),
+            },
+        );
+
+        Self { layout, pipeline }
+    }
+
+    /// Binds the pass with `source` and `level` of `target`, the descriptor set has to be kept alive
+    /// until the command buffer finished executing.
+    fn bind(
+        &self,
+        device: &Device,
+        cmd: &CommandBuffer,
+        pool: &DescriptorPool,
+        (source, sampler): (&Image, &Sampler),
+        target: &Image,
+        level: u32,
+    ) -> DescriptorSet {
+        let mut set = DescriptorSet::from_layout(
+            device.clone(),
+            MVDescriptorSetFromLayoutCreateInfo {
+                pool: pool.clone(),
+                layout: self.layout.clone(),
+                label: None,
+            },
+        );
+        set.add_image(0, source, sampler, ImageLayout::ShaderReadOnlyOptimal);
+        set.add_storage_image(1, target, level);
+        set.build();
+
+        self.pipeline.bind(cmd);
+        set.bind(cmd, &self.pipeline, 0);
+        set
+    }
+
+    /// Runs one invocation for every texel of every face of `level` of `target`.
+    fn dispatch(&self, cmd: &CommandBuffer, target: &Image, level: u32) {
+        let extent = target.get_extent().get_mip_extent(level);
+        cmd.dispatch(Extent3D {
+            width: extent.width.div_ceil(8),
+            height: extent.height.div_ceil(8),
+            depth: target.get_layer_count(),
+        });
+    }
+}
+
+impl EnvironmentBaker {
+    /// Compiles the compute shaders of all passes.
+    pub fn new(device: Device) -> Self {
+        let sampler = Sampler::new(
+            device.clone(),
+            MVSamplerCreateInfo {
+                address_mode: SamplerAddressMode::Repeat,
+                filter_mode: Filter::Linear,
+                mipmap_mode: MipmapMode::Linear,
+                anisotropy: None,
+                lod_bias: 0.0,
+                min_lod: 0.0,
+                max_lod: LOD_CLAMP_NONE,
+                label: Some("Environment sampler".to_string()),
+            },
+        );
+
+        Self {
+            to_cubemap: ComputePass::new(
+                &device,
+                include_str!("shaders/environment/equirect_to_cubemap.comp"),
+                "equirect_to_cubemap.comp",
+                0,
+            ),
+            prefilter: ComputePass::new(
+                &device,
+                include_str!("shaders/environment/prefilter.comp"),
+                "prefilter.comp",
+                std::mem::size_of::<f32>() as u32,
+            ),
+            irradiance: ComputePass::new(
+                &device,
+                include_str!("shaders/environment/irradiance.comp"),
+                "irradiance.comp",
+                0,
+            ),
+            device,
+            sampler,
+        }
+    }
+
+    /// A cubemap with a full mip chain that can be written by the passes, `data` holds either all
+    /// mip levels or only the first one, like for [`MVImageCreateInfo::data`].
+    pub fn create_cubemap(device: &Device, face_size: u32, data: Option<Vec<u8>>, label: &str) -> Image {
+        let size = Extent2D {
+            width: face_size,
+            height: face_size,
+        };
+        Image::new(
+            device.clone(),
+            MVImageCreateInfo {
+                size,
+                format: ENVIRONMENT_FORMAT,
+                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED | ImageUsage::STORAGE,
+                memory_properties: MemoryProperties::DEVICE_LOCAL,
+                aspect: ImageAspect::COLOR,
+                tiling: ImageTiling::Optimal,
+                layer_count: 6,
+                mip_level_count: size.get_max_mip_levels(),
+                image_type: ImageType::Cubemap,
+                cubemap: true,
+                memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                data,
+                label: Some(label.to_string()),
+            },
+        )
+    }
+
+    /// Converts an equirectangular map into a cubemap with faces of `face_size`, then bakes it like
+    /// [`EnvironmentBaker::bake_cubemap`]. `equirect` has to be ready for sampling.
+    pub fn bake_equirect(
+        &self,
+        equirect: &Image,
+        face_size: u32,
+        options: &EnvironmentOptions,
+        label: &str,
+    ) -> BakedEnvironment {
+        let cubemap = Self::create_cubemap(&self.device, face_size, None, label);
+        let cmd = self
+            .device
+            .begin_single_time_command(self.device.get_graphics_command_pool());
+        let pool = self.create_pool(cubemap.get_mip_level_count() + 2);
+
+        cubemap.transition_layout(
+            ImageLayout::General,
+            Some(&cmd),
+            AccessFlags::empty(),
+            AccessFlags::empty(),
+        );
+        let mut sets = vec![self.to_cubemap.bind(
+            &self.device,
+            &cmd,
+            &pool,
+            (equirect, &self.sampler),
+            &cubemap,
+            0,
+        )];
+        self.to_cubemap.dispatch(&cmd, &cubemap, 0);
+        cubemap.generate_mipmaps(Some(&cmd));
+
+        let baked = self.record_maps(&cmd, &pool, cubemap, options, label, &mut sets);
+        self.device.end_single_time_command(
+            cmd,
+            self.device.get_graphics_command_pool(),
+            self.device.get_graphics_queue(),
+        );
+        baked
+    }
+
+    /// Bakes the prefiltered mip levels and the irradiance map of a cubemap, depending on `options`.
+    /// `cubemap` has to be ready for sampling with all of its mip levels, with prefiltering a new
+    /// cubemap of the same size is returned in its place.
+    pub fn bake_cubemap(
+        &self,
+        cubemap: Image,
+        options: &EnvironmentOptions,
+        label: &str,
+    ) -> BakedEnvironment {
+        let cmd = self
+            .device
+            .begin_single_time_command(self.device.get_graphics_command_pool());
+        let pool = self.create_pool(cubemap.get_mip_level_count() + 1);
+        let mut sets = Vec::new();
+
+        let baked = self.record_maps(&cmd, &pool, cubemap, options, label, &mut sets);
+        self.device.end_single_time_command(
+            cmd,
+            self.device.get_graphics_command_pool(),
+            self.device.get_graphics_queue(),
+        );
+        baked
+    }
+
+    fn record_maps(
+        &self,
+        cmd: &CommandBuffer,
+        pool: &DescriptorPool,
+        cubemap: Image,
+        options: &EnvironmentOptions,
+        label: &str,
+        sets: &mut Vec<DescriptorSet>,
+    ) -> BakedEnvironment {
+        let source = (&cubemap, &self.sampler);
+
+        // Every level is filtered from the downsampled source, the smaller levels of it keep the
+        // samples of rough levels from missing bright spots
+        let specular = options.prefilter_specular.then(|| {
+            let specular = Self::create_cubemap(&self.device, cubemap.get_extent().width, None, label);
+            specular.transition_layout(
+                ImageLayout::General,
+                Some(cmd),
+                AccessFlags::empty(),
+                AccessFlags::empty(),
+            );
+            let last_level = (specular.get_mip_level_count() - 1).max(1);
+            for level in 0..specular.get_mip_level_count() {
+                sets.push(self.prefilter.bind(&self.device, cmd, pool, source, &specular, level));
+                let roughness = PushConstant::new(
+                    self.device.clone(),
+                    MVPushConstantCreateInfo {
+                        stage: ShaderStage::Compute,
+                        value: level as f32 / last_level as f32,
+                    },
+                );
+                roughness.push(cmd, &self.prefilter.pipeline);
+                self.prefilter.dispatch(cmd, &specular, level);
+            }
+            specular.transition_layout(
+                ImageLayout::ShaderReadOnlyOptimal,
+                Some(cmd),
+                AccessFlags::empty(),
+                AccessFlags::empty(),
+            );
+            specular
+        });
+
+        let irradiance = options.irradiance_size.map(|size| {
+            let size = Extent2D {
+                width: size,
+                height: size,
+            };
+            let irradiance = Image::new(
+                self.device.clone(),
+                MVImageCreateInfo {
+                    size,
+                    format: ENVIRONMENT_FORMAT,
+                    usage: ImageUsage::SAMPLED | ImageUsage::STORAGE,
+                    memory_properties: MemoryProperties::DEVICE_LOCAL,
+                    aspect: ImageAspect::COLOR,
+                    tiling: ImageTiling::Optimal,
+                    layer_count: 6,
+                    mip_level_count: 1,
+                    image_type: ImageType::Cubemap,
+                    cubemap: true,
+                    memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                    data: None,
+                    label: Some(format!("{label} irradiance")),
+                },
+            );
+            irradiance.transition_layout(
+                ImageLayout::General,
+                Some(cmd),
+                AccessFlags::empty(),
+                AccessFlags::empty(),
+            );
+            sets.push(self.irradiance.bind(&self.device, cmd, pool, source, &irradiance, 0));
+            self.irradiance.dispatch(cmd, &irradiance, 0);
+            irradiance.transition_layout(
+                ImageLayout::ShaderReadOnlyOptimal,
+                Some(cmd),
+                AccessFlags::empty(),
+                AccessFlags::empty(),
+            );
+            irradiance
+        });
+
+        BakedEnvironment {
+            cubemap: specular.unwrap_or(cubemap),
+            irradiance,
+        }
+    }
+
+    fn create_pool(&self, max_sets: u32) -> DescriptorPool {
+        DescriptorPool::new(
+            self.device.clone(),
+            MVDescriptorPoolCreateInfo {
+                sizes: vec![
+                    DescriptorPoolSize {
+                        ty: DescriptorType::CombinedImageSampler,
+                        count: max_sets,
+                    },
+                    DescriptorPoolSize {
+                        ty: DescriptorType::StorageImage,
+                        count: max_sets,
+                    },
+                ],
+                max_sets,
+                flags: DescriptorPoolFlags::empty(),
+                label: Some("Environment descriptor pool".to_string()),
+            },
+        )
+    }
+}
@@ -108,6 +108,16 @@ impl Mesh {
         ));
     }
 
+    /// Size of the vertex and index buffers in bytes.
+    pub fn get_size(&self) -> u64 {
+        self.vertex_buffer.get_size()
+            + self
+                .index_buffer
+                .as_ref()
+                .map(|buffer| buffer.get_size())
+                .unwrap_or_default()
+    }
+
     pub fn draw(&self, cmd: &CommandBuffer) {
         cmd.bind_vertex_buffer(&self.vertex_buffer);
 
@@ -5,10 +5,14 @@ pub mod mesh;
 pub mod renderer;
 pub mod window;
 
+pub mod atlas;
 pub mod camera;
+pub mod environment;
 pub mod model;
 pub mod texture;
 
+/// Callbacks of [`Window::run`]. The keyboard and mouse state is available through [`Window::get_input`],
+/// presses and releases show up as `JustPressed` and `JustReleased` during one update.
 pub trait ApplicationLoopCallbacks {
     fn new(window: &mut Window) -> Self;
     fn update(&mut self, window: &mut Window, delta_t: f64);
@@ -1,37 +1,215 @@
 use crate::asset::manager::AssetHandle;
-use crate::math::vec::Vec4;
+use crate::math::mat::Mat4;
+use crate::math::quat::Quat;
+use crate::math::vec::{Vec2, Vec3, Vec4};
+use crate::render::backend::pipeline::{AttributeType, Topology};
+use crate::render::mesh::Mesh;
+
+/// Vertex layout of every mesh imported from a model file.
+#[derive(Default, Debug, Copy, Clone)]
+#[repr(C)]
+pub struct ModelVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub tex_coord: Vec2,
+    pub tangent: Vec4,
+    pub color: Vec4,
+}
+
+impl ModelVertex {
+    pub fn get_attribute_description() -> Vec<AttributeType> {
+        vec![
+            AttributeType::Float32x3,
+            AttributeType::Float32x3,
+            AttributeType::Float32x2,
+            AttributeType::Float32x4,
+            AttributeType::Float32x4,
+        ]
+    }
+}
 
 pub struct Model {
+    pub(crate) meshes: Vec<ModelMesh>,
+    pub(crate) materials: Vec<Material>,
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) root_nodes: Vec<usize>,
+    pub(crate) textures: Vec<AssetHandle>,
+}
+
+impl Model {
+    pub fn get_meshes(&self) -> &[ModelMesh] {
+        &self.meshes
+    }
+
+    pub fn get_materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    /// All nodes of the model, the hierarchy is described by [`Node::parent`] and
+    /// [`Node::children`], which index into this slice.
+    pub fn get_nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Nodes of the default scene without a parent.
+    pub fn get_root_nodes(&self) -> &[usize] {
+        &self.root_nodes
+    }
+
+    /// Textures referenced by the materials, already loaded together with the model.
+    pub fn get_textures(&self) -> &[AssetHandle] {
+        &self.textures
+    }
+
+    /// Size of the vertex and index buffers of all meshes in bytes, textures are separate assets
+    /// and not included.
+    pub fn get_size(&self) -> u64 {
+        self.meshes
+            .iter()
+            .flat_map(|mesh| &mesh.primitives)
+            .map(|primitive| primitive.mesh.get_size())
+            .sum()
+    }
+}
+
+pub struct ModelMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+}
+
+pub struct Primitive {
+    pub mesh: Mesh,
+    pub topology: Topology,
+    /// Index into [`Model::get_materials`], `None` means the default material.
+    pub material: Option<usize>,
+}
+
+pub struct Node {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Index into [`Model::get_meshes`].
+    pub mesh: Option<usize>,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Node {
+    /// Transform relative to the parent node.
+    pub fn get_local_transform(&self) -> Mat4 {
+        Mat4::view(
+            Vec4::new(
+                self.translation.x,
+                self.translation.y,
+                self.translation.z,
+                1.0,
+            ),
+            self.rotation,
+            Vec4::new(self.scale.x, self.scale.y, self.scale.z, 1.0),
+        )
+    }
+}
+
+/// Textures are referenced by handle, cooked and not yet imported materials use other references.
+pub struct Material<T = AssetHandle> {
+    pub color: Vec4,
+    pub color_tex: Option<T>,
+    pub metallic: f32,
+    pub metallic_tex: Option<T>,
+    pub roughness: f32,
+    pub roughness_tex: Option<T>,
+    pub reflectance: f32,
+    pub reflectance_tex: Option<T>,
+    pub clear_coat: f32,
+    pub clear_coat_tex: Option<T>,
+    pub clear_coat_roughness: f32,
+    pub clear_coat_roughness_tex: Option<T>,
+    pub anisotropy: f32,
+    pub anisotropy_tex: Option<T>,
+    pub anisotropy_direction: Vec4,
+    pub ambient_occlusion: f32,
+    pub ambient_occlusion_tex: Option<T>,
+    pub normal: Vec4,
+    pub normal_tex: Option<T>,
+    pub clear_coat_normal: Vec4,
+    pub clear_coat_normal_tex: Option<T>,
+    pub emissive: Vec4,
+    pub emissive_tex: Option<T>,
+    pub ior: f32,
+    pub transmission: f32,
+    pub absorption: f32,
+    pub thickness: f32,
+    pub sheen_color: Vec4,
+}
 
+impl<T> Default for Material<T> {
+    fn default() -> Self {
+        Self {
+            color: Vec4::splat(1.0),
+            color_tex: None,
+            metallic: 0.0,
+            metallic_tex: None,
+            roughness: 1.0,
+            roughness_tex: None,
+            reflectance: 0.5,
+            reflectance_tex: None,
+            clear_coat: 0.0,
+            clear_coat_tex: None,
+            clear_coat_roughness: 0.0,
+            clear_coat_roughness_tex: None,
+            anisotropy: 0.0,
+            anisotropy_tex: None,
+            anisotropy_direction: Vec4::new(1.0, 0.0, 0.0, 0.0),
+            ambient_occlusion: 1.0,
+            ambient_occlusion_tex: None,
+            normal: Vec4::new(0.0, 0.0, 1.0, 0.0),
+            normal_tex: None,
+            clear_coat_normal: Vec4::new(0.0, 0.0, 1.0, 0.0),
+            clear_coat_normal_tex: None,
+            emissive: Vec4::new(0.0, 0.0, 0.0, 1.0),
+            emissive_tex: None,
+            ior: 1.5,
+            transmission: 0.0,
+            absorption: 0.0,
+            thickness: 0.0,
+            sheen_color: Vec4::splat(0.0),
+        }
+    }
 }
 
-pub struct Material {
-    color: Vec4,
-    color_tex: Option<AssetHandle>,
-    metallic: f32,
-    metallic_tex: Option<AssetHandle>,
-    roughness: f32,
-    roughness_tex: Option<AssetHandle>,
-    reflectance: f32,
-    reflectance_tex: Option<AssetHandle>,
-    clear_coat: f32,
-    clear_coat_tex: Option<AssetHandle>,
-    clear_coat_roughness: f32,
-    clear_coat_roughness_tex: Option<AssetHandle>,
-    anisotropy: f32,
-    anisotropy_tex: Option<AssetHandle>,
-    anisotropy_direction: Vec4,
-    ambient_occlusion: f32,
-    ambient_occlusion_tex: Option<AssetHandle>,
-    normal: Vec4,
-    normal_tex: Option<AssetHandle>,
-    clear_coat_normal: Vec4,
-    clear_coat_normal_tex: Option<AssetHandle>,
-    emissive: Vec4,
-    emissive_tex: Option<AssetHandle>,
-    ior: f32,
-    transmission: f32,
-    absorption: f32,
-    thickness: f32,
-    sheen_color: Vec4,
+impl<T> Material<T> {
+    /// Replaces every texture reference with the result of `texture`.
+    pub fn map_textures<U>(self, mut texture: impl FnMut(T) -> U) -> Material<U> {
+        Material {
+            color: self.color,
+            color_tex: self.color_tex.map(&mut texture),
+            metallic: self.metallic,
+            metallic_tex: self.metallic_tex.map(&mut texture),
+            roughness: self.roughness,
+            roughness_tex: self.roughness_tex.map(&mut texture),
+            reflectance: self.reflectance,
+            reflectance_tex: self.reflectance_tex.map(&mut texture),
+            clear_coat: self.clear_coat,
+            clear_coat_tex: self.clear_coat_tex.map(&mut texture),
+            clear_coat_roughness: self.clear_coat_roughness,
+            clear_coat_roughness_tex: self.clear_coat_roughness_tex.map(&mut texture),
+            anisotropy: self.anisotropy,
+            anisotropy_tex: self.anisotropy_tex.map(&mut texture),
+            anisotropy_direction: self.anisotropy_direction,
+            ambient_occlusion: self.ambient_occlusion,
+            ambient_occlusion_tex: self.ambient_occlusion_tex.map(&mut texture),
+            normal: self.normal,
+            normal_tex: self.normal_tex.map(&mut texture),
+            clear_coat_normal: self.clear_coat_normal,
+            clear_coat_normal_tex: self.clear_coat_normal_tex.map(&mut texture),
+            emissive: self.emissive,
+            emissive_tex: self.emissive_tex.map(&mut texture),
+            ior: self.ior,
+            transmission: self.transmission,
+            absorption: self.absorption,
+            thickness: self.thickness,
+            sheen_color: self.sheen_color,
+        }
+    }
 }
@@ -1,5 +1,9 @@
+use std::sync::Arc;
+
 use mvutils::remake::Remake;
-use shaderc::{OptimizationLevel, ShaderKind, TargetEnv};
+use shaderc::ShaderKind;
+
+use crate::asset::manager::AssetManager;
 
 use crate::render::backend::command_buffer::{
     CommandBuffer, CommandBufferLevel, MVCommandBufferCreateInfo,
@@ -8,7 +12,7 @@ use crate::render::backend::device::Device;
 use crate::render::backend::Extent2D;
 use crate::render::backend::framebuffer::Framebuffer;
 use crate::render::backend::image::{AccessFlags, Image, ImageLayout};
-use crate::render::backend::shader::{MVShaderCreateInfo, Shader};
+use crate::render::backend::shader::Shader;
 use crate::render::backend::swapchain::{MVSwapchainCreateInfo, Swapchain, SwapchainError};
 use crate::render::window::Window;
 
@@ -22,6 +26,7 @@ pub struct Renderer {
     max_frames_in_flight: u32,
     width: u32,
     height: u32,
+    assets: Option<Arc<AssetManager>>,
 }
 
 impl Renderer {
@@ -62,9 +67,17 @@ impl Renderer {
             max_frames_in_flight: window.info.max_frames_in_flight,
             width: window.get_extent().width,
             height: window.get_extent().height,
+            assets: None,
         }
     }
 
+    /// Keeps the gpu resources of unloaded and replaced assets alive until no frame in flight uses
+    /// them anymore, and destroys them at the end of the frame after that.
+    pub fn set_asset_manager(&mut self, assets: Arc<AssetManager>) {
+        assets.set_max_frames_in_flight(self.swapchain.get_max_frames_in_flight());
+        self.assets = Some(assets);
+    }
+
     pub fn begin_frame(&mut self) -> Result<u32, SwapchainError> {
         self.current_image_index = self.swapchain.acquire_next_image()?;
         self.get_current_command_buffer().begin();
@@ -80,6 +93,9 @@ impl Renderer {
         self.swapchain.submit_command_buffer(cmd, index)?;
 
         self.current_frame = (self.current_frame + 1) % self.max_frames_in_flight;
+        if let Some(assets) = &self.assets {
+            assets.end_frame();
+        }
         Ok(())
     }
 
@@ -178,6 +194,10 @@ impl Renderer {
             }
         }
 
+        if let Some(assets) = &self.assets {
+            assets.set_max_frames_in_flight(self.swapchain.get_max_frames_in_flight());
+        }
+
         self.vsync = vsync;
         self.max_frames_in_flight = max_frames_in_flight;
         self.width = width;
@@ -213,33 +233,6 @@ impl Renderer {
     }
 
     pub fn compile_shader(&self, data: &str, kind: ShaderKind, name: Option<String>, defines: &[String]) -> Shader {
-        let compiler = shaderc::Compiler::new().unwrap();
-        let mut options = shaderc::CompileOptions::new().unwrap();
-        options.set_optimization_level(OptimizationLevel::Performance);
-
-        for define in defines {
-            options.add_macro_definition(define.as_str(), None);
-        }
-        options.set_target_env(TargetEnv::Vulkan, ash::vk::API_VERSION_1_2);
-        let code = compiler
-            .compile_into_spirv(
-                data,
-                kind,
-                name.as_ref().unwrap_or(&"".to_string()),
-                "main",
-                Some(&options),
-            )
-            .unwrap()
-            .as_binary()
-            .to_vec();
-
-        Shader::new(
-            self.device.clone(),
-            MVShaderCreateInfo {
-                stage: kind.into(),
-                code,
-                label: name,
-            },
-        )
+        Shader::compile(self.device.clone(), data, kind, name, defines)
     }
 }
@@ -16,7 +16,13 @@ impl Texture {
     }
 
     pub fn as_region(&self, x: u32, y: u32, width: u32, height: u32) -> TextureRegion {
-        TextureRegion::new(self.clone(), x, self.image.get_extent().height - y - height, width, height)
+        TextureRegion::new(
+            self.clone(),
+            x,
+            self.image.get_extent().height - y - height,
+            width,
+            height,
+        )
     }
 
     pub fn as_full_region(&self) -> TextureRegion {
@@ -32,6 +38,16 @@ impl Texture {
     pub fn image(&self) -> Image {
         self.image.clone()
     }
+
+    /// Size of the pixel data of all mip levels and layers in bytes.
+    pub fn get_size(&self) -> u64 {
+        let extent = self.image.get_extent();
+        let format = self.image.get_format();
+        let layer_size = (0..self.image.get_mip_level_count())
+            .map(|level| format.get_data_size(extent.get_mip_extent(level)))
+            .sum::<u64>();
+        layer_size * self.image.get_layer_count() as u64
+    }
 }
 
 impl PartialEq for Texture {
@@ -70,4 +86,4 @@ impl TextureRegion {
     pub fn coords(&self) -> Vec4 {
         Vec4::new(self.x, self.y, self.width, self.height)
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,14 @@
+use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 
-use winit::dpi::{PhysicalSize, Size};
+use winit::dpi::{PhysicalPosition, PhysicalSize, Size};
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::{Fullscreen, Theme, WindowBuilder};
 
+use crate::asset::manager::AssetManager;
+use crate::input::raw::Input;
+use crate::input::InputCollector;
 use crate::render::ApplicationLoopCallbacks;
 use crate::render::backend::Extent2D;
 
@@ -105,6 +109,8 @@ pub struct Window {
     handle: winit::window::Window,
     state: State,
     event_loop: Option<EventLoop<()>>,
+    input: InputCollector,
+    assets: Option<Arc<AssetManager>>,
 
     frame_time_nanos: u64,
     update_time_nanos: u64,
@@ -136,6 +142,8 @@ impl Window {
             handle: window,
             state: State::Ready,
             event_loop: Some(event_loop),
+            input: InputCollector::new(Arc::new(RwLock::new(Input::new()))),
+            assets: None,
             delta_t: 0.0,
             delta_u: 0.0,
         }
@@ -159,7 +167,11 @@ impl Window {
                         time_u = SystemTime::now();
                         self.delta_u = elapsed as f64 / NANOS_PER_SEC as f64;
                         let delta_u = self.delta_u;
+                        if let Some(assets) = &self.assets {
+                            assets.update();
+                        }
                         app_loop.update(&mut self, delta_u);
+                        self.input.end_tick();
                     }
 
                     let elapsed = time_f.elapsed().expect("SystemTime error").as_nanos();
@@ -176,42 +188,45 @@ impl Window {
                     self.state = State::Exited;
                     app_loop.exiting(&mut self);
                 }
-                Event::WindowEvent { window_id, event } => match event {
-                    WindowEvent::ActivationTokenDone { .. } => {}
-                    WindowEvent::Resized(size) => {
-                        self.info.width = size.width;
-                        self.info.height = size.height;
-                        app_loop.resize(&mut self, size.width, size.height);
+                Event::WindowEvent { window_id, event } => {
+                    self.input.collect_window_event(&event);
+                    match event {
+                        WindowEvent::ActivationTokenDone { .. } => {}
+                        WindowEvent::Resized(size) => {
+                            self.info.width = size.width;
+                            self.info.height = size.height;
+                            app_loop.resize(&mut self, size.width, size.height);
+                        }
+                        WindowEvent::Moved(_) => {}
+                        WindowEvent::CloseRequested => target.exit(),
+                        WindowEvent::Destroyed => {}
+                        WindowEvent::DroppedFile(_) => {}
+                        WindowEvent::HoveredFile(_) => {}
+                        WindowEvent::HoveredFileCancelled => {}
+                        WindowEvent::Focused(_) => {}
+                        WindowEvent::KeyboardInput { .. } => {}
+                        WindowEvent::ModifiersChanged(_) => {}
+                        WindowEvent::Ime(_) => {}
+                        WindowEvent::CursorMoved { .. } => {}
+                        WindowEvent::CursorEntered { .. } => {}
+                        WindowEvent::CursorLeft { .. } => {}
+                        WindowEvent::MouseWheel { .. } => {}
+                        WindowEvent::MouseInput { .. } => {}
+                        WindowEvent::TouchpadMagnify { .. } => {}
+                        WindowEvent::SmartMagnify { .. } => {}
+                        WindowEvent::TouchpadRotate { .. } => {}
+                        WindowEvent::TouchpadPressure { .. } => {}
+                        WindowEvent::AxisMotion { .. } => {}
+                        WindowEvent::Touch(_) => {}
+                        WindowEvent::ScaleFactorChanged { .. } => {}
+                        WindowEvent::ThemeChanged(_) => {}
+                        WindowEvent::Occluded(_) => {}
+                        WindowEvent::RedrawRequested => {
+                            let delta_t = self.delta_t;
+                            app_loop.draw(&mut self, delta_t);
+                        }
                     }
-                    WindowEvent::Moved(_) => {}
-                    WindowEvent::CloseRequested => target.exit(),
-                    WindowEvent::Destroyed => {}
-                    WindowEvent::DroppedFile(_) => {}
-                    WindowEvent::HoveredFile(_) => {}
-                    WindowEvent::HoveredFileCancelled => {}
-                    WindowEvent::Focused(_) => {}
-                    WindowEvent::KeyboardInput { .. } => {}
-                    WindowEvent::ModifiersChanged(_) => {}
-                    WindowEvent::Ime(_) => {}
-                    WindowEvent::CursorMoved { .. } => {}
-                    WindowEvent::CursorEntered { .. } => {}
-                    WindowEvent::CursorLeft { .. } => {}
-                    WindowEvent::MouseWheel { .. } => {}
-                    WindowEvent::MouseInput { .. } => {}
-                    WindowEvent::TouchpadMagnify { .. } => {}
-                    WindowEvent::SmartMagnify { .. } => {}
-                    WindowEvent::TouchpadRotate { .. } => {}
-                    WindowEvent::TouchpadPressure { .. } => {}
-                    WindowEvent::AxisMotion { .. } => {}
-                    WindowEvent::Touch(_) => {}
-                    WindowEvent::ScaleFactorChanged { .. } => {}
-                    WindowEvent::ThemeChanged(_) => {}
-                    WindowEvent::Occluded(_) => {}
-                    WindowEvent::RedrawRequested => {
-                        let delta_t = self.delta_t;
-                        app_loop.draw(&mut self, delta_t);
-                    }
-                },
+                }
                 _ => {}
             })
             .unwrap()
@@ -232,6 +247,40 @@ impl Window {
         &self.handle
     }
 
+    /// The keyboard and mouse state, updated from the events of the window. `JustPressed` and
+    /// `JustReleased` states, the scroll and the typed text last until the end of the next update.
+    pub fn get_input(&self) -> Arc<RwLock<Input>> {
+        self.input.get_input()
+    }
+
+    pub fn get_input_collector(&mut self) -> &mut InputCollector {
+        &mut self.input
+    }
+
+    /// Runs the load callbacks of the asset manager before every update.
+    pub fn set_asset_manager(&mut self, assets: Arc<AssetManager>) {
+        self.assets = Some(assets);
+    }
+
+    /// Lets the input method compose text, like Chinese characters or accents from dead keys, and
+    /// commit it as typed text. Enable it while a text field has focus, games should leave it disabled
+    /// because key presses that compose text are not reported as key presses.
+    pub fn set_ime_enabled(&mut self, enabled: bool) {
+        self.handle.set_ime_allowed(enabled);
+        if !enabled {
+            self.input.ime_disabled();
+        }
+    }
+
+    /// Places the candidate window of the input method next to the text being composed. The area is
+    /// in pixels from the top left of the window, X11 only uses its position.
+    pub fn set_ime_cursor_area(&self, x: i32, y: i32, width: u32, height: u32) {
+        self.handle.set_ime_cursor_area(
+            PhysicalPosition::new(x, y),
+            PhysicalSize::new(width, height),
+        );
+    }
+
     pub fn get_delta_t(&self) -> f64 {
         self.delta_t
     }
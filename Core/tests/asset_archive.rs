@@ -0,0 +1,212 @@
+//! Packs assets into an archive and loads them through the virtual file system of the asset
+//! manager, on the null backend.
+
+use std::io::ErrorKind;
+use std::path::Path;
+
+use include_dir::{include_dir, Dir};
+use log::LevelFilter;
+use mvcore::asset::archive::{ArchiveWriter, AssetArchive, Compression};
+use mvcore::asset::token::LoadStatus;
+use mvcore::asset::vfs::{self, AssetSource};
+use mvcore::render::model::Model;
+
+mod common;
+
+static TESTS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/tests");
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [{ "mesh": 0 }],
+    "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+    "materials": [{ "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } }],
+    "textures": [{ "source": 0 }],
+    "images": [{ "uri": "../textures/red%20square.png" }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+    "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+    "accessors": [{
+        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+fn write_texture(path: &Path, size: u32) {
+    std::fs::create_dir_all(path.parent().unwrap()).expect("Failed to create directory");
+    image::RgbaImage::from_pixel(size, size, image::Rgba([255, 0, 0, 255]))
+        .save(path)
+        .expect("Failed to write texture");
+}
+
+/// An archive with a single index entry and no data, with the sizes of the header and the entry
+/// taken as they are.
+fn raw_archive(
+    count: u32,
+    index_size: u64,
+    compression: u8,
+    offset: u64,
+    stored: u64,
+    size: u64,
+) -> Vec<u8> {
+    let mut bytes = b"MVPK".to_vec();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes.extend_from_slice(&index_size.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.push(b'a');
+    bytes.push(compression);
+    for value in [offset, stored, size] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    assert_eq!(
+        vfs::normalize("./textures\\..//models/a.gltf"),
+        "models/a.gltf"
+    );
+    assert_eq!(vfs::normalize("/a/./b/"), "a/b");
+
+    let dir = common::test_dir("asset_archive");
+    let loose = dir.join("loose");
+    std::fs::create_dir_all(loose.join("models")).expect("Failed to create test directory");
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    std::fs::write(loose.join("models/triangle.bin"), &bin).expect("Failed to write buffer");
+    std::fs::write(loose.join("models/triangle.gltf"), GLTF).expect("Failed to write model");
+    write_texture(&loose.join("textures/red square.png"), 2);
+    let notes = "compresses well ".repeat(64);
+    std::fs::write(loose.join("notes.txt"), &notes).expect("Failed to write notes");
+
+    // Packing
+    let mut writer = ArchiveWriter::new();
+    writer
+        .add_directory(&loose, Compression::Deflate)
+        .expect("Failed to pack directory");
+    assert_eq!(writer.len(), 4);
+    let packed = dir.join("assets.mvpk");
+    writer
+        .write_to_file(&packed)
+        .expect("Failed to write archive");
+
+    let archive = AssetArchive::open(&packed).expect("Failed to open archive");
+    let mut paths = archive.paths().collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "models/triangle.bin",
+            "models/triangle.gltf",
+            "notes.txt",
+            "textures/red square.png"
+        ]
+    );
+    let entry = archive.get_entry("notes.txt").unwrap();
+    assert_eq!(entry.compression, Compression::Deflate);
+    assert!(entry.stored_size < entry.size);
+    assert_eq!(archive.read("./notes.txt").unwrap(), notes.as_bytes());
+    assert_eq!(archive.read("models/triangle.bin").unwrap(), bin);
+    assert_eq!(
+        archive.read("missing.txt").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    // Corrupted data is caught by the checksum
+    let mut bytes = std::fs::read(&packed).unwrap();
+    assert!(AssetArchive::from_bytes(bytes.clone()).is_ok());
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    let corrupted = AssetArchive::from_bytes(bytes).unwrap();
+    let failures = corrupted
+        .paths()
+        .filter(|path| corrupted.read(path).is_err())
+        .count();
+    assert_eq!(failures, 1);
+    assert!(AssetArchive::from_bytes(b"nope".to_vec()).is_err());
+
+    // Sizes and offsets from the header and index are checked before anything is allocated
+    let invalid = |bytes: Vec<u8>| AssetArchive::from_bytes(bytes).err().map(|e| e.kind());
+    assert!(AssetArchive::from_bytes(raw_archive(1, 34, 0, 0, 0, 0)).is_ok());
+    assert_eq!(
+        invalid(raw_archive(1, u64::MAX, 0, 0, 0, 0)),
+        Some(ErrorKind::InvalidData)
+    );
+    assert_eq!(
+        invalid(raw_archive(1, 35, 0, 0, 0, 0)),
+        Some(ErrorKind::InvalidData)
+    );
+    assert_eq!(
+        invalid(raw_archive(1, 34, 0, u64::MAX, 1, 1)),
+        Some(ErrorKind::InvalidData)
+    );
+    assert_eq!(
+        invalid(raw_archive(1, 34, 0, 0, u64::MAX, u64::MAX)),
+        Some(ErrorKind::InvalidData)
+    );
+    assert_eq!(
+        invalid(raw_archive(1, 34, 1, 0, 0, u64::MAX)),
+        Some(ErrorKind::InvalidData)
+    );
+    assert_eq!(
+        invalid(raw_archive(u32::MAX, 34, 0, 0, 0, 0)),
+        Some(ErrorKind::UnexpectedEof)
+    );
+    let huge = dir.join("huge.mvpk");
+    std::fs::write(&huge, raw_archive(1, u64::MAX - 8, 0, 0, 0, 0)).unwrap();
+    assert_eq!(
+        AssetArchive::open(&huge).err().map(|e| e.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+
+    // Loading through mounts
+    let manager = common::null_manager("Asset archive test", 2);
+    let fs = manager.get_file_system();
+    fs.mount(AssetSource::Archive(archive), 0);
+    let overrides = dir.join("overrides");
+    write_texture(&overrides.join("textures/red square.png"), 4);
+    let override_mount = fs.mount(AssetSource::Directory(overrides), 10);
+    fs.mount(AssetSource::Embedded(&TESTS), -10);
+
+    assert!(fs.exists("notes.txt"));
+    assert!(!fs.exists("loose/notes.txt"));
+    assert_eq!(fs.read_to_string("notes.txt").unwrap(), notes);
+    assert_eq!(
+        fs.read_to_string("asset_archive.rs").unwrap(),
+        include_str!("asset_archive.rs")
+    );
+    assert!(fs.modified("notes.txt").is_none());
+
+    let model = manager.create::<Model>("models/triangle.gltf");
+    assert_eq!(model.load().wait(), LoadStatus::Loaded);
+    let textures = model.get().unwrap().get_textures().to_vec();
+    assert_eq!(textures[0].get_path(), "models/../textures/red square.png");
+    let texture = textures[0]
+        .get()
+        .as_texture()
+        .expect("Texture did not load");
+    assert_eq!(texture.image().get_extent().width, 4);
+    assert!(fs.modified(textures[0].get_path()).is_some());
+
+    // Without the override the archived texture is used again
+    assert!(fs.unmount(override_mount));
+    assert!(!fs.unmount(override_mount));
+    textures[0].reload();
+    while manager.get_queued() > 0 {
+        std::thread::yield_now();
+    }
+    let texture = textures[0]
+        .get()
+        .as_texture()
+        .expect("Texture did not reload");
+    assert_eq!(texture.image().get_extent().width, 2);
+
+    model.unload();
+    println!("asset archive: ok");
+}
@@ -0,0 +1,120 @@
+//! Keeps unreferenced assets cached within the memory budget of the asset manager and evicts the
+//! least recently used ones, on the null backend.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use log::LevelFilter;
+use mvcore::asset::importer::{AssetImporter, ImportContext};
+use mvcore::asset::manager::Handle;
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::texture::Texture;
+
+mod common;
+
+static IMPORTS: AtomicU32 = AtomicU32::new(0);
+
+/// Imports the bytes of a file, its size is the length of the file.
+struct BlobImporter;
+
+impl AssetImporter for BlobImporter {
+    type Asset = Vec<u8>;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["blob"]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<Vec<u8>, String> {
+        IMPORTS.fetch_add(1, Ordering::AcqRel);
+        context.read(path).map_err(|e| e.to_string())
+    }
+
+    fn size_of(&self, blob: &Vec<u8>) -> u64 {
+        blob.len() as u64
+    }
+}
+
+fn load(blob: &Handle<Vec<u8>>) {
+    assert_eq!(blob.load().wait(), LoadStatus::Loaded);
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    let dir = common::test_dir("asset_budget");
+    for name in ["a", "b", "c"] {
+        std::fs::write(dir.join(format!("{name}.blob")), [0u8; 100]).expect("Failed to write blob");
+    }
+    image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255]))
+        .save(dir.join("texture.png"))
+        .expect("Failed to write texture");
+
+    let manager = common::null_manager("Asset budget test", 2);
+    manager.register_importer(BlobImporter);
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let a = manager.create::<Vec<u8>>(&path("a.blob"));
+    let b = manager.create::<Vec<u8>>(&path("b.blob"));
+    let c = manager.create::<Vec<u8>>(&path("c.blob"));
+
+    // Built in assets are measured as well
+    let texture = manager.create::<Texture>(&path("texture.png"));
+    assert_eq!(texture.load().wait(), LoadStatus::Loaded);
+    // 4x2, 2x1 and 1x1 mip levels
+    assert_eq!(texture.get_asset().get_size(), (8 + 2 + 1) * 4);
+    assert_eq!(manager.get_memory_usage(), (8 + 2 + 1) * 4);
+    texture.unload();
+    common::wait_idle(&manager);
+    assert_eq!(manager.get_memory_usage(), 0);
+
+    // Without a budget, assets are unloaded as soon as they aren't referenced
+    load(&a);
+    assert_eq!(manager.get_memory_usage(), 100);
+    a.unload();
+    common::wait_idle(&manager);
+    assert!(!a.is_loaded());
+    assert_eq!(manager.get_memory_usage(), 0);
+    assert_eq!(IMPORTS.load(Ordering::Acquire), 1);
+
+    // Within the budget, they stay cached and loading them again doesn't import them
+    manager.set_memory_budget(250);
+    load(&a);
+    load(&b);
+    a.unload();
+    b.unload();
+    common::wait_idle(&manager);
+    assert!(a.is_loaded() && b.is_loaded());
+    assert_eq!(manager.get_cached_count(), 2);
+    assert_eq!(manager.get_memory_usage(), 200);
+    load(&a);
+    assert_eq!(IMPORTS.load(Ordering::Acquire), 3);
+    assert_eq!(manager.get_cached_count(), 1);
+
+    // Exceeding the budget evicts the least recently used cached asset
+    load(&c);
+    common::wait_idle(&manager);
+    assert!(!b.is_loaded());
+    assert!(a.is_loaded() && c.is_loaded());
+    assert_eq!(manager.get_memory_usage(), 200);
+    assert_eq!(manager.get_cached_count(), 0);
+
+    // a lost its last reference before c, so it is evicted first
+    a.unload();
+    c.unload();
+    common::wait_idle(&manager);
+    load(&b);
+    common::wait_idle(&manager);
+    assert!(!a.is_loaded() && c.is_loaded());
+
+    // Referenced assets are never evicted
+    manager.set_memory_budget(1);
+    common::wait_idle(&manager);
+    assert!(b.is_loaded());
+    assert!(!a.is_loaded() && !c.is_loaded());
+    assert_eq!(manager.get_memory_usage(), 100);
+
+    b.unload();
+    common::wait_idle(&manager);
+    assert_eq!(manager.get_memory_usage(), 0);
+    assert_eq!(manager.get_cached_count(), 0);
+    println!("asset budget: ok");
+}
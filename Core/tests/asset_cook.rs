@@ -0,0 +1,191 @@
+//! Cooks an asset directory and loads the cooked assets through the asset manager on the null backend.
+
+use image::ColorType;
+use std::path::Path;
+use std::sync::Arc;
+
+use log::LevelFilter;
+use mvcore::asset::cook::{self, CookManifest, Cooker, MANIFEST_NAME};
+use mvcore::asset::manager::AssetManager;
+use mvcore::asset::token::LoadStatus;
+use mvcore::asset::vfs::AssetSource;
+use mvcore::render::model::Model;
+use mvcore::render::texture::Texture;
+
+mod common;
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [
+        { "name": "root", "children": [1], "translation": [1.0, 2.0, 3.0] },
+        { "name": "triangle", "mesh": 0 }
+    ],
+    "meshes": [{ "name": "triangle", "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+    "materials": [{
+        "pbrMetallicRoughness": { "baseColorFactor": [0.5, 0.25, 1.0, 1.0], "baseColorTexture": { "index": 0 } }
+    }],
+    "textures": [{ "source": 0 }],
+    "images": [{ "uri": "../textures/wall.png" }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+    "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+    "accessors": [{
+        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+const BROKEN_SHADER: &str = "#version 450\nvoid main() { undefined(); }\n";
+
+/// Loads a corrupt cooked model, which has to fail with an error containing `error`.
+fn load_broken_model(
+    manager: &Arc<AssetManager>,
+    output: &Path,
+    name: &str,
+    data: &[u8],
+    error: &str,
+) {
+    let path = format!("models/{name}.mvmodel");
+    std::fs::write(output.join(&path), data).expect("Failed to write model");
+    let handle = manager.create::<Model>(&path);
+    assert_eq!(handle.load().wait(), LoadStatus::Failed, "{name}");
+    let asset = handle.into_untyped().get();
+    let message = asset.error_as::<String>().expect("Wrong error type");
+    assert!(message.contains(error), "{name}: {}", *message);
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("asset_cook");
+    let input = dir.join("input");
+    let output = dir.join("output");
+    std::fs::create_dir_all(input.join("models")).expect("Failed to create test directory");
+    std::fs::create_dir_all(input.join("textures")).expect("Failed to create test directory");
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    std::fs::write(input.join("models/triangle.bin"), bin).expect("Failed to write buffer");
+    std::fs::write(input.join("models/triangle.gltf"), GLTF).expect("Failed to write model");
+    image::RgbImage::from_pixel(4, 2, image::Rgb([0, 255, 0]))
+        .save(input.join("textures/wall.png"))
+        .expect("Failed to write texture");
+    std::fs::write(input.join("readme.txt"), "not an asset").expect("Failed to write file");
+
+    let report = Cooker::new(&input, &output).cook().expect("Failed to cook");
+
+    assert_eq!(report.cooked, 2);
+    assert_eq!(report.copied, 2);
+    assert!(report.failed.is_empty());
+
+    let manifest = std::fs::read_to_string(output.join(MANIFEST_NAME)).unwrap();
+    let manifest = CookManifest::parse(&manifest).expect("Invalid manifest");
+    assert!(manifest.entries.contains(&(
+        "textures/wall.png".to_string(),
+        "textures/wall.png.mvtex".to_string()
+    )));
+    assert!(output.join("readme.txt").is_file());
+    assert!(!output.join("textures/wall.png").exists());
+
+    // Textures are expanded to rgba with the whole mip chain
+    let texture = std::fs::read(output.join("textures/wall.png.mvtex")).unwrap();
+    let texture = cook::read_texture(&texture).expect("Invalid cooked texture");
+    assert_eq!((texture.width, texture.height), (4, 2));
+    assert_eq!(texture.color, ColorType::Rgba8);
+    let sizes = texture.levels.iter().map(Vec::len).collect::<Vec<_>>();
+    assert_eq!(sizes, [4 * 2 * 4, 2 * 4, 4]);
+    assert_eq!(&texture.levels[0][..4], &[0, 255, 0, 255]);
+
+    // Truncated files and lengths larger than the file fail without panicking or allocating them
+    let data = std::fs::read(output.join("textures/wall.png.mvtex")).unwrap();
+    for len in 0..data.len() {
+        assert!(cook::read_texture(&data[..len]).is_err(), "{len}");
+    }
+    let mut huge_level = data.clone();
+    huge_level[21..29].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(cook::read_texture(&huge_level).is_err());
+    let mut huge_count = data.clone();
+    huge_count[17..21].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(cook::read_texture(&huge_count).is_err());
+
+    let manager = common::null_manager("Asset cook test", 2);
+    manager
+        .get_file_system()
+        .mount(AssetSource::Directory(output.clone()), 0);
+    assert_eq!(
+        manager.load_cooked_manifest(MANIFEST_NAME).unwrap(),
+        manifest.entries.len()
+    );
+
+    // The sources aren't in the output, so these only load from the cooked versions
+    let wall = manager.create::<Texture>("textures/wall.png");
+    assert_eq!(wall.load().wait(), LoadStatus::Loaded);
+    assert_eq!(wall.get().unwrap().image().get_extent().width, 4);
+
+    let model = manager.create::<Model>("./models/triangle.gltf");
+    assert_eq!(model.load().wait(), LoadStatus::Loaded);
+    let loaded = model.get().unwrap();
+    let nodes = loaded.get_nodes();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1].name.as_deref(), Some("triangle"));
+    assert_eq!(nodes[1].parent, Some(0));
+    assert_eq!(nodes[0].translation.y, 2.0);
+    assert_eq!(loaded.get_root_nodes(), [0]);
+    assert_eq!(loaded.get_meshes()[0].primitives.len(), 1);
+    let material = &loaded.get_materials()[0];
+    assert_eq!(material.color.y, 0.25);
+    let texture = material
+        .color_tex
+        .as_ref()
+        .expect("Material lost its texture");
+    assert_eq!(texture.get_path(), "models/../textures/wall.png");
+    assert!(texture.is_loaded());
+    drop(loaded);
+
+    // Corrupt models fail to load instead of panicking, indices are checked against their tables
+    let data = std::fs::read(output.join("models/triangle.gltf.mvmodel")).unwrap();
+    for len in (0..data.len()).step_by(16) {
+        let name = format!("truncated{len}");
+        load_broken_model(&manager, &output, &name, &data[..len], "");
+    }
+    let mut huge_count = data.clone();
+    huge_count[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    load_broken_model(&manager, &output, "count", &huge_count, "don't fit");
+    // The color texture index follows the image path, the material count and the color
+    let image_path_len = u32::from_le_bytes(data[12..16].try_into().unwrap()) as usize;
+    let color_tex = 16 + image_path_len + 4 + 16;
+    let mut bad_texture = data.clone();
+    bad_texture[color_tex..color_tex + 4].copy_from_slice(&5u32.to_le_bytes());
+    load_broken_model(
+        &manager,
+        &output,
+        "texture",
+        &bad_texture,
+        "Invalid image index 5",
+    );
+    let mut bad_root = data.clone();
+    let root = data.len() - 4;
+    bad_root[root..].copy_from_slice(&9u32.to_le_bytes());
+    load_broken_model(
+        &manager,
+        &output,
+        "root",
+        &bad_root,
+        "Invalid root node index 9",
+    );
+
+    model.unload();
+    wall.unload();
+
+    // A shader that doesn't compile fails the cook instead of being copied
+    let shaders = dir.join("shaders");
+    std::fs::create_dir_all(&shaders).expect("Failed to create test directory");
+    std::fs::write(shaders.join("broken.frag"), BROKEN_SHADER).expect("Failed to write shader");
+    let error = Cooker::new(&shaders, dir.join("shaders_output"))
+        .cook()
+        .expect_err("Broken shader was cooked");
+    assert!(error.to_string().contains("broken.frag"), "{error}");
+    println!("asset cook: ok");
+}
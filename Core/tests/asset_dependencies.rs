@@ -0,0 +1,190 @@
+//! Loads assets that depend on other assets through the asset manager on the null backend.
+
+use std::path::Path;
+use std::time::Duration;
+
+use log::LevelFilter;
+use mvcore::asset::importer::{AssetImporter, ImportContext};
+use mvcore::asset::manager::{AssetManager, Handle};
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::model::Model;
+use mvsync::block::await_sync;
+
+mod common;
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [{ "mesh": 0 }],
+    "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+    "materials": [{ "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } }],
+    "textures": [{ "source": 0 }],
+    "images": [{ "uri": "texture.png" }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+    "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+    "accessors": [{
+        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+/// Text files, slow to import so dependencies finish after the assets depending on them.
+struct TextImporter;
+
+impl AssetImporter for TextImporter {
+    type Asset = String;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn import(&self, path: &str, _: &mut ImportContext) -> Result<String, String> {
+        std::thread::sleep(Duration::from_millis(50));
+        std::fs::read_to_string(path).map_err(|e| e.to_string())
+    }
+}
+
+/// Lists of text files, one per line, that are loaded as dependencies.
+struct GroupImporter;
+
+impl AssetImporter for GroupImporter {
+    type Asset = Vec<Handle<String>>;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["group"]
+    }
+
+    fn import(
+        &self,
+        path: &str,
+        context: &mut ImportContext,
+    ) -> Result<Vec<Handle<String>>, String> {
+        let dir = Path::new(path).parent().unwrap();
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Ok(source
+            .lines()
+            .map(|line| context.load_dependency::<String>(dir.join(line).to_str().unwrap()))
+            .collect())
+    }
+}
+
+/// A path to another link, which is loaded as a dependency. Links can depend on each other.
+struct LinkImporter;
+
+impl AssetImporter for LinkImporter {
+    type Asset = String;
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["link"]
+    }
+
+    fn import(&self, path: &str, context: &mut ImportContext) -> Result<String, String> {
+        let target = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let target = Path::new(path).parent().unwrap().join(target);
+        context.load_dependency::<String>(target.to_str().unwrap());
+        Ok(target.to_str().unwrap().to_string())
+    }
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("asset_dependencies");
+    let write = |name: &str, content: &str| {
+        std::fs::write(dir.join(name), content).expect("Failed to write asset")
+    };
+    write("x.txt", "x");
+    write("y.txt", "y");
+    write("a.group", "x.txt\ny.txt");
+    write("b.group", "y.txt");
+
+    let device = common::null_device("Asset dependency test");
+    let manager = AssetManager::new(device.clone(), 2);
+    manager.register_importer(TextImporter);
+    manager.register_importer(GroupImporter);
+    manager.register_importer(LinkImporter);
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+    // Groups only finish loading once their dependencies did
+    let a = manager.create::<Vec<Handle<String>>>(&path("a.group"));
+    let b = manager.create::<Vec<Handle<String>>>(&path("b.group"));
+    assert_eq!(await_sync(a.load()), LoadStatus::Loaded);
+    let texts = a.get().expect("Group did not load");
+    assert_eq!(texts.len(), 2);
+    assert!(texts.iter().all(|text| text.is_loaded()));
+    assert_eq!(texts[0].get().as_deref().map(String::as_str), Some("x"));
+    let x = texts[0].clone();
+    let y = texts[1].clone();
+
+    assert_eq!(b.load().wait(), LoadStatus::Loaded);
+    assert_eq!(y.get_asset().get_dependents().len(), 2);
+
+    // Unloading a group releases the dependencies nothing else uses
+    a.unload();
+    common::wait_idle(&manager);
+    assert!(!x.is_loaded());
+    assert!(y.is_loaded());
+
+    // Reloading a dependency reports the assets depending on it as reloaded too
+    let reloads = manager.subscribe_reloads();
+    write("y.txt", "new y");
+    y.reload();
+    common::wait_idle(&manager);
+    let reloaded = reloads.try_iter().collect::<Vec<_>>();
+    assert_eq!(
+        reloaded.len(),
+        2,
+        "Expected y.txt and b.group to be reloaded"
+    );
+    assert!(reloaded.contains(y.untyped()));
+    assert!(reloaded.contains(b.untyped()));
+    assert_eq!(y.get().as_deref().map(String::as_str), Some("new y"));
+
+    b.unload();
+    common::wait_idle(&manager);
+    assert!(!y.is_loaded());
+    assert!(y.get_asset().get_dependents().is_empty());
+
+    // Dependency cycles finish loading instead of checking each other forever
+    write("first.link", "second.link");
+    write("second.link", "first.link");
+    let first = manager.create::<String>(&path("first.link"));
+    assert_eq!(first.load().wait(), LoadStatus::Loaded);
+    let second = manager.get_or_create::<String>(&path("second.link"));
+    assert_eq!(second.load().wait(), LoadStatus::Loaded);
+    assert!(first.get_asset().dependencies_ready());
+    assert!(second.get_asset().get_dependents() == [first.untyped().clone()]);
+    second.unload();
+    first.unload();
+
+    // Textures of models in their own files are dependencies of the model
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    std::fs::write(dir.join("triangle.bin"), bin).expect("Failed to write buffer");
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]))
+        .save(dir.join("texture.png"))
+        .expect("Failed to write texture");
+    write("model.gltf", GLTF);
+
+    let model = manager.create::<Model>(&path("model.gltf"));
+    assert_eq!(model.load().wait(), LoadStatus::Loaded);
+    let textures = model.get().unwrap().get_textures().to_vec();
+    assert_eq!(textures.len(), 1);
+    assert_eq!(textures[0].get_path(), path("texture.png"));
+    assert!(textures[0].is_loaded());
+    let loaded = model.get().unwrap();
+    assert!(loaded.get_materials()[0].color_tex.as_ref() == Some(&textures[0]));
+    drop(loaded);
+
+    model.unload();
+    common::wait_idle(&manager);
+    assert!(!textures[0].is_loaded());
+
+    println!("asset dependencies: ok");
+}
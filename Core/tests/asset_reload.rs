@@ -0,0 +1,117 @@
+//! Reloads and unloads a model through the asset manager on the null backend and checks that the
+//! old version is only destroyed once no frame in flight can use it. Also checks that hot
+//! reloading picks up changed files.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use log::LevelFilter;
+use mvcore::asset::asset::AssetType;
+
+mod common;
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [{ "name": "NAME", "mesh": 0 }],
+    "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+    "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+    "accessors": [{
+        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("asset_reload");
+
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    std::fs::write(dir.join("triangle.bin"), bin).expect("Failed to write buffer");
+    let path = dir.join("triangle.gltf");
+    let write_model = |name: &str| {
+        std::fs::write(&path, GLTF.replace("NAME", name)).expect("Failed to write model")
+    };
+    write_model("first");
+
+    let manager = common::null_manager("Asset reload test", 1);
+    manager.set_max_frames_in_flight(2);
+
+    let handle = manager.create_asset(path.to_str().unwrap(), AssetType::Model);
+    let node_name = || {
+        handle
+            .get()
+            .as_model()
+            .expect("Asset is not a loaded model")
+            .get_nodes()[0]
+            .name
+            .clone()
+    };
+
+    handle.load();
+    common::wait_idle(&manager);
+    assert_eq!(node_name().as_deref(), Some("first"));
+
+    write_model("second");
+    handle.reload();
+    common::wait_idle(&manager);
+    assert_eq!(node_name().as_deref(), Some("second"));
+    assert_eq!(manager.get_retired(), 1);
+
+    manager.end_frame();
+    assert_eq!(manager.get_retired(), 1, "Destroyed while still in flight");
+    manager.end_frame();
+    assert_eq!(manager.get_retired(), 0);
+
+    // A failed reload keeps the current version
+    std::fs::write(&path, "not a model").expect("Failed to write model");
+    handle.reload();
+    common::wait_idle(&manager);
+    assert_eq!(node_name().as_deref(), Some("second"));
+    assert_eq!(manager.get_retired(), 0);
+
+    let reloads = manager.subscribe_reloads();
+    let callbacks = Arc::new(AtomicU32::new(0));
+    let counter = callbacks.clone();
+    manager.on_reload(move |_| {
+        counter.fetch_add(1, Ordering::AcqRel);
+    });
+    manager.enable_hot_reload(Duration::from_millis(10));
+    // Let the watcher see the current state of the file first
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(reloads.try_recv().is_err(), "Reloaded without a change");
+
+    write_model("third");
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(10)))
+        .expect("Failed to touch model");
+    let reloaded = reloads
+        .recv_timeout(Duration::from_secs(10))
+        .expect("Changed model was not reloaded");
+    assert!(reloaded == handle);
+    common::wait_idle(&manager);
+    assert_eq!(node_name().as_deref(), Some("third"));
+    assert_eq!(callbacks.load(Ordering::Acquire), 1);
+    manager.disable_hot_reload();
+    manager.end_frame();
+    manager.end_frame();
+
+    handle.unload();
+    common::wait_idle(&manager);
+    assert!(!handle.is_loaded());
+    assert_eq!(manager.get_retired(), 1);
+    manager.end_frame();
+    manager.end_frame();
+    assert_eq!(manager.get_retired(), 0);
+
+    println!("asset reload: ok");
+}
@@ -0,0 +1,107 @@
+//! Loads, unloads, reloads and reads the same assets from many threads at once on the null backend.
+//! Nothing in the asset storage casts shared references to mutable ones anymore, so this can also
+//! be run under miri:
+//! `MIRIFLAGS=-Zmiri-disable-isolation cargo +nightly miri test -p mvcore --test asset_stress`.
+
+use log::LevelFilter;
+use mvcore::asset::asset::AssetType;
+use mvcore::asset::manager::AssetHandle;
+
+mod common;
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [{ "name": "triangle", "mesh": 0 }],
+    "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+    "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+    "accessors": [{
+        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+const THREADS: u64 = 8;
+const ITERATIONS: u64 = if cfg!(miri) { 20 } else { 500 };
+
+fn stress(handle: AssetHandle, seed: u64) {
+    let mut state = seed;
+    let mut loads = 0;
+    for _ in 0..ITERATIONS {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        match (state >> 33) % 5 {
+            0 => {
+                handle.load();
+                loads += 1;
+            }
+            1 if loads > 0 => {
+                handle.unload();
+                loads -= 1;
+            }
+            2 => handle.reload(),
+            3 => handle.get_manager().end_frame(),
+            _ => {
+                let asset = handle.get();
+                if let Some(model) = asset.as_model() {
+                    assert_eq!(model.get_nodes().len(), 1);
+                    assert_eq!(model.get_nodes()[0].name.as_deref(), Some("triangle"));
+                } else {
+                    assert!(!asset.failed(), "Asset failed while stressing");
+                };
+            }
+        }
+    }
+    for _ in 0..loads {
+        handle.unload();
+    }
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("asset_stress");
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    std::fs::write(dir.join("triangle.bin"), bin).expect("Failed to write buffer");
+    std::fs::write(dir.join("triangle.gltf"), GLTF).expect("Failed to write model");
+
+    let manager = common::null_manager("Asset stress test", 4);
+    let path = dir.join("triangle.gltf");
+    let handle = manager.create_asset(path.to_str().unwrap(), AssetType::Model);
+
+    let threads = (0..THREADS)
+        .map(|seed| {
+            let handle = handle.clone();
+            std::thread::spawn(move || stress(handle, seed))
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().expect("Stress thread panicked");
+    }
+
+    common::wait_idle(&manager);
+    assert!(
+        !handle.is_loaded(),
+        "Asset stayed loaded without references"
+    );
+
+    handle.load();
+    common::wait_idle(&manager);
+    assert!(handle.is_loaded(), "Asset did not load after stressing");
+    handle.unload();
+    common::wait_idle(&manager);
+    assert!(!handle.is_loaded());
+
+    for _ in 0..3 {
+        manager.end_frame();
+    }
+    assert_eq!(manager.get_retired(), 0);
+
+    println!("asset stress: ok");
+}
@@ -0,0 +1,72 @@
+//! Imports textures as sRGB color or linear data and checks the sRGB format variants, on the null
+//! backend.
+
+use log::LevelFilter;
+use mvcore::asset::cook;
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::backend::image::{ColorSpace, ImageFormat};
+use mvcore::render::texture::Texture;
+
+mod common;
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    assert_eq!(
+        ImageFormat::R8G8B8A8.with_color_space(ColorSpace::Srgb),
+        ImageFormat::R8G8B8A8Srgb
+    );
+    assert_eq!(
+        ImageFormat::Bc7Srgb.with_color_space(ColorSpace::Linear),
+        ImageFormat::Bc7
+    );
+    assert!(ImageFormat::Astc8x8Srgb.is_srgb() && !ImageFormat::Astc8x8.is_srgb());
+    // Formats without an sRGB variant stay as they are
+    assert_eq!(
+        ImageFormat::R16G16B16A16.with_color_space(ColorSpace::Srgb),
+        ImageFormat::R16G16B16A16
+    );
+    assert_eq!(
+        ImageFormat::B8G8R8A8Srgb.get_pixel_size(),
+        ImageFormat::B8G8R8A8.get_pixel_size()
+    );
+
+    let dir = common::test_dir("color_space");
+    let png = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 128, 0, 255]));
+    png.save(dir.join("color.png"))
+        .expect("Failed to write texture");
+    png.save(dir.join("normal.png"))
+        .expect("Failed to write texture");
+    image::ImageBuffer::<image::Rgba<u16>, _>::from_pixel(4, 4, image::Rgba([1000; 4]))
+        .save(dir.join("height.png"))
+        .expect("Failed to write texture");
+    std::fs::write(dir.join("normal.mvtex"), cook::cook_image(png.into()))
+        .expect("Failed to write cooked texture");
+
+    let manager = common::null_manager("Color space test", 1);
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    manager.set_color_space(&path("normal.png"), ColorSpace::Linear);
+    manager.set_color_space(&path("normal.mvtex"), ColorSpace::Linear);
+    assert_eq!(
+        manager.get_color_space(&path("color.png")),
+        ColorSpace::Srgb
+    );
+
+    for (name, format) in [
+        ("color.png", ImageFormat::R8G8B8A8Srgb),
+        ("normal.png", ImageFormat::R8G8B8A8),
+        ("height.png", ImageFormat::R16G16B16A16),
+        ("normal.mvtex", ImageFormat::R8G8B8A8),
+    ] {
+        let texture = manager.create::<Texture>(&path(name));
+        assert_eq!(texture.load().wait(), LoadStatus::Loaded, "{name}");
+        let image = texture.get().unwrap().image();
+        assert_eq!(image.get_format(), format, "{name}");
+        // The stored bytes are the same, only how they are sampled changes
+        if format.get_pixel_size() == 4 {
+            assert_eq!(&image.read_pixels().data[..4], &[255, 128, 0, 255]);
+        }
+        texture.unload();
+    }
+    println!("color space: ok");
+}
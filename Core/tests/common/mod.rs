@@ -0,0 +1,53 @@
+//! Fixtures shared by the tests that run on the null backend. Every test binary includes this
+//! module and only uses part of it.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mvcore::asset::manager::AssetManager;
+use mvcore::render::backend::device::{Device, Extensions, MVDeviceCreateInfo};
+use mvcore::render::backend::Backend;
+use mvutils::version::Version;
+
+/// Upper bound for anything a test waits on, so a hang fails the test instead of blocking it.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn null_device(name: &str) -> Device {
+    Device::new_headless(
+        Backend::Null,
+        MVDeviceCreateInfo {
+            app_name: name.to_string(),
+            app_version: Version::new(0, 0, 1, 0),
+            engine_name: "MVEngine".to_string(),
+            engine_version: Version::new(0, 0, 1, 0),
+            device_extensions: Extensions::empty(),
+        },
+    )
+}
+
+pub fn null_manager(name: &str, thread_count: u64) -> Arc<AssetManager> {
+    AssetManager::new(null_device(name), thread_count)
+}
+
+/// An empty directory for the files of a test, left over files of earlier runs are removed.
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create test directory");
+    dir
+}
+
+pub fn wait_until(what: &str, mut done: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        assert!(start.elapsed() < TIMEOUT, "{what} took too long");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Waits until the asset manager has no queued or running tasks left.
+pub fn wait_idle(manager: &AssetManager) {
+    wait_until("Asset tasks", || manager.get_queued() == 0);
+}
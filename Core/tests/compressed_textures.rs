@@ -0,0 +1,279 @@
+//! Reads handwritten KTX2 and DDS files with compressed formats, mip levels and array layers and
+//! imports them as textures, on the null backend.
+
+use log::LevelFilter;
+use mvcore::asset::container;
+use mvcore::asset::manager::AssetManager;
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::backend::image::{ImageFormat, ImageLimits, ImageUsage};
+use mvcore::render::backend::Extent2D;
+use mvcore::render::texture::Texture;
+
+mod common;
+
+const KTX2_IDENTIFIER: &[u8; 12] = b"\xABKTX 20\xBB\r\n\x1A\n";
+
+fn push_u32(data: &mut Vec<u8>, values: &[u32]) {
+    for value in values {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// A KTX2 file, `levels` start with the full size one and hold all layers and faces.
+fn ktx2(
+    format: ash::vk::Format,
+    width: u32,
+    height: u32,
+    layers: u32,
+    faces: u32,
+    levels: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut data = KTX2_IDENTIFIER.to_vec();
+    push_u32(
+        &mut data,
+        &[
+            format.as_raw() as u32,
+            1,
+            width,
+            height,
+            0,
+            layers,
+            faces,
+            levels.len() as u32,
+            0,
+        ],
+    );
+    // No data format descriptor, key/value or supercompression data
+    push_u32(&mut data, &[0; 8]);
+    let mut offset = data.len() + levels.len().max(1) * 24;
+    for level in levels {
+        for value in [offset, level.len(), level.len()] {
+            data.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        offset += level.len();
+    }
+    if levels.is_empty() {
+        data.extend_from_slice(&[0; 24]);
+    }
+    for level in levels {
+        data.extend_from_slice(level);
+    }
+    data
+}
+
+/// A DDS file with the DX10 header, `layers` each hold their whole mip chain.
+fn dds_dx10(
+    dxgi_format: u32,
+    width: u32,
+    height: u32,
+    level_count: u32,
+    layers: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut data = b"DDS ".to_vec();
+    // Size, flags with the mip map count, height, width, pitch, depth and mip map count
+    push_u32(
+        &mut data,
+        &[124, 0x1007 | 0x20000, height, width, 0, 0, level_count],
+    );
+    push_u32(&mut data, &[0; 11]);
+    // Pixel format with the DX10 FourCC
+    push_u32(&mut data, &[32, 0x4]);
+    data.extend_from_slice(b"DX10");
+    push_u32(&mut data, &[0; 5]);
+    // Caps
+    push_u32(&mut data, &[0x1000, 0, 0, 0, 0]);
+    // DXGI format, 2d texture, no misc flags and the array size
+    push_u32(&mut data, &[dxgi_format, 3, 0, layers.len() as u32, 0]);
+    for layer in layers {
+        data.extend_from_slice(layer);
+    }
+    data
+}
+
+/// A DDS cubemap with the DXT5 FourCC and a single mip level.
+fn dds_cubemap(width: u32, faces: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = b"DDS ".to_vec();
+    push_u32(&mut data, &[124, 0x1007, width, width, 0, 0, 0]);
+    push_u32(&mut data, &[0; 11]);
+    push_u32(&mut data, &[32, 0x4]);
+    data.extend_from_slice(b"DXT5");
+    push_u32(&mut data, &[0; 5]);
+    push_u32(&mut data, &[0x1008, 0x200 | 0xFC00, 0, 0, 0]);
+    for face in faces {
+        data.extend_from_slice(face);
+    }
+    data
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    // Partial blocks at the edges take up a whole block
+    let extent = |width, height| Extent2D { width, height };
+    assert!(ImageFormat::Bc1.is_compressed() && !ImageFormat::R8G8B8A8.is_compressed());
+    assert_eq!(ImageFormat::Bc1.get_data_size(extent(8, 8)), 4 * 8);
+    assert_eq!(ImageFormat::Bc7.get_data_size(extent(1, 1)), 16);
+    assert_eq!(ImageFormat::Astc6x6.get_data_size(extent(13, 6)), 3 * 16);
+    assert_eq!(ImageFormat::Astc12x10.get_block_extent(), (12, 10));
+    assert_eq!(ImageFormat::R16G16B16A16.get_data_size(extent(3, 2)), 6 * 8);
+    assert_eq!(
+        ImageFormat::R32G32B32A32.checked_data_size(extent(u32::MAX, u32::MAX)),
+        None
+    );
+
+    let limits = ImageLimits::default();
+
+    // DDS stores every layer with its mip chain, levels are read with all their layers
+    let layer = |value: u8| {
+        [
+            vec![value; 32],
+            vec![value + 1; 8],
+            vec![value + 2; 8],
+            vec![value + 3; 8],
+        ]
+        .concat()
+    };
+    let dds = dds_dx10(71, 8, 8, 4, &[layer(0x10), layer(0x20)]);
+    let texture = container::read_texture(&dds, limits).expect("Failed to read DDS file");
+    assert_eq!(texture.format, ImageFormat::Bc1);
+    assert_eq!(texture.layer_count, 2);
+    assert!(!texture.cubemap);
+    assert_eq!(texture.levels.len(), 4);
+    assert_eq!(texture.levels[0], [vec![0x10; 32], vec![0x20; 32]].concat());
+    assert_eq!(texture.levels[3], [vec![0x13; 8], vec![0x23; 8]].concat());
+
+    let faces = (0..6).map(|face| vec![face; 16]).collect::<Vec<_>>();
+    let texture =
+        container::read_dds(&dds_cubemap(4, &faces), limits).expect("Failed to read DDS cubemap");
+    assert_eq!(texture.format, ImageFormat::Bc3);
+    assert!(texture.cubemap);
+    assert_eq!(texture.layer_count, 6);
+    assert_eq!(texture.levels, [faces.concat()]);
+
+    // KTX2 levels are stored with their layers already
+    let astc = ktx2(
+        ash::vk::Format::ASTC_6X6_SRGB_BLOCK,
+        12,
+        12,
+        0,
+        6,
+        &[vec![1; 4 * 16 * 6], vec![2; 16 * 6]],
+    );
+    let texture = container::read_ktx2(&astc, limits).expect("Failed to read KTX2 file");
+    assert_eq!(texture.format, ImageFormat::Astc6x6Srgb);
+    assert!(texture.cubemap);
+    assert_eq!(texture.layer_count, 6);
+    assert_eq!(texture.levels.len(), 2);
+    assert!(!texture.generate_mipmaps);
+
+    // Broken files are rejected instead of read past their end
+    assert!(container::read_texture(&dds[..dds.len() - 1], limits).is_err());
+    assert!(container::read_texture(b"PNG", limits).is_err());
+    let wrong_size = ktx2(ash::vk::Format::BC7_UNORM_BLOCK, 8, 8, 0, 1, &[vec![0; 16]]);
+    assert!(container::read_ktx2(&wrong_size, limits).is_err());
+    let unsupported = ktx2(
+        ash::vk::Format::ETC2_R8G8B8_UNORM_BLOCK,
+        4,
+        4,
+        0,
+        1,
+        &[vec![0; 8]],
+    );
+    assert!(container::read_ktx2(&unsupported, limits).is_err());
+
+    // Sizes from the header are checked against the device limits before anything is computed
+    let huge = ktx2(
+        ash::vk::Format::R32G32B32A32_SFLOAT,
+        u32::MAX,
+        u32::MAX,
+        0,
+        1,
+        &[vec![0; 16]],
+    );
+    assert!(container::read_ktx2(&huge, limits).is_err());
+    let layers = ktx2(
+        ash::vk::Format::BC7_UNORM_BLOCK,
+        4,
+        4,
+        u32::MAX,
+        6,
+        &[vec![0; 16]],
+    );
+    assert!(container::read_ktx2(&layers, limits).is_err());
+    let too_many = ktx2(
+        ash::vk::Format::BC7_UNORM_BLOCK,
+        4,
+        4,
+        257,
+        1,
+        &[vec![0; 16 * 257]],
+    );
+    assert!(container::read_ktx2(&too_many, limits).is_err());
+    let unlimited = ImageLimits {
+        max_array_layers: 512,
+        ..limits
+    };
+    assert_eq!(
+        container::read_ktx2(&too_many, unlimited)
+            .unwrap()
+            .layer_count,
+        257
+    );
+
+    let dir = common::test_dir("compressed_textures");
+    std::fs::write(dir.join("array.dds"), &dds).expect("Failed to write DDS file");
+    std::fs::write(dir.join("sky.ktx2"), &astc).expect("Failed to write KTX2 file");
+    // A level count of 0 asks for the mip chain to be generated, only uncompressed formats can be
+    let mut rgba = ktx2(
+        ash::vk::Format::R8G8B8A8_UNORM,
+        4,
+        4,
+        0,
+        1,
+        &[vec![255; 4 * 4 * 4]],
+    );
+    rgba[40..44].copy_from_slice(&0u32.to_le_bytes());
+    std::fs::write(dir.join("generated.ktx2"), rgba).expect("Failed to write KTX2 file");
+    std::fs::write(dir.join("broken.dds"), &dds[..100]).expect("Failed to write DDS file");
+
+    let device = common::null_device("Compressed texture test");
+    assert!(device.supports_format(ImageFormat::Bc7, &ImageUsage::SAMPLED, false));
+    assert_eq!(device.get_image_limits(), ImageLimits::default());
+    let manager = AssetManager::new(device, 1);
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+    let array = manager.create::<Texture>(&path("array.dds"));
+    assert_eq!(array.load().wait(), LoadStatus::Loaded);
+    let image = array.get().unwrap().image();
+    assert_eq!(image.get_format(), ImageFormat::Bc1);
+    assert_eq!(image.get_mip_level_count(), 4);
+    assert_eq!(image.get_layer_count(), 2);
+    assert_eq!(
+        image.read_pixels().data,
+        [vec![0x10; 32], vec![0x20; 32]].concat()
+    );
+    assert_eq!(array.get_asset().get_size(), (32 + 8 + 8 + 8) * 2);
+
+    let sky = manager.create::<Texture>(&path("sky.ktx2"));
+    assert_eq!(sky.load().wait(), LoadStatus::Loaded);
+    let image = sky.get().unwrap().image();
+    assert_eq!(image.get_format(), ImageFormat::Astc6x6Srgb);
+    assert_eq!(image.get_layer_count(), 6);
+    assert_eq!(sky.get_asset().get_size(), (4 * 16 + 16) * 6);
+
+    let generated = manager.create::<Texture>(&path("generated.ktx2"));
+    assert_eq!(generated.load().wait(), LoadStatus::Loaded);
+    assert_eq!(generated.get().unwrap().image().get_mip_level_count(), 3);
+
+    let broken = manager.create::<Texture>(&path("broken.dds"));
+    assert_eq!(broken.load().wait(), LoadStatus::Failed);
+    assert!(broken
+        .get_asset()
+        .error_as::<String>()
+        .is_some_and(|e| e.contains("broken.dds")));
+
+    for texture in [&array, &sky, &generated, &broken] {
+        texture.unload();
+    }
+    println!("compressed textures: ok");
+}
@@ -0,0 +1,93 @@
+//! Uploads textures from several loader threads at once while the main thread reads textures back,
+//! on a Vulkan device. Needs a Vulkan driver, a software one like lavapipe works.
+
+use image::{Rgba, RgbaImage};
+use log::LevelFilter;
+use mvcore::asset::manager::{AssetManager, Handle};
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::backend::device::{Device, Extensions, MVDeviceCreateInfo};
+use mvcore::render::backend::Backend;
+use mvcore::render::texture::Texture;
+use mvutils::version::Version;
+
+mod common;
+
+const TEXTURES: usize = 64;
+const SIZE: u32 = 64;
+const THREADS: u64 = 8;
+
+fn color(index: usize) -> [u8; 4] {
+    [index as u8 * 4, 255 - index as u8, index as u8 / 2, 255]
+}
+
+/// Every texture has to hold its own pixels, uploads of different threads mustn't mix.
+fn check(index: usize, texture: &Handle<Texture>) {
+    let pixels = texture.get().unwrap().image().read_pixels();
+    let level = &pixels.data[..(SIZE * SIZE * 4) as usize];
+    assert!(
+        level.chunks(4).all(|pixel| pixel == color(index)),
+        "Texture {index} has the wrong data"
+    );
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let device = Device::new_headless(
+        Backend::Vulkan,
+        MVDeviceCreateInfo {
+            app_name: "Concurrent upload test".to_string(),
+            app_version: Version::new(0, 0, 1, 0),
+            engine_name: "MVEngine".to_string(),
+            engine_version: Version::new(0, 0, 1, 0),
+            device_extensions: Extensions::empty(),
+        },
+    );
+
+    let dir = common::test_dir("concurrent_upload");
+    let textures = (0..TEXTURES)
+        .map(|index| {
+            let path = dir.join(format!("{index}.png"));
+            RgbaImage::from_pixel(SIZE, SIZE, Rgba(color(index)))
+                .save(&path)
+                .expect("Failed to write texture");
+            path.to_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+
+    let manager = AssetManager::new(device.clone(), THREADS);
+    let textures = textures
+        .iter()
+        .map(|path| manager.create::<Texture>(path))
+        .collect::<Vec<_>>();
+    let (first, second) = textures.split_at(TEXTURES / 2);
+    let tokens = first
+        .iter()
+        .map(|texture| texture.load())
+        .collect::<Vec<_>>();
+    for token in tokens {
+        assert_eq!(token.wait(), LoadStatus::Loaded);
+    }
+
+    // The loader threads upload and generate mipmaps while the main thread submits readbacks
+    let tokens = second
+        .iter()
+        .map(|texture| texture.load())
+        .collect::<Vec<_>>();
+    for (index, texture) in first.iter().enumerate() {
+        check(index, texture);
+    }
+    for token in tokens {
+        assert_eq!(token.wait(), LoadStatus::Loaded);
+    }
+    for (index, texture) in second.iter().enumerate() {
+        check(first.len() + index, texture);
+    }
+
+    for texture in &textures {
+        texture.unload();
+    }
+    common::wait_idle(&manager);
+    device.wait_idle();
+    println!("concurrent upload: ok");
+}
@@ -0,0 +1,87 @@
+//! Registers a custom importer with the asset manager and loads assets through typed handles.
+
+use log::LevelFilter;
+use mvcore::asset::asset::AssetType;
+use mvcore::asset::importer::{AssetImporter, ImportContext};
+use mvcore::render::model::Model;
+use mvcore::render::texture::Texture;
+
+mod common;
+
+struct Level {
+    rows: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum LevelError {
+    Io,
+    Empty,
+}
+
+struct LevelImporter;
+
+impl AssetImporter for LevelImporter {
+    type Asset = Level;
+    type Error = LevelError;
+
+    fn extensions(&self) -> &[&str] {
+        &["level"]
+    }
+
+    fn import(&self, path: &str, _: &mut ImportContext) -> Result<Level, LevelError> {
+        let source = std::fs::read_to_string(path).map_err(|_| LevelError::Io)?;
+        let rows = source.lines().map(str::to_string).collect::<Vec<_>>();
+        if rows.is_empty() {
+            return Err(LevelError::Empty);
+        }
+        Ok(Level { rows })
+    }
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("custom_importer");
+    std::fs::write(dir.join("first.LEVEL"), "#..#\n#..#\n####").expect("Failed to write level");
+    std::fs::write(dir.join("empty.level"), "").expect("Failed to write level");
+
+    let manager = common::null_manager("Custom importer test", 1);
+    manager.register_importer(LevelImporter);
+
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let level = manager.create::<Level>(&path("first.LEVEL"));
+    let empty = manager.create::<Level>(&path("empty.level"));
+    assert!(level.get().is_none());
+    level.load();
+    empty.load();
+    common::wait_idle(&manager);
+
+    let loaded = level.get().expect("Level did not load");
+    assert_eq!(loaded.rows, vec!["#..#", "#..#", "####"]);
+
+    assert!(empty.get().is_none());
+    let asset = empty.get_asset();
+    assert_eq!(
+        asset.error_as::<LevelError>().as_deref(),
+        Some(&LevelError::Empty)
+    );
+
+    // Typed handles can be recovered from untyped ones, but only as the imported type
+    let untyped = level.clone().into_untyped();
+    assert!(untyped.clone().typed::<Model>().is_none());
+    assert!(untyped.typed::<Level>() == Some(level.clone()));
+
+    // The built in kinds are picked by extension as well
+    let texture = manager.create::<Texture>(&path("missing.png"));
+    assert!(texture.untyped().clone().typed::<Texture>().is_some());
+    let model = manager.create_asset(&path("missing.gltf"), AssetType::Model);
+    assert!(model.typed::<Model>().is_some());
+
+    // Unloading keeps previously returned assets valid
+    level.unload();
+    common::wait_idle(&manager);
+    assert!(level.get().is_none());
+    assert_eq!(loaded.rows.len(), 3);
+
+    println!("custom importer: ok");
+}
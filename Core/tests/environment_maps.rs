@@ -0,0 +1,124 @@
+//! Imports cubemaps from six face images and checks the faces end up as linear half floats, on the
+//! null backend. Baking equirectangular maps needs a shader compiler and isn't covered here.
+
+use log::LevelFilter;
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::backend::image::{ColorSpace, ImagePixels};
+use mvcore::render::backend::Extent2D;
+use mvcore::render::environment::{EnvironmentOptions, ENVIRONMENT_FORMAT};
+use mvcore::render::texture::Texture;
+
+mod common;
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-3 + 1e-3,
+            "{actual} != {expected}"
+        );
+    }
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    let options = EnvironmentOptions::default();
+    assert_eq!(options.face_size, None);
+    assert!(!options.prefilter_specular);
+    assert_eq!(options.irradiance_size, None);
+
+    let dir = common::test_dir("environment_maps");
+    let png = |name: &str, value: u8| {
+        image::RgbaImage::from_pixel(4, 4, image::Rgba([value, 128, 0, 255]))
+            .save(dir.join(name))
+            .expect("Failed to write face");
+    };
+    png("px.png", 255);
+    png("nx.png", 0);
+    png("py.png", 64);
+    png("ny.png", 128);
+    png("pz.png", 192);
+    // Values above one survive, values too large for half floats are clamped. The shared exponent
+    // of the file rounds them to 69632, 65536 and 39936 first
+    image::Rgb32FImage::from_pixel(4, 4, image::Rgb([70000.0, 65536.0, 40000.0]))
+        .save(dir.join("nz.hdr"))
+        .expect("Failed to write face");
+    std::fs::write(
+        dir.join("sky.cubemap"),
+        "# +x, -x, +y, -y, +z, -z\npx.png\nnx.png\n\npy.png\nny.png\npz.png\nnz.hdr\n",
+    )
+    .expect("Failed to write cubemap");
+    std::fs::write(dir.join("short.cubemap"), "px.png\nnx.png\n").expect("Failed to write cubemap");
+    image::RgbaImage::new(4, 2)
+        .save(dir.join("wide.png"))
+        .expect("Failed to write face");
+    std::fs::write(
+        dir.join("wide.cubemap"),
+        "px.png\nnx.png\npy.png\nny.png\npz.png\nwide.png\n",
+    )
+    .expect("Failed to write cubemap");
+
+    let manager = common::null_manager("Environment map test", 1);
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    manager.set_color_space(&path("ny.png"), ColorSpace::Linear);
+    assert_eq!(
+        manager.get_environment_options(&path("sky.cubemap")),
+        EnvironmentOptions::default()
+    );
+
+    let sky = manager.create::<Texture>(&path("sky.cubemap"));
+    assert_eq!(sky.load().wait(), LoadStatus::Loaded);
+    let image = sky.get().unwrap().image();
+    assert_eq!(image.get_format(), ENVIRONMENT_FORMAT);
+    assert_eq!(image.get_layer_count(), 6);
+    assert_eq!(image.get_mip_level_count(), 3);
+    assert_eq!(sky.get_asset().get_size(), (16 + 4 + 1) * 8 * 6);
+
+    // The first mip level holds the faces one after another
+    let pixels = image.read_pixels();
+    let faces = pixels
+        .data
+        .chunks_exact(4 * 4 * 8)
+        .map(|face| {
+            let face = ImagePixels {
+                data: face.to_vec(),
+                format: pixels.format,
+                extent: Extent2D {
+                    width: 4,
+                    height: 4,
+                },
+            };
+            face.to_dynamic_image()
+                .unwrap()
+                .to_rgba32f()
+                .get_pixel(1, 2)
+                .0
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(faces.len(), 6);
+    // sRGB faces are converted to linear, 128 is about 0.2158
+    assert_close(&faces[0], &[1.0, 0.2158, 0.0, 1.0]);
+    assert_close(&faces[1], &[0.0, 0.2158, 0.0, 1.0]);
+    assert_close(&faces[2], &[0.0513, 0.2158, 0.0, 1.0]);
+    assert_close(&faces[3], &[128.0 / 255.0, 128.0 / 255.0, 0.0, 1.0]);
+    assert_close(&faces[5], &[65504.0, 65504.0, 39936.0, 1.0]);
+
+    let short = manager.create::<Texture>(&path("short.cubemap"));
+    assert_eq!(short.load().wait(), LoadStatus::Failed);
+    assert!(short
+        .get_asset()
+        .error_as::<String>()
+        .is_some_and(|e| e.contains("lists 2 faces")));
+
+    let wide = manager.create::<Texture>(&path("wide.cubemap"));
+    assert_eq!(wide.load().wait(), LoadStatus::Failed);
+    assert!(wide
+        .get_asset()
+        .error_as::<String>()
+        .is_some_and(|e| e.contains("square")));
+
+    for texture in [&sky, &short, &wide] {
+        texture.unload();
+    }
+    println!("environment maps: ok");
+}
@@ -0,0 +1,151 @@
+//! Imports a small generated glTF file through the asset manager on the null backend.
+
+use log::LevelFilter;
+use mvcore::asset::asset::AssetType;
+use mvcore::asset::importer::{AssetImporter, ImportContext};
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::backend::pipeline::Topology;
+
+mod common;
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [
+        { "name": "root", "translation": [1.0, 2.0, 3.0], "children": [1] },
+        { "name": "triangle", "scale": [2.0, 2.0, 2.0], "mesh": 0 }
+    ],
+    "meshes": [{
+        "name": "triangle",
+        "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+    }],
+    "materials": [{
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [1.0, 0.5, 0.25, 1.0],
+            "metallicFactor": 0.75,
+            "roughnessFactor": 0.25
+        }
+    }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 44 }],
+    "bufferViews": [
+        { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+        { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+    ],
+    "accessors": [
+        {
+            "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+            "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+        },
+        { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+    ]
+}"#;
+
+struct PanicImporter;
+
+impl AssetImporter for PanicImporter {
+    type Asset = ();
+    type Error = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["panic"]
+    }
+
+    fn import(&self, _: &str, _: &mut ImportContext) -> Result<(), String> {
+        panic!("Importer panicked on purpose");
+    }
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("gltf_import");
+
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    let mut broken = bin.clone();
+    for index in [0u16, 1, 2, 0] {
+        bin.extend_from_slice(&index.to_le_bytes());
+    }
+    for index in [0u16, 1, 3, 0] {
+        broken.extend_from_slice(&index.to_le_bytes());
+    }
+    std::fs::write(dir.join("triangle.bin"), bin).expect("Failed to write buffer");
+    std::fs::write(dir.join("broken.bin"), broken).expect("Failed to write buffer");
+    std::fs::write(dir.join("triangle.gltf"), GLTF).expect("Failed to write model");
+    std::fs::write(
+        dir.join("broken.gltf"),
+        GLTF.replace("triangle.bin", "broken.bin"),
+    )
+    .expect("Failed to write model");
+
+    let manager = common::null_manager("glTF import test", 1);
+
+    let path = dir.join("triangle.gltf");
+    let handle = manager.create_asset(path.to_str().unwrap(), AssetType::Model);
+    handle.load();
+
+    common::wait_idle(&manager);
+
+    let asset = handle.get();
+    if let Some(error) = asset.error() {
+        panic!("Model failed to load: {:?}", error.downcast_ref::<String>());
+    }
+    let model = asset.as_model().expect("Asset is not a model");
+
+    let nodes = model.get_nodes();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(model.get_root_nodes(), &[0]);
+    assert_eq!(nodes[0].name.as_deref(), Some("root"));
+    assert_eq!(nodes[0].children, vec![1]);
+    assert_eq!(nodes[1].parent, Some(0));
+    assert_eq!(nodes[1].mesh, Some(0));
+    assert_eq!(
+        (
+            nodes[0].translation.x,
+            nodes[0].translation.y,
+            nodes[0].translation.z
+        ),
+        (1.0, 2.0, 3.0)
+    );
+    assert_eq!(nodes[1].scale.x, 2.0);
+
+    let meshes = model.get_meshes();
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].name.as_deref(), Some("triangle"));
+    assert_eq!(meshes[0].primitives.len(), 1);
+    assert!(meshes[0].primitives[0].topology == Topology::Triangle);
+    assert_eq!(meshes[0].primitives[0].material, Some(0));
+
+    let material = &model.get_materials()[0];
+    assert_eq!(
+        (material.color.x, material.color.y, material.color.z),
+        (1.0, 0.5, 0.25)
+    );
+    assert_eq!(material.metallic, 0.75);
+    assert_eq!(material.roughness, 0.25);
+    assert!((material.reflectance - 0.5).abs() < 1e-5);
+    assert!(material.color_tex.is_none());
+
+    // Indices out of range fail the import instead of panicking the loader thread
+    let path = dir.join("broken.gltf");
+    let broken = manager.create_asset(path.to_str().unwrap(), AssetType::Model);
+    assert_eq!(broken.load().wait(), LoadStatus::Failed);
+    let asset = broken.get();
+    let error = asset.error_as::<String>().expect("Wrong error type");
+    assert!(error.contains("Index 3 is out of range"), "{}", *error);
+
+    // A panicking importer still finishes its task, and the loader thread is replaced
+    manager.register_importer(PanicImporter);
+    let panics = manager.create::<()>("bad.panic");
+    panics.load();
+    common::wait_idle(&manager);
+    assert!(!panics.is_loaded());
+    handle.reload();
+    common::wait_idle(&manager);
+    assert!(handle.get().as_model().is_some());
+
+    println!("gltf import: ok");
+}
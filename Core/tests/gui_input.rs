@@ -0,0 +1,136 @@
+//! Hit-tests the cursor against gui widgets, tracks hover, focus and capture and checks which
+//! events the gui processor consumes.
+
+use std::sync::{Arc, RwLock};
+
+use mvcore::input::gui::{GuiEvent, GuiInputProcessor, WidgetRect};
+use mvcore::input::raw::Input;
+use mvcore::input::*;
+
+const PANEL: u64 = 1;
+const BUTTON: u64 = 2;
+const TEXT_FIELD: u64 = 3;
+
+fn main() {
+    let mut gui = GuiInputProcessor::new(Arc::new(RwLock::new(Input::new())));
+    gui.set_widget(PANEL, WidgetRect::new(0, 0, 200, 100), 0, false);
+    gui.set_widget(BUTTON, WidgetRect::new(10, 10, 50, 20), 1, false);
+    gui.set_widget(TEXT_FIELD, WidgetRect::new(10, 50, 100, 20), 1, true);
+    assert_eq!(gui.widget_at(20, 20), Some(BUTTON));
+    assert_eq!(gui.widget_at(100, 20), Some(PANEL));
+    assert_eq!(gui.widget_at(60, 20), Some(PANEL));
+    assert_eq!(gui.widget_at(300, 20), None);
+    assert_eq!(gui.get_hovered(), None);
+
+    // Moves update the hover but always reach the other processors
+    gui.mouse_change(MouseAction::Move(20, 20));
+    assert!(!gui.is_consumed());
+    assert_eq!(gui.get_hovered(), Some(BUTTON));
+    gui.mouse_change(MouseAction::Move(300, 20));
+    assert_eq!(gui.get_hovered(), None);
+    assert_eq!(
+        gui.take_events(),
+        [GuiEvent::Enter(BUTTON), GuiEvent::Leave(BUTTON)]
+    );
+
+    // Clicks on the world aren't consumed
+    gui.mouse_change(MouseAction::Press(MOUSE_LEFT));
+    assert!(!gui.is_consumed());
+    gui.mouse_change(MouseAction::Release(MOUSE_LEFT));
+    assert!(!gui.is_consumed());
+    gui.mouse_change(MouseAction::Wheel(0.0, 1.0));
+    assert!(!gui.is_consumed());
+    assert!(gui.take_events().is_empty());
+
+    // Clicking a button consumes the press and its release, the button keeps the capture
+    gui.mouse_change(MouseAction::Move(20, 20));
+    gui.mouse_change(MouseAction::Press(MOUSE_LEFT));
+    assert!(gui.is_consumed());
+    assert_eq!(gui.get_captured(), Some(BUTTON));
+    assert_eq!(gui.get_focused(), None);
+    gui.mouse_change(MouseAction::Release(MOUSE_LEFT));
+    assert!(gui.is_consumed());
+    assert_eq!(gui.get_captured(), None);
+    assert_eq!(
+        gui.take_events(),
+        [
+            GuiEvent::Enter(BUTTON),
+            GuiEvent::Press(BUTTON, MOUSE_LEFT),
+            GuiEvent::Release(BUTTON, MOUSE_LEFT),
+            GuiEvent::Click(BUTTON, MOUSE_LEFT),
+        ]
+    );
+
+    // Releasing outside of the pressed widget is no click, but is still consumed
+    gui.mouse_change(MouseAction::Press(MOUSE_RIGHT));
+    gui.mouse_change(MouseAction::Move(300, 20));
+    gui.mouse_change(MouseAction::Release(MOUSE_RIGHT));
+    assert!(gui.is_consumed());
+    assert_eq!(
+        gui.take_events(),
+        [
+            GuiEvent::Press(BUTTON, MOUSE_RIGHT),
+            GuiEvent::Leave(BUTTON),
+            GuiEvent::Release(BUTTON, MOUSE_RIGHT),
+        ]
+    );
+
+    // A button pressed on the world is released there, even if it is released over the gui
+    gui.mouse_change(MouseAction::Press(MOUSE_LEFT));
+    gui.mouse_change(MouseAction::Move(150, 50));
+    gui.mouse_change(MouseAction::Release(MOUSE_LEFT));
+    assert!(!gui.is_consumed());
+    gui.mouse_change(MouseAction::Wheel(0.0, -2.0));
+    assert!(gui.is_consumed());
+    assert_eq!(
+        gui.take_events(),
+        [GuiEvent::Enter(PANEL), GuiEvent::Scroll(PANEL, 0.0, -2.0)]
+    );
+
+    // Keys go to the world until a focusable widget is pressed
+    gui.keyboard_change(KeyboardAction::Press(KEY_W));
+    assert!(!gui.is_consumed());
+    gui.mouse_change(MouseAction::Move(20, 60));
+    gui.mouse_change(MouseAction::Press(MOUSE_LEFT));
+    gui.mouse_change(MouseAction::Release(MOUSE_LEFT));
+    assert_eq!(gui.get_focused(), Some(TEXT_FIELD));
+    gui.keyboard_change(KeyboardAction::Press(KEY_A));
+    assert!(gui.is_consumed());
+    gui.keyboard_change(KeyboardAction::Release(KEY_W));
+    assert!(!gui.is_consumed());
+    gui.keyboard_change(KeyboardAction::Release(KEY_A));
+    assert!(gui.is_consumed());
+    let events = gui.take_events();
+    assert_eq!(
+        events[..2],
+        [GuiEvent::Leave(PANEL), GuiEvent::Enter(TEXT_FIELD)]
+    );
+    assert_eq!(events[2], GuiEvent::Focus(TEXT_FIELD));
+    assert_eq!(
+        events[events.len() - 2..],
+        [
+            GuiEvent::Key(TEXT_FIELD, KeyboardAction::Press(KEY_A)),
+            GuiEvent::Key(TEXT_FIELD, KeyboardAction::Release(KEY_A)),
+        ]
+    );
+
+    // Focus is lost by pressing outside of the gui or by removing the widget
+    gui.mouse_change(MouseAction::Move(300, 300));
+    gui.mouse_change(MouseAction::Press(MOUSE_LEFT));
+    assert_eq!(gui.get_focused(), None);
+    gui.set_focus(Some(BUTTON));
+    assert_eq!(gui.get_focused(), None);
+    gui.set_focus(Some(TEXT_FIELD));
+    assert_eq!(gui.get_focused(), Some(TEXT_FIELD));
+    assert!(gui.remove_widget(TEXT_FIELD));
+    assert!(!gui.remove_widget(TEXT_FIELD));
+    assert_eq!(gui.get_focused(), None);
+    gui.keyboard_change(KeyboardAction::Press(KEY_A));
+    assert!(!gui.is_consumed());
+
+    gui.mouse_change(MouseAction::Move(20, 20));
+    gui.clear_widgets();
+    assert_eq!(gui.get_hovered(), None);
+    assert_eq!(gui.widget_at(20, 20), None);
+    println!("gui input: ok");
+}
@@ -0,0 +1,106 @@
+//! Binds actions to keys, mouse buttons, scroll directions, axes and chords, queries them against a
+//! raw input state and round trips the bindings through the config format.
+
+use mvcore::input::action::{ActionMap, Binding, Modifier, Trigger};
+use mvcore::input::raw::{Input, State};
+use mvcore::input::*;
+
+const CONFIG: &str = "# Controls
+jump = space, mouse_left
+move_x = a/d, left_arrow/right_arrow
+zoom = mouse_scroll_up
+save = ctrl+s
+move_back = s
+";
+
+fn press(input: &mut Input, key: usize) {
+    input.keys[key] = true;
+    input.keystates[key] = State::JustPressed;
+}
+
+fn main() {
+    let mut actions = ActionMap::parse(CONFIG).expect("Failed to parse bindings");
+    assert_eq!(
+        actions.get_actions().collect::<Vec<_>>(),
+        ["jump", "move_x", "zoom", "save", "move_back"]
+    );
+    assert_eq!(
+        actions.get_bindings("save"),
+        [Binding::button(Trigger::Key(KEY_S)).with_modifier(Modifier::Ctrl)]
+    );
+    assert_eq!(ActionMap::parse(&actions.write()).unwrap(), actions);
+
+    let mut input = Input::new();
+    assert!(!actions.is_pressed("jump", &input));
+    assert!(!actions.is_pressed("unbound", &input));
+
+    // Any binding triggers the action
+    input.mouse[MOUSE_LEFT] = true;
+    input.mousestates[MOUSE_LEFT] = State::JustPressed;
+    assert!(actions.is_pressed("jump", &input));
+    assert!(actions.is_just_pressed("jump", &input));
+    input.mousestates[MOUSE_LEFT] = State::Pressed;
+    assert!(!actions.is_just_pressed("jump", &input));
+    input.mouse[MOUSE_LEFT] = false;
+    input.mousestates[MOUSE_LEFT] = State::JustReleased;
+    assert!(actions.is_just_released("jump", &input));
+    press(&mut input, KEY_SPACE);
+    assert!(!actions.is_just_released("jump", &input));
+    assert_eq!(actions.get_value("jump", &input), 1.0);
+
+    // Axes report the direction, the binding with the larger magnitude wins
+    press(&mut input, KEY_A);
+    assert_eq!(actions.get_value("move_x", &input), -1.0);
+    press(&mut input, KEY_D);
+    assert_eq!(actions.get_value("move_x", &input), 0.0);
+    assert!(!actions.is_pressed("move_x", &input));
+    press(&mut input, KEY_RIGHT_ARROW);
+    assert_eq!(actions.get_value("move_x", &input), 1.0);
+
+    input.scroll[MOUSE_SCROLL_UP] = true;
+    input.scrollstates[MOUSE_SCROLL_UP] = 2.5;
+    assert!(actions.is_just_pressed("zoom", &input));
+    assert_eq!(actions.get_value("zoom", &input), 2.5);
+
+    // Chords need their modifiers and shadow the bindings without them
+    press(&mut input, KEY_S);
+    assert!(actions.is_pressed("move_back", &input));
+    assert!(!actions.is_pressed("save", &input));
+    press(&mut input, KEY_RIGHT_CTRL);
+    assert!(actions.is_just_pressed("save", &input));
+    assert!(!actions.is_pressed("move_back", &input));
+
+    // Players pick bindings by pressing them, overrides replace only the actions they list
+    assert_eq!(
+        Binding::capture(&input),
+        Some(Binding::button(Trigger::Key(KEY_A)).with_modifier(Modifier::Ctrl))
+    );
+    let mut modifier_only = Input::new();
+    press(&mut modifier_only, KEY_LEFT_SHIFT);
+    assert_eq!(
+        Binding::capture(&modifier_only),
+        Some(Binding::button(Trigger::Key(KEY_LEFT_SHIFT)))
+    );
+    assert_eq!(Binding::capture(&Input::new()), None);
+
+    let overrides = ActionMap::parse("jump = shift+w\n").unwrap();
+    actions.apply(&overrides);
+    assert_eq!(
+        actions.get_bindings("jump"),
+        [Binding::button(Trigger::Key(KEY_W)).with_modifier(Modifier::Shift)]
+    );
+    assert_eq!(actions.get_bindings("move_x").len(), 2);
+    actions.bind("jump", Binding::button(Trigger::Mouse(MOUSE_4)));
+    assert!(actions.unbind("jump", &Binding::parse("shift+w").unwrap()));
+    assert_eq!(actions.write().lines().next(), Some("jump = mouse_5"));
+
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("input_actions.cfg");
+    actions.save(&path).expect("Failed to save bindings");
+    assert_eq!(ActionMap::load(&path).unwrap(), actions);
+
+    assert!(ActionMap::parse("jump space").is_err());
+    assert!(ActionMap::parse("jump = hyper+space").is_err());
+    assert!(ActionMap::parse("jump = spaec").is_err());
+    assert!(ActionMap::parse(" = space").is_err());
+    println!("input actions: ok");
+}
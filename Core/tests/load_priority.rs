@@ -0,0 +1,135 @@
+//! Checks the order queued assets load in, cancellation of queued loads and that idle loader threads
+//! take work while another one is busy, on the null backend.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use log::LevelFilter;
+use mvcore::asset::importer::{AssetImporter, ImportContext};
+use mvcore::asset::manager::LoadPriority;
+use mvcore::asset::token::LoadStatus;
+use parking_lot::Mutex;
+
+mod common;
+
+static IMPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static GATE_ENTERED: AtomicBool = AtomicBool::new(false);
+static GATE_OPEN: AtomicBool = AtomicBool::new(false);
+
+/// Records the order assets are imported in.
+struct NameImporter;
+
+impl AssetImporter for NameImporter {
+    type Asset = String;
+    type Error = ();
+
+    fn extensions(&self) -> &[&str] {
+        &["name"]
+    }
+
+    fn import(&self, path: &str, _: &mut ImportContext) -> Result<String, ()> {
+        IMPORTED.lock().push(path.to_string());
+        Ok(path.to_string())
+    }
+}
+
+/// Blocks its loader thread until the gate is opened.
+struct GateImporter;
+
+impl AssetImporter for GateImporter {
+    type Asset = ();
+    type Error = ();
+
+    fn extensions(&self) -> &[&str] {
+        &["gate"]
+    }
+
+    fn import(&self, _: &str, _: &mut ImportContext) -> Result<(), ()> {
+        GATE_ENTERED.store(true, Ordering::Release);
+        while !GATE_OPEN.load(Ordering::Acquire) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    // With one loader thread blocked by the gate, everything else stays queued
+    let manager = common::null_manager("Load priority test", 1);
+    manager.register_importer(NameImporter);
+    manager.register_importer(GateImporter);
+    let gate = manager.create::<()>("first.gate");
+    gate.load();
+    common::wait_until("Entering the gate", || GATE_ENTERED.load(Ordering::Acquire));
+
+    let background = manager.create::<String>("background.name");
+    let normal = manager.create::<String>("normal.name");
+    let immediate = manager.create::<String>("immediate.name");
+    let promoted = manager.create::<String>("promoted.name");
+    let cancelled = manager.create::<String>("cancelled.name");
+    background.load_with_priority(LoadPriority::Background);
+    promoted.load_with_priority(LoadPriority::Background);
+    normal.load();
+    immediate.load_with_priority(LoadPriority::Immediate);
+    let cancelled_token = cancelled.load();
+    // Loading again with a higher priority moves the queued load up
+    promoted.load_with_priority(LoadPriority::Immediate);
+
+    // Unloading before the load started cancels it right away
+    assert_eq!(cancelled.get_asset().status(), LoadStatus::Loading);
+    assert_eq!(cancelled_token.status(), LoadStatus::Pending);
+    cancelled.unload();
+    assert!(cancelled_token.is_done());
+    assert_eq!(cancelled_token.status(), LoadStatus::Cancelled);
+    assert_eq!(cancelled.get_asset().status(), LoadStatus::Cancelled);
+
+    GATE_OPEN.store(true, Ordering::Release);
+    common::wait_idle(&manager);
+    assert_eq!(
+        *IMPORTED.lock(),
+        [
+            "immediate.name",
+            "promoted.name",
+            "normal.name",
+            "background.name"
+        ]
+    );
+    assert!(!cancelled.is_loaded());
+    assert_eq!(*background.get().unwrap(), "background.name");
+
+    // Loading a cancelled asset again works as usual
+    assert_eq!(cancelled.load().wait(), LoadStatus::Loaded);
+    for handle in [&background, &normal, &immediate, &promoted, &cancelled] {
+        handle.unload();
+    }
+    gate.unload();
+    common::wait_idle(&manager);
+    drop(manager);
+
+    // A second thread keeps loading while the first one is stuck on the gate
+    GATE_OPEN.store(false, Ordering::Release);
+    IMPORTED.lock().clear();
+    let manager = common::null_manager("Load priority test", 2);
+    manager.register_importer(NameImporter);
+    manager.register_importer(GateImporter);
+    let gate = manager.create::<()>("second.gate");
+    let gate_token = gate.load();
+    let names = (0..8)
+        .map(|i| manager.create::<String>(&format!("{i}.name")))
+        .collect::<Vec<_>>();
+    for name in &names {
+        assert_eq!(name.load().wait(), LoadStatus::Loaded);
+    }
+    assert!(!gate_token.is_done());
+    GATE_OPEN.store(true, Ordering::Release);
+    assert_eq!(gate_token.wait(), LoadStatus::Loaded);
+
+    for name in &names {
+        name.unload();
+    }
+    gate.unload();
+    common::wait_idle(&manager);
+    println!("load priority: ok");
+}
@@ -0,0 +1,95 @@
+//! Waits for assets through the load tokens returned by `AssetHandle::load`, on the null backend.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use log::LevelFilter;
+use mvcore::asset::asset::AssetType;
+use mvcore::asset::token::{LoadStatus, LoadToken};
+use mvsync::block::await_sync;
+
+mod common;
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [{ "name": "triangle", "mesh": 0 }],
+    "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+    "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+    "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+    "accessors": [{
+        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+fn wait_done(token: &LoadToken) -> LoadStatus {
+    common::wait_until("Load token", || token.is_done());
+    token.status()
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    let dir = common::test_dir("load_token");
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    std::fs::write(dir.join("triangle.bin"), bin).expect("Failed to write buffer");
+    std::fs::write(dir.join("triangle.gltf"), GLTF).expect("Failed to write model");
+
+    let manager = common::null_manager("Load token test", 2);
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+    let loaded = Arc::new(AtomicU32::new(0));
+    let failed = Arc::new(AtomicU32::new(0));
+    let count = |counter: &Arc<AtomicU32>| {
+        let counter = counter.clone();
+        move |_| {
+            counter.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+
+    // Awaiting a token
+    let model = manager.create_asset(&path("triangle.gltf"), AssetType::Model);
+    let token = model.load();
+    token.on_loaded(count(&loaded)).on_failed(count(&failed));
+    assert_eq!(await_sync(token.clone()), LoadStatus::Loaded);
+    assert!(model.is_loaded());
+
+    // Callbacks only run during update
+    assert_eq!(loaded.load(Ordering::Acquire), 0);
+    manager.update();
+    assert_eq!(loaded.load(Ordering::Acquire), 1);
+    assert_eq!(failed.load(Ordering::Acquire), 0);
+
+    // Tokens of assets that are already loaded complete right away
+    let again = model.load();
+    assert_eq!(again.status(), LoadStatus::Loaded);
+    again.on_loaded(count(&loaded));
+    manager.update();
+    assert_eq!(loaded.load(Ordering::Acquire), 2);
+
+    // Failing assets
+    let missing = manager.create_asset(&path("missing.gltf"), AssetType::Model);
+    let token = missing.load();
+    token.on_loaded(count(&loaded)).on_failed(count(&failed));
+    assert_eq!(token.wait(), LoadStatus::Failed);
+    manager.update();
+    assert_eq!(loaded.load(Ordering::Acquire), 2);
+    assert_eq!(failed.load(Ordering::Acquire), 1);
+    assert_eq!(missing.load().status(), LoadStatus::Failed);
+
+    // Unloading right away never leaves a token pending
+    model.unload();
+    model.unload();
+    for _ in 0..50 {
+        let token = model.load();
+        model.unload();
+        assert_ne!(wait_done(&token), LoadStatus::Pending);
+    }
+
+    println!("load token: ok");
+}
@@ -3,6 +3,7 @@ use log::LevelFilter;
 use mvutils::version::Version;
 use mvcore::asset::asset::AssetType;
 use mvcore::asset::manager::{AssetHandle, AssetManager};
+use mvcore::asset::token::{LoadStatus, LoadToken};
 use mvcore::math::vec::Vec4;
 use mvcore::render::ApplicationLoopCallbacks;
 use mvcore::render::backend::Backend;
@@ -35,6 +36,7 @@ struct AppLoop {
     device: Device,
     manager: Arc<AssetManager>,
     handle: AssetHandle,
+    token: LoadToken,
 }
 
 impl ApplicationLoopCallbacks for AppLoop {
@@ -52,28 +54,33 @@ impl ApplicationLoopCallbacks for AppLoop {
         );
 
         let manager = AssetManager::new(device.clone(), 1);
+        window.set_asset_manager(manager.clone());
 
         let handle = manager.create_asset("texture.png", AssetType::Texture);
 
-        handle.load();
+        let token = handle.load();
+        token
+            .on_loaded(|_| println!("loaded"))
+            .on_failed(|_| println!("Failed"));
 
-        Self { device, manager, handle }
+        Self { device, manager, handle, token }
     }
 
     fn update(&mut self, window: &mut Window, delta_t: f64) {}
 
     fn draw(&mut self, window: &mut Window, delta_t: f64) {
-        let texture = self.handle.get();
-        if texture.failed() {
-            //draw failed thing
-            println!("Failed")
-        } else if !texture.is_loaded() {
-            //draw loading thing
-            println!("loading")
-        } else {
-            let Some(texture) = texture.as_texture() else { unreachable!() };
-            //draw the texture ig
-            println!("loaded")
+        match self.token.status() {
+            LoadStatus::Loaded => {
+                let texture = self.handle.get();
+                let Some(texture) = texture.as_texture() else { unreachable!() };
+                //draw the texture ig
+            }
+            LoadStatus::Pending => {
+                //draw loading thing
+            }
+            _ => {
+                //draw failed thing
+            }
         }
     }
 
@@ -0,0 +1,118 @@
+//! Creates images with mip chains and imports textures with mipmaps, on the null backend.
+
+use log::LevelFilter;
+use mvcore::asset::cook;
+use mvcore::asset::manager::AssetManager;
+use mvcore::asset::token::LoadStatus;
+use mvcore::render::backend::buffer::MemoryProperties;
+use mvcore::render::backend::command_buffer::RecordedCommand;
+use mvcore::render::backend::image::{
+    Image, ImageAspect, ImageFormat, ImageTiling, ImageType, ImageUsage, MVImageCreateInfo,
+};
+use mvcore::render::backend::sampler::{
+    Filter, MVSamplerCreateInfo, MipmapMode, Sampler, SamplerAddressMode, LOD_CLAMP_NONE,
+};
+use mvcore::render::backend::Extent2D;
+use mvcore::render::texture::Texture;
+
+mod common;
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let extent = Extent2D {
+        width: 1024,
+        height: 256,
+    };
+    assert_eq!(extent.get_max_mip_levels(), 11);
+    assert_eq!(
+        extent.get_mip_extent(3),
+        Extent2D {
+            width: 128,
+            height: 32
+        }
+    );
+    assert_eq!(
+        extent.get_mip_extent(10),
+        Extent2D {
+            width: 1,
+            height: 1
+        }
+    );
+    assert_eq!(Extent2D::default().get_max_mip_levels(), 1);
+
+    let device = common::null_device("Mipmap test");
+
+    // Generating the chain of an image rendered to
+    let size = Extent2D {
+        width: 8,
+        height: 8,
+    };
+    let image = Image::new(
+        device.clone(),
+        MVImageCreateInfo {
+            size,
+            format: ImageFormat::R8G8B8A8,
+            usage: ImageUsage::SAMPLED | ImageUsage::COLOR_ATTACHMENT,
+            memory_properties: MemoryProperties::DEVICE_LOCAL,
+            aspect: ImageAspect::COLOR,
+            tiling: ImageTiling::Optimal,
+            layer_count: 1,
+            mip_level_count: size.get_max_mip_levels(),
+            image_type: ImageType::Image2D,
+            cubemap: false,
+            memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+            data: None,
+            label: Some("Mipmapped image".to_string()),
+        },
+    );
+    assert_eq!(image.get_mip_level_count(), 4);
+    let cmd = device.begin_single_time_command(device.get_graphics_command_pool());
+    image.generate_mipmaps(Some(&cmd));
+    cmd.end();
+    assert!(cmd
+        .get_recorded_commands()
+        .contains(&RecordedCommand::GenerateMipmaps {
+            image: image.get_id(),
+            levels: 4
+        }));
+    device.end_single_time_command(
+        cmd,
+        device.get_graphics_command_pool(),
+        device.get_graphics_queue(),
+    );
+
+    let _sampler = Sampler::new(
+        device.clone(),
+        MVSamplerCreateInfo {
+            address_mode: SamplerAddressMode::Repeat,
+            filter_mode: Filter::Linear,
+            mipmap_mode: MipmapMode::Linear,
+            anisotropy: Some(8.0),
+            lod_bias: -0.5,
+            min_lod: 0.0,
+            max_lod: LOD_CLAMP_NONE,
+            label: None,
+        },
+    );
+
+    // Imported and cooked textures both get a full chain
+    let dir = common::test_dir("mipmaps");
+    let png = image::RgbaImage::from_pixel(16, 4, image::Rgba([0, 0, 255, 255]));
+    png.save(dir.join("strip.png"))
+        .expect("Failed to write texture");
+    std::fs::write(dir.join("strip.mvtex"), cook::cook_image(png.into()))
+        .expect("Failed to write cooked texture");
+
+    let manager = AssetManager::new(device, 1);
+    for name in ["strip.png", "strip.mvtex"] {
+        let texture = manager.create::<Texture>(dir.join(name).to_str().unwrap());
+        assert_eq!(texture.load().wait(), LoadStatus::Loaded);
+        let loaded = texture.get().unwrap();
+        assert_eq!(loaded.image().get_mip_level_count(), 5);
+        assert_eq!(loaded.get_size(), (64 + 16 + 4 + 2 + 1) * 4);
+        assert_eq!(&loaded.image().read_pixels().data[..4], &[0, 0, 255, 255]);
+        texture.unload();
+    }
+    println!("mipmaps: ok");
+}
@@ -0,0 +1,84 @@
+//! Loads shader source files through the asset manager on the null backend and checks that
+//! compile errors are reported with their location.
+
+use log::LevelFilter;
+use mvcore::asset::asset::{AssetType, ShaderKind};
+use mvcore::render::backend::shader::ShaderCompileError;
+
+mod common;
+
+const COMMON: &str = "vec4 tint(vec4 color) {
+    return color * 0.5;
+}
+";
+
+const VALID: &str = "#version 450
+#extension GL_GOOGLE_include_directive : require
+#include \"common.glsl\"
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = tint(vec4(1.0));
+}
+";
+
+const BROKEN: &str = "#version 450
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = undefinedValue;
+}
+";
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let dir = common::test_dir("shader_asset");
+    std::fs::write(dir.join("common.glsl"), COMMON).expect("Failed to write shader");
+    std::fs::write(dir.join("valid.frag"), VALID).expect("Failed to write shader");
+    std::fs::write(dir.join("broken.frag"), BROKEN).expect("Failed to write shader");
+
+    let manager = common::null_manager("Shader asset test", 2);
+
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let valid = manager.create_asset(&path("valid.frag"), AssetType::Shader(ShaderKind::Fragment));
+    let broken = manager.create_asset(
+        &path("broken.frag"),
+        AssetType::Shader(ShaderKind::Fragment),
+    );
+    let missing = manager.create_asset(
+        &path("missing.frag"),
+        AssetType::Shader(ShaderKind::Fragment),
+    );
+    valid.load();
+    broken.load();
+    missing.load();
+
+    common::wait_idle(&manager);
+
+    let asset = valid.get();
+    if let Some(error) = asset.error_as::<ShaderCompileError>() {
+        panic!("Valid shader failed: {}", *error);
+    }
+    assert!(!asset.failed());
+    assert!(asset.as_shader().is_some());
+
+    let asset = broken.get();
+    assert!(asset.failed());
+    let error = asset
+        .error_as::<ShaderCompileError>()
+        .expect("Broken shader should fail with a ShaderCompileError");
+    assert!(error.file.ends_with("broken.frag"), "{error}");
+    assert_eq!(error.line, Some(6), "{error}");
+    assert!(error.message.contains("undefinedValue"), "{error}");
+
+    let asset = missing.get();
+    let error = asset
+        .error_as::<ShaderCompileError>()
+        .expect("Missing shader should fail with a ShaderCompileError");
+    assert_eq!(error.line, None);
+
+    println!("shader asset: ok");
+}
@@ -0,0 +1,96 @@
+//! Translates key and input method events into typed text, and types into focused gui widgets
+//! instead of the game world.
+
+use std::sync::{Arc, RwLock};
+
+use mvcore::input::gui::{GuiEvent, WidgetRect};
+use mvcore::input::raw::{Input, State};
+use mvcore::input::*;
+use winit::event::{ElementState, Ime};
+use winit::keyboard::ModifiersState;
+
+const CHAT: u64 = 1;
+
+fn press(collector: &mut InputCollector, key: usize, text: &str) {
+    collector.collect_key(Some(key), ElementState::Pressed, false, Some(text));
+    collector.collect_key(Some(key), ElementState::Released, false, None);
+}
+
+fn main() {
+    let input = Arc::new(RwLock::new(Input::new()));
+    let mut collector = InputCollector::new(input.clone());
+
+    // Key presses type their text, held keys type it again without pressing again
+    press(&mut collector, KEY_H, "H");
+    collector.collect_key(None, ElementState::Pressed, false, Some("é"));
+    collector.collect_key(Some(KEY_L), ElementState::Pressed, false, Some("l"));
+    collector.collect_key(Some(KEY_L), ElementState::Pressed, true, Some("l"));
+    assert!(input.read().unwrap().keystates[KEY_L] == State::JustPressed);
+    collector.collect_key(Some(KEY_L), ElementState::Released, false, None);
+    assert_eq!(input.read().unwrap().text, "Héll");
+
+    // Control characters like backspace and enter are keys, not text
+    press(&mut collector, KEY_BACKSPACE, "\u{8}");
+    press(&mut collector, KEY_ENTER, "\r");
+    press(&mut collector, KEY_TAB, "\t");
+    assert_eq!(input.read().unwrap().text, "Héll");
+    assert!(input.read().unwrap().keystates[KEY_BACKSPACE] == State::JustReleased);
+
+    // Shortcuts don't type, AltGr reports ctrl and alt and types
+    collector.collect_modifiers(ModifiersState::CONTROL);
+    press(&mut collector, KEY_C, "c");
+    collector.collect_modifiers(ModifiersState::SUPER);
+    press(&mut collector, KEY_V, "v");
+    collector.collect_modifiers(ModifiersState::CONTROL | ModifiersState::ALT);
+    press(&mut collector, KEY_Q, "@");
+    collector.collect_modifiers(ModifiersState::SHIFT);
+    press(&mut collector, KEY_1, "!");
+    collector.collect_modifiers(ModifiersState::empty());
+    assert_eq!(input.read().unwrap().text, "Héll@!");
+
+    // The text is cleared after every tick
+    collector.end_tick();
+    assert!(input.read().unwrap().text.is_empty());
+    assert!(input.read().unwrap().keystates[KEY_L] == State::Released);
+
+    // Composed text is shown as preedit until it is committed
+    collector.collect_ime(&Ime::Enabled);
+    assert!(input.read().unwrap().ime);
+    collector.collect_ime(&Ime::Preedit("せかい".to_string(), Some((3, 6))));
+    assert_eq!(input.read().unwrap().preedit, "せかい");
+    assert_eq!(input.read().unwrap().preedit_cursor, Some((3, 6)));
+    assert!(input.read().unwrap().text.is_empty());
+    collector.collect_ime(&Ime::Preedit(String::new(), None));
+    collector.collect_ime(&Ime::Commit("世界".to_string()));
+    assert_eq!(input.read().unwrap().text, "世界");
+    assert!(input.read().unwrap().preedit.is_empty());
+    assert_eq!(input.read().unwrap().preedit_cursor, None);
+
+    // Disabling the input method drops what it was still composing
+    collector.collect_ime(&Ime::Preedit("にほ".to_string(), Some((0, 0))));
+    collector.collect_ime(&Ime::Disabled);
+    assert!(!input.read().unwrap().ime);
+    assert!(input.read().unwrap().preedit.is_empty());
+    assert_eq!(input.read().unwrap().preedit_cursor, None);
+    assert_eq!(input.read().unwrap().text, "世界");
+    collector.end_tick();
+
+    // Focused widgets receive the typed characters instead of the game world
+    let gui = collector.get_gui_processor();
+    gui.set_widget(CHAT, WidgetRect::new(0, 0, 100, 20), 0, true);
+    gui.set_focus(Some(CHAT));
+    collector.collect_ime(&Ime::Commit("日".to_string()));
+    press(&mut collector, KEY_A, "a");
+    assert!(input.read().unwrap().text.is_empty());
+    assert_eq!(
+        collector.get_gui_processor().take_events(),
+        [
+            GuiEvent::Focus(CHAT),
+            GuiEvent::Key(CHAT, KeyboardAction::Type('日')),
+            GuiEvent::Key(CHAT, KeyboardAction::Press(KEY_A)),
+            GuiEvent::Key(CHAT, KeyboardAction::Type('a')),
+            GuiEvent::Key(CHAT, KeyboardAction::Release(KEY_A)),
+        ]
+    );
+    println!("text input: ok");
+}
@@ -0,0 +1,219 @@
+//! Packs sprites into atlas pages with padding and extrusion, grows and repacks them, and loads
+//! atlases from source files and their cooked versions on the null backend.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use log::LevelFilter;
+use mvcore::asset::cook::{self, Cooker, MANIFEST_NAME};
+use mvcore::asset::manager::AssetManager;
+use mvcore::asset::token::LoadStatus;
+use mvcore::asset::vfs::AssetSource;
+use mvcore::render::atlas::{AtlasBuilder, AtlasOptions, AtlasPacker, AtlasRect, TextureAtlas};
+
+mod common;
+
+fn sprite(width: u32, height: u32, value: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        width,
+        height,
+        Rgba([value, 0, 0, 255]),
+    ))
+}
+
+fn overlaps(a: &AtlasRect, b: &AtlasRect) -> bool {
+    a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Error);
+
+    // Inserted rects never overlap, growing keeps them and makes room for more
+    let mut packer = AtlasPacker::new(64, 64);
+    let mut used = Vec::new();
+    while let Some(rect) = packer.insert(10, 7) {
+        assert!(rect.x + rect.width <= 64 && rect.y + rect.height <= 64);
+        assert!(used.iter().all(|other| !overlaps(&rect, other)));
+        used.push(rect);
+    }
+    assert_eq!(used.len(), 6 * 9);
+    assert_eq!(packer.insert(64, 2), None);
+    packer.grow(128, 64);
+    let wide = packer.insert(64, 64).expect("Grown packer has space left");
+    assert!(used.iter().all(|other| !overlaps(&wide, other)));
+
+    // Sprites are drawn after the padding with their border pixels repeated around them
+    let options = AtlasOptions {
+        padding: 2,
+        extrude: 1,
+        page_size: 32,
+        max_page_size: 64,
+        ..AtlasOptions::default()
+    };
+    let mut builder = AtlasBuilder::new(options);
+    let mut checker = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 255, 255]));
+    checker.put_pixel(0, 0, Rgba([255, 255, 255, 255]));
+    builder
+        .add("checker", DynamicImage::ImageRgba8(checker))
+        .unwrap();
+    assert_eq!(
+        builder.get_rect("checker"),
+        Some((
+            0,
+            AtlasRect {
+                x: 3,
+                y: 3,
+                width: 4,
+                height: 4
+            }
+        ))
+    );
+    let page = builder.render_page(0);
+    assert_eq!(page.dimensions(), (32, 32));
+    assert_eq!(page.get_pixel(2, 2), &Rgba([255, 255, 255, 255]));
+    assert_eq!(page.get_pixel(3, 2), &Rgba([255, 255, 255, 255]));
+    assert_eq!(page.get_pixel(7, 7), &Rgba([0, 0, 255, 255]));
+    assert_eq!(page.get_pixel(1, 1), &Rgba([0, 0, 0, 0]));
+    assert_eq!(page.get_pixel(8, 8), &Rgba([0, 0, 0, 0]));
+
+    // Full pages grow up to the maximum size before another page is started
+    builder.add("large", sprite(40, 20, 1)).unwrap();
+    assert_eq!(builder.get_page_count(), 1);
+    assert_eq!(builder.get_page_size(0).width, 64);
+    builder.add("tall", sprite(20, 50, 2)).unwrap();
+    builder.add("wide", sprite(50, 20, 3)).unwrap();
+    assert_eq!(builder.get_page_count(), 2);
+    assert!(builder.add("huge", sprite(61, 1, 4)).is_err());
+    assert!(builder.add("empty", sprite(0, 0, 4)).is_err());
+    assert_eq!(builder.get_sprites().len(), 4);
+
+    // Sprites with their borders and the padding before them don't overlap
+    let padded = |rect: AtlasRect| AtlasRect {
+        x: rect.x - 3,
+        y: rect.y - 3,
+        width: rect.width + 4,
+        height: rect.height + 4,
+    };
+    builder.repack();
+    let sprites = builder.get_sprites();
+    for (index, (name, page, rect)) in sprites.iter().enumerate() {
+        let size = builder.get_page_size(*page);
+        assert!(
+            rect.x + rect.width + 3 <= size.width,
+            "{name} is on the border"
+        );
+        assert!(
+            rect.y + rect.height + 3 <= size.height,
+            "{name} is on the border"
+        );
+        for (other, other_page, other_rect) in &sprites[index + 1..] {
+            assert!(
+                page != other_page || !overlaps(&padded(*rect), &padded(*other_rect)),
+                "{name} overlaps {other}"
+            );
+        }
+    }
+
+    let dir = common::test_dir("texture_atlas");
+    let input = dir.join("input");
+    let output = dir.join("output");
+    std::fs::create_dir_all(input.join("sprites")).expect("Failed to create test directory");
+    for (name, width, height, value) in [
+        ("player", 16, 24, 10),
+        ("coin", 8, 8, 20),
+        ("tree", 32, 48, 30),
+    ] {
+        RgbaImage::from_pixel(width, height, Rgba([value, 0, 0, 255]))
+            .save(input.join(format!("sprites/{name}.png")))
+            .expect("Failed to write sprite");
+    }
+    std::fs::write(
+        input.join("ui.atlas"),
+        "# sprites of the ui\npadding 1\nextrude 0\npage_size 64\n\nsprites/player.png\nsprites/coin.png\nsprites/tree.png\n",
+    )
+    .expect("Failed to write atlas");
+    std::fs::write(input.join("broken.atlas"), "padding many\n").expect("Failed to write atlas");
+
+    let report = Cooker::new(&input, &output).cook().expect("Failed to cook");
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "broken.atlas");
+    let cooked = cook::read_atlas(&std::fs::read(output.join("ui.atlas.mvatlas")).unwrap())
+        .expect("Invalid cooked atlas");
+    assert_eq!(cooked.options.padding, 1);
+    assert_eq!(cooked.options.extrude, 0);
+    assert_eq!(cooked.pages, ["ui.atlas.page0.mvtex"]);
+    assert_eq!(cooked.sprites.len(), 3);
+    assert!(output.join("ui.atlas.page0.mvtex").is_file());
+
+    let device = common::null_device("Texture atlas test");
+
+    // Runtime atlases register their pages as textures and report changed pages as reloads
+    let manager = AssetManager::new(device.clone(), 1);
+    let reloads = manager.subscribe_reloads();
+    let mut atlas = TextureAtlas::new(&manager, "sprites", options);
+    atlas.add("red", sprite(8, 8, 255)).unwrap();
+    assert!(atlas.get_region("red").is_none());
+    atlas.upload();
+    assert_eq!(atlas.get_page_count(), 1);
+    let page = atlas.get_page(0).unwrap();
+    assert_eq!(page.get_path(), "sprites#page0");
+    assert!(page.is_loaded());
+    let texture = page.get().unwrap();
+    assert_eq!(texture.image().get_extent().width, 32);
+    let pixels = texture.image().read_pixels().data;
+    assert_eq!(&pixels[(3 * 32 + 3) * 4..][..4], &[255, 0, 0, 255]);
+    // Regions are flipped vertically like the ones of Texture::as_region
+    let region = atlas.get_region("red").unwrap();
+    let coords = region.coords();
+    assert_eq!(
+        (coords.x, coords.y, coords.z, coords.w),
+        (3.0 / 32.0, 21.0 / 32.0, 8.0 / 32.0, 8.0 / 32.0)
+    );
+    assert!(reloads.try_recv().is_err());
+
+    atlas.add("blue", sprite(30, 30, 0)).unwrap();
+    atlas.upload();
+    assert_eq!(atlas.get_page_count(), 1);
+    assert_eq!(reloads.try_recv().unwrap().get_path(), "sprites#page0");
+    assert_eq!(page.get().unwrap().image().get_extent().width, 64);
+    assert!(!region.same_texture(&atlas.get_region("red").unwrap()));
+    assert!(atlas.remove("blue"));
+    atlas.repack();
+    atlas.upload();
+    assert_eq!(page.get().unwrap().image().get_extent().width, 32);
+    assert_eq!(manager.get_retired(), 2);
+    drop(atlas);
+
+    // Source files are packed when they are loaded
+    let path = |name: &str| input.join(name).to_str().unwrap().to_string();
+    let atlas = TextureAtlas::load(&manager, &path("ui.atlas")).expect("Failed to load atlas");
+    assert_eq!(atlas.get_page_count(), 1);
+    assert_eq!(atlas.get_builder().get_options().padding, 1);
+    assert_eq!(atlas.get_rect("sprites/tree.png").unwrap().1.height, 48);
+    assert!(atlas.get_region("sprites/coin.png").is_some());
+    assert!(TextureAtlas::load(&manager, &path("broken.atlas")).is_err());
+    assert!(TextureAtlas::load(&manager, &path("missing.atlas")).is_err());
+    drop(atlas);
+
+    // Cooked atlases load their pages, new sprites go onto pages after them
+    let manager = AssetManager::new(device, 1);
+    manager
+        .get_file_system()
+        .mount(AssetSource::Directory(output), 0);
+    manager.load_cooked_manifest(MANIFEST_NAME).unwrap();
+    let mut atlas = TextureAtlas::load(&manager, "ui.atlas").expect("Failed to load cooked atlas");
+    let cooked_page = atlas.get_page(0).unwrap();
+    assert_eq!(cooked_page.get_path(), "ui.atlas.page0.mvtex");
+    assert_eq!(cooked_page.load().wait(), LoadStatus::Loaded);
+    cooked_page.unload();
+    assert!(atlas.is_loaded());
+    let (page, rect) = atlas.get_rect("sprites/player.png").unwrap();
+    assert_eq!((page, rect.width, rect.height), (0, 16, 24));
+    assert!(atlas.get_region("sprites/player.png").is_some());
+
+    atlas.add("sprites/coin.png", sprite(8, 8, 40)).unwrap();
+    atlas.upload();
+    assert_eq!(atlas.get_page_count(), 2);
+    assert_eq!(atlas.get_rect("sprites/coin.png").unwrap().0, 1);
+    assert_eq!(atlas.get_page(1).unwrap().get_path(), "ui.atlas#page1");
+    drop(atlas);
+    println!("texture atlas: ok");
+}
@@ -1,13 +1,15 @@
 use std::mem;
 use std::sync::Arc;
 
+use crossbeam_channel::Receiver;
 use mvutils::unsafe_utils::{DangerousCell, Unsafe};
 use shaderc::ShaderKind;
 use mvcore::asset::asset::AssetType;
-use mvcore::asset::manager::{AssetHandle, AssetManager};
+use mvcore::asset::manager::{AssetHandle, AssetManager, Handle};
 
 use mvcore::math::vec::{Vec2, Vec3, Vec4};
 use mvcore::render::backend::buffer::{Buffer, BufferUsage, MVBufferCreateInfo, MemoryProperties};
+use mvcore::render::backend::command_buffer::CommandBuffer;
 use mvcore::render::backend::descriptor_set::{
     DescriptorPool, DescriptorPoolFlags, DescriptorPoolSize, DescriptorSet,
     DescriptorSetLayoutBinding, DescriptorType, MVDescriptorPoolCreateInfo,
@@ -21,12 +23,13 @@ use mvcore::render::backend::pipeline::{
     MVGraphicsPipelineCreateInfo, Pipeline, Topology,
 };
 use mvcore::render::backend::sampler::{
-    Filter, MVSamplerCreateInfo, MipmapMode, Sampler, SamplerAddressMode,
+    Filter, MVSamplerCreateInfo, MipmapMode, Sampler, SamplerAddressMode, LOD_CLAMP_NONE,
 };
-use mvcore::render::backend::shader::ShaderStage;
+use mvcore::render::backend::shader::{Shader, ShaderStage};
 use mvcore::render::backend::{Extent2D, Extent3D};
 use mvcore::render::camera::OrthographicCamera;
 use mvcore::render::mesh::Mesh;
+use mvcore::render::texture::Texture;
 use mvcore::render::renderer::Renderer;
 use mvcore::render::window::Window;
 
@@ -47,6 +50,7 @@ pub struct Transform {
     pub rotation: Vec3,
     pub scale: Vec2,
     pub tex_coord: Vec4,
+    /// sRGB, the same as colors picked in image editors.
     pub color: Vec4,
 }
 
@@ -54,7 +58,8 @@ static MAX_BATCH_SIZE: u32 = 10000;
 
 pub struct Renderer2D {
     device: Device,
-    core_renderer: Arc<DangerousCell<Renderer>>,
+    core_renderer: Option<Arc<DangerousCell<Renderer>>>,
+    max_frames_in_flight: u32,
     quad_mesh: Mesh,
     camera_sets: Vec<DescriptorSet>,
     camera_buffers: Vec<Buffer>,
@@ -69,7 +74,10 @@ pub struct Renderer2D {
     handle: AssetHandle,
     default_sampler: Sampler,
     atlas_sets: Vec<DescriptorSet>,
-    default_image: Image
+    default_image: Image,
+    atlas_texture: Option<Handle<Texture>>,
+    atlas_reloads: Option<Receiver<AssetHandle>>,
+    atlas_outdated: Vec<bool>,
 }
 
 impl Renderer2D {
@@ -79,7 +87,23 @@ impl Renderer2D {
     }
 
     pub fn new(device: Device, renderer: Arc<DangerousCell<Renderer>>, extent: Extent2D) -> Self {
+        let max_frames_in_flight = renderer.get().get_max_frames_in_flight();
+        Self::create(device, Some(renderer), extent, max_frames_in_flight)
+    }
+
+    /// Creates a renderer that isn't bound to a window. There is no frame to draw into, so it has
+    /// to be driven with [`Renderer2D::draw_to`], the result can be read back from
+    /// [`Renderer2D::get_geometry_image`].
+    pub fn new_offscreen(device: Device, extent: Extent2D, max_frames_in_flight: u32) -> Self {
+        Self::create(device, None, extent, max_frames_in_flight)
+    }
 
+    fn create(
+        device: Device,
+        renderer: Option<Arc<DangerousCell<Renderer>>>,
+        extent: Extent2D,
+        max_frames_in_flight: u32,
+    ) -> Self {
         //
         // Pool
         //
@@ -122,7 +146,7 @@ impl Renderer2D {
 
         let mut camera_buffers = Vec::new();
 
-        for _ in 0..renderer.get().get_max_frames_in_flight() {
+        for _ in 0..max_frames_in_flight {
             let mut buffer = Buffer::new(
                 device.clone(),
                 MVBufferCreateInfo {
@@ -146,7 +170,7 @@ impl Renderer2D {
 
         let mut camera_sets = Vec::new();
 
-        for index in 0..renderer.get().get_max_frames_in_flight() {
+        for index in 0..max_frames_in_flight {
             let mut camera_set = DescriptorSet::new(
                 device.clone(),
                 MVDescriptorSetCreateInfo {
@@ -172,12 +196,12 @@ impl Renderer2D {
         //
 
         let mut transforms_buffers = Vec::new();
-        for _ in 0..renderer.get().get_max_frames_in_flight() {
+        for _ in 0..max_frames_in_flight {
             transforms_buffers.push(Self::create_transform_buffer(device.clone()));
         }
 
         let mut transforms_sets = Vec::new();
-        for index in 0..renderer.get().get_max_frames_in_flight() {
+        for index in 0..max_frames_in_flight {
             let mut set = DescriptorSet::new(
                 device.clone(),
                 MVDescriptorSetCreateInfo {
@@ -207,7 +231,10 @@ impl Renderer2D {
             address_mode: SamplerAddressMode::ClampToEdge,
             filter_mode: Filter::Nearest,
             mipmap_mode: MipmapMode::Nearest,
-            anisotropy: false,
+            anisotropy: None,
+            lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: LOD_CLAMP_NONE,
             label: None,
         });
 
@@ -219,6 +246,7 @@ impl Renderer2D {
             aspect: ImageAspect::COLOR,
             tiling: ImageTiling::Optimal,
             layer_count: 1,
+            mip_level_count: 1,
             image_type: ImageType::Image2D,
             cubemap: false,
             memory_usage_flags: gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
@@ -228,7 +256,7 @@ impl Renderer2D {
         default_image.transition_layout(ImageLayout::ShaderReadOnlyOptimal, None, AccessFlags::empty(), AccessFlags::empty());
 
         let mut atlas_sets = Vec::new();
-        for index in 0..renderer.get().get_max_frames_in_flight() {
+        for index in 0..max_frames_in_flight {
             let mut set = DescriptorSet::new(
                 device.clone(),
                 MVDescriptorSetCreateInfo {
@@ -294,7 +322,7 @@ impl Renderer2D {
         // Framebuffer
         //
         let mut geometry_framebuffers = Vec::new();
-        for _ in 0..renderer.get().get_max_frames_in_flight() {
+        for _ in 0..max_frames_in_flight {
             let framebuffer = Framebuffer::new(
                 device.clone(),
                 MVFramebufferCreateInfo {
@@ -314,13 +342,15 @@ impl Renderer2D {
         //
 
         // Shaders
-        let vertex_shader = renderer.get().compile_shader(
+        let vertex_shader = Shader::compile(
+            device.clone(),
             include_str!("shaders/default.vert"),
             ShaderKind::Vertex,
             Some("Default Quad Vertex Shader".to_string()),
             &[],
         );
-        let fragment_shader = renderer.get().compile_shader(
+        let fragment_shader = Shader::compile(
+            device.clone(),
             include_str!("shaders/default.frag"),
             ShaderKind::Fragment,
             Some("Default Quad Fragment Shader".to_string()),
@@ -357,6 +387,7 @@ impl Renderer2D {
             extent,
             device,
             core_renderer: renderer,
+            max_frames_in_flight,
             quad_mesh,
             transforms: vec![],
             camera_sets,
@@ -371,6 +402,9 @@ impl Renderer2D {
             atlas_sets,
             default_sampler,
             default_image,
+            atlas_texture: None,
+            atlas_reloads: None,
+            atlas_outdated: vec![false; max_frames_in_flight as usize],
         }
     }
 
@@ -384,11 +418,46 @@ impl Renderer2D {
         &self.default_sampler
     }
 
+    /// Uses the texture of `handle` for the atlas sets. They are updated once the texture is loaded
+    /// and again whenever it is reloaded, each frame's set is only touched when that frame is drawn.
+    /// Pages of a [`TextureAtlas`](mvcore::render::atlas::TextureAtlas) are updated the same way
+    /// whenever the atlas uploads them again.
+    pub fn set_atlas_texture(&mut self, handle: Handle<Texture>) {
+        self.atlas_reloads = Some(handle.untyped().get_manager().subscribe_reloads());
+        self.atlas_texture = Some(handle);
+        self.atlas_outdated.fill(true);
+    }
+
+    fn update_atlas(&mut self, current_frame: u32) {
+        let Some(handle) = &self.atlas_texture else { return; };
+        if let Some(reloads) = &self.atlas_reloads {
+            if reloads.try_iter().any(|reloaded| reloaded == *handle.untyped()) {
+                self.atlas_outdated.fill(true);
+            }
+        }
+        if !self.atlas_outdated[current_frame as usize] {
+            return;
+        }
+        let Some(texture) = handle.get() else { return; };
+        self.atlas_sets[current_frame as usize].update_image(0, &texture.image(), &self.default_sampler, ImageLayout::ShaderReadOnlyOptimal);
+        self.atlas_outdated[current_frame as usize] = false;
+    }
+
     pub fn draw(&mut self) {
-        let current_frame = self.core_renderer.get_mut().get_current_frame_index();
-        let cmd = unsafe { Unsafe::cast_static(self.core_renderer.get_mut().get_current_command_buffer()) };
+        let Some(core_renderer) = &self.core_renderer else {
+            log::error!("Offscreen Renderer2D has no frame to draw into, use draw_to instead");
+            panic!();
+        };
+        let current_frame = core_renderer.get().get_current_frame_index();
+        let cmd = unsafe { Unsafe::cast_static(core_renderer.get().get_current_command_buffer()) };
 
-        let swapchain = self.core_renderer.get_mut().get_swapchain_mut();
+        self.draw_to(cmd, current_frame);
+    }
+
+    /// Records the geometry pass of all queued quads into `cmd`, using the resources of frame
+    /// `current_frame`.
+    pub fn draw_to(&mut self, cmd: &CommandBuffer, current_frame: u32) {
+        self.update_atlas(current_frame);
         let geometry_framebuffer = &self.geometry_framebuffers[current_frame as usize];
 
         // Push data to the storage buffer
@@ -457,7 +526,7 @@ impl Renderer2D {
         self.extent = extent;
         self.geometry_framebuffers.clear();
 
-        for _ in 0..self.core_renderer.get().get_max_frames_in_flight() {
+        for _ in 0..self.max_frames_in_flight {
             let framebuffer = Framebuffer::new(
                 self.device.clone(),
                 MVFramebufferCreateInfo {
@@ -472,13 +541,15 @@ impl Renderer2D {
             self.geometry_framebuffers.push(framebuffer);
         }
 
-        let vertex_shader = self.core_renderer.get().compile_shader(
+        let vertex_shader = Shader::compile(
+            self.device.clone(),
             include_str!("shaders/default.vert"),
             ShaderKind::Vertex,
             Some("Default Quad Vertex Shader".to_string()),
             &[],
         );
-        let fragment_shader = self.core_renderer.get().compile_shader(
+        let fragment_shader = Shader::compile(
+            self.device.clone(),
             include_str!("shaders/default.frag"),
             ShaderKind::Fragment,
             Some("Default Quad Fragment Shader".to_string()),
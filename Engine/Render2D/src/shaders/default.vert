@@ -64,6 +64,11 @@ mat4 createModelMatrix(vec3 translation, vec3 rotation, vec2 scale) {
     return translateMatrix * rotateMatrix * scaleMatrix;
 }
 
+// Colors are given in sRGB like textures, blending happens on the linear values
+vec3 srgbToLinear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), greaterThan(color, vec3(0.04045)));
+}
+
 layout(location = 0) in vec3 pos;
 
 layout(location = 0) out vec2 outTexCoord;
@@ -110,5 +115,5 @@ void main() {
     mat4 model = createModelMatrix(t.position.xyz, t.rotation, t.scale.xy);
     gl_Position = mat.proj * mat.view * model * vec4(pos.xyz, 1.0f);
     outTexCoord = GetTextureCoords(gl_VertexIndex, t.texCoords);
-    outColor = t.color;
+    outColor = vec4(srgbToLinear(t.color.rgb), t.color.a);
 }
\ No newline at end of file
@@ -0,0 +1,279 @@
+//! Golden image tests for [`Renderer2D`].
+//!
+//! Every scene is rendered offscreen on a headless device, read back and compared against
+//! `tests/golden/reference/<scene>.png`. On a mismatch the rendered image and a diff image are
+//! written next to the other test artifacts in the target directory.
+//!
+//! References are blessed on lavapipe, so they don't depend on the rasterization rules of one GPU
+//! vendor, and the driver used goes into the commit that changes them:
+//! `VK_ICD_FILENAMES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json MV_GOLDEN_BLESS=1 cargo test -p mvengine_render2d --test golden`
+//!
+//! Environment variables:
+//! - `MV_GOLDEN_BLESS=1` overwrites the references with the current output instead of comparing
+//! - `MV_GOLDEN_TOLERANCE=<0-255>` overrides the per channel tolerance of every scene
+//! - `MV_GOLDEN_FILTER=<name>` only runs scenes whose name contains the given string
+
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+use log::LevelFilter;
+use mvcore::math::vec::{Vec2, Vec3, Vec4};
+use mvcore::render::backend::device::{Device, Extensions, MVDeviceCreateInfo};
+use mvcore::render::backend::{Backend, Extent2D};
+use mvengine_render2d::renderer2d::{Renderer2D, Transform};
+use mvutils::version::Version;
+
+struct Scene {
+    name: &'static str,
+    extent: Extent2D,
+    /// Maximum allowed difference per color channel, in 8 bit units.
+    tolerance: u8,
+    quads: fn() -> Vec<Transform>,
+}
+
+fn quad(x: f32, y: f32, z: f32, rotation: f32, size: f32, color: Vec4) -> Transform {
+    Transform {
+        position: Vec3::new(x, y, z),
+        rotation: Vec3::new(0.0, 0.0, rotation),
+        scale: Vec2::splat(size),
+        tex_coord: Vec4::new(0.0, 0.0, 1.0, 1.0),
+        color,
+    }
+}
+
+static SCENES: &[Scene] = &[
+    Scene {
+        name: "single_quad",
+        extent: Extent2D {
+            width: 128,
+            height: 128,
+        },
+        tolerance: 2,
+        quads: || {
+            vec![quad(
+                64.0,
+                64.0,
+                1.0,
+                0.0,
+                32.0,
+                Vec4::new(1.0, 0.0, 0.0, 1.0),
+            )]
+        },
+    },
+    Scene {
+        name: "rotated_quads",
+        extent: Extent2D {
+            width: 256,
+            height: 256,
+        },
+        tolerance: 2,
+        quads: || {
+            vec![
+                quad(64.0, 64.0, 1.0, 0.3, 30.0, Vec4::new(1.0, 0.0, 0.0, 1.0)),
+                quad(192.0, 64.0, 1.0, -0.7, 30.0, Vec4::new(0.0, 1.0, 0.0, 1.0)),
+                quad(64.0, 192.0, 1.0, 1.2, 30.0, Vec4::new(0.0, 0.0, 1.0, 1.0)),
+                quad(192.0, 192.0, 1.0, 2.5, 30.0, Vec4::new(1.0, 1.0, 0.0, 1.0)),
+            ]
+        },
+    },
+    Scene {
+        name: "depth_order",
+        extent: Extent2D {
+            width: 128,
+            height: 128,
+        },
+        tolerance: 2,
+        quads: || {
+            vec![
+                quad(56.0, 56.0, 1.0, 0.0, 32.0, Vec4::new(1.0, 0.0, 0.0, 1.0)),
+                quad(72.0, 72.0, 5.0, 0.0, 32.0, Vec4::new(0.0, 0.0, 1.0, 1.0)),
+            ]
+        },
+    },
+    Scene {
+        name: "textured_quad",
+        extent: Extent2D {
+            width: 64,
+            height: 64,
+        },
+        tolerance: 2,
+        // Zero alpha color shows the default atlas texture
+        quads: || vec![quad(32.0, 32.0, 1.0, 0.0, 24.0, Vec4::splat(0.0))],
+    },
+    Scene {
+        name: "srgb_colors",
+        extent: Extent2D {
+            width: 64,
+            height: 64,
+        },
+        tolerance: 2,
+        // Quad colors are sRGB, the geometry image stores them linear
+        quads: || {
+            vec![
+                quad(16.0, 32.0, 1.0, 0.0, 12.0, Vec4::new(0.5, 0.5, 0.5, 1.0)),
+                quad(48.0, 32.0, 1.0, 0.0, 12.0, Vec4::new(0.8, 0.2, 0.03, 1.0)),
+            ]
+        },
+    },
+];
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let bless = std::env::var("MV_GOLDEN_BLESS").is_ok_and(|v| v == "1");
+    let tolerance_override = std::env::var("MV_GOLDEN_TOLERANCE").ok().map(|v| {
+        v.parse::<u8>()
+            .expect("MV_GOLDEN_TOLERANCE has to be in 0..=255")
+    });
+    let filter = std::env::var("MV_GOLDEN_FILTER").ok();
+
+    let device = Device::new_headless(
+        Backend::Vulkan,
+        MVDeviceCreateInfo {
+            app_name: "Golden image tests".to_string(),
+            app_version: Version::new(0, 0, 1, 0),
+            engine_name: "MVEngine".to_string(),
+            engine_version: Version::new(0, 0, 1, 0),
+            device_extensions: Extensions::empty(),
+        },
+    );
+
+    let reference_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/reference");
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).expect("Failed to create golden output directory");
+
+    let mut failures = Vec::new();
+    for scene in SCENES {
+        if filter
+            .as_ref()
+            .is_some_and(|f| !scene.name.contains(f.as_str()))
+        {
+            continue;
+        }
+
+        let actual = render_scene(&device, scene);
+        let reference_path = reference_dir.join(format!("{}.png", scene.name));
+
+        if bless {
+            std::fs::create_dir_all(&reference_dir).expect("Failed to create reference directory");
+            actual
+                .save(&reference_path)
+                .expect("Failed to write reference image");
+            println!("golden {}: blessed", scene.name);
+            continue;
+        }
+
+        let tolerance = tolerance_override.unwrap_or(scene.tolerance);
+        match compare_to_reference(&actual, &reference_path, tolerance) {
+            Ok(()) => println!("golden {}: ok", scene.name),
+            Err((message, diff)) => {
+                let actual_path = output_dir.join(format!("{}.actual.png", scene.name));
+                actual
+                    .save(&actual_path)
+                    .expect("Failed to write output image");
+                let mut message = format!("{message}, output written to {}", actual_path.display());
+                if let Some(diff) = diff {
+                    let diff_path = output_dir.join(format!("{}.diff.png", scene.name));
+                    diff.save(&diff_path).expect("Failed to write diff image");
+                    message += &format!(", diff written to {}", diff_path.display());
+                }
+                println!("golden {}: FAILED, {message}", scene.name);
+                failures.push(scene.name);
+            }
+        }
+    }
+
+    device.wait_idle();
+
+    if !failures.is_empty() {
+        panic!(
+            "{} golden image test(s) failed: {failures:?}",
+            failures.len()
+        );
+    }
+}
+
+fn render_scene(device: &Device, scene: &Scene) -> RgbaImage {
+    let mut renderer = Renderer2D::new_offscreen(device.clone(), scene.extent, 1);
+    for quad in (scene.quads)() {
+        renderer.add_quad(quad);
+    }
+
+    let cmd = device.begin_single_time_command(device.get_graphics_command_pool());
+    renderer.draw_to(&cmd, 0);
+    device.end_single_time_command(
+        cmd,
+        device.get_graphics_command_pool(),
+        device.get_graphics_queue(),
+    );
+
+    renderer
+        .get_geometry_image(0)
+        .read_pixels()
+        .to_dynamic_image()
+        .expect("Geometry image format can't be converted")
+        .to_rgba8()
+}
+
+/// Compares per channel and returns a message and, if the sizes matched, a diff image that is
+/// black where the images match and red where they don't.
+fn compare_to_reference(
+    actual: &RgbaImage,
+    reference_path: &Path,
+    tolerance: u8,
+) -> Result<(), (String, Option<RgbaImage>)> {
+    let reference = image::open(reference_path)
+        .map_err(|e| {
+            (
+                format!(
+                    "could not open reference {} ({e}), run with MV_GOLDEN_BLESS=1 to create it",
+                    reference_path.display()
+                ),
+                None,
+            )
+        })?
+        .to_rgba8();
+
+    if reference.dimensions() != actual.dimensions() {
+        return Err((
+            format!(
+                "size mismatch, expected {:?}, got {:?}",
+                reference.dimensions(),
+                actual.dimensions()
+            ),
+            None,
+        ));
+    }
+
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched = 0usize;
+    let mut max_difference = 0u8;
+    for (x, y, expected) in reference.enumerate_pixels() {
+        let got = actual.get_pixel(x, y);
+        let difference = expected
+            .0
+            .iter()
+            .zip(got.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            mismatched += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            diff.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+        }
+    }
+
+    if mismatched > 0 {
+        Err((
+            format!(
+                "{mismatched} pixel(s) differ by more than {tolerance}, max difference {max_difference}"
+            ),
+            Some(diff),
+        ))
+    } else {
+        Ok(())
+    }
+}
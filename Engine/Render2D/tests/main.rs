@@ -1,3 +1,4 @@
+use std::sync::mpsc::{self, Receiver};
 use std::sync::Arc;
 use log::LevelFilter;
 use mvutils::once::CreateOnce;
@@ -13,7 +14,7 @@ use mvcore::asset::asset::AssetType;
 use mvcore::asset::manager::{AssetHandle, AssetManager};
 use mvcore::render::backend::buffer::MemoryProperties;
 use mvcore::render::backend::image::{AccessFlags, Image, ImageAspect, ImageFormat, ImageLayout, ImageTiling, ImageType, ImageUsage, MVImageCreateInfo};
-use mvcore::render::backend::sampler::{Filter, MipmapMode, MVSamplerCreateInfo, Sampler, SamplerAddressMode};
+use mvcore::render::backend::sampler::{Filter, MipmapMode, MVSamplerCreateInfo, Sampler, SamplerAddressMode, LOD_CLAMP_NONE};
 use mvcore::render::renderer::Renderer;
 use mvcore::render::texture::TextureRegion;
 
@@ -49,7 +50,7 @@ struct AppLoop {
 
     manager: Arc<AssetManager>,
     handle: AssetHandle,
-    loaded: bool,
+    loaded: Receiver<AssetHandle>,
     sampler: Sampler,
 }
 
@@ -71,29 +72,36 @@ impl ApplicationLoopCallbacks for AppLoop {
         let renderer2d = Renderer2D::new(device.clone(), core_renderer.clone(), core_renderer.get().get_swapchain().get_extent());
 
         let manager = AssetManager::new(device.clone(), 1);
+        core_renderer.get_mut().set_asset_manager(manager.clone());
+        window.set_asset_manager(manager.clone());
 
         let handle = manager.create_asset("texture.png", AssetType::Texture);
 
-        handle.load();
+        let (sender, loaded) = mpsc::channel();
+        handle
+            .load()
+            .on_loaded(move |handle| sender.send(handle).unwrap())
+            .on_failed(|_| println!("Failed!"));
 
         let sampler = Sampler::new(device.clone(), MVSamplerCreateInfo {
             address_mode: SamplerAddressMode::ClampToEdge,
             filter_mode: Filter::Nearest,
             mipmap_mode: MipmapMode::Nearest,
-            anisotropy: false,
+            anisotropy: None,
+            lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: LOD_CLAMP_NONE,
             label: None,
         });
 
-        Self { sampler, device, renderer2d, core_renderer, quad_rotation: 0.0, quad_position: Vec2::splat(0.0), timer: 0.0, manager, handle, loaded: false }
+        Self { sampler, device, renderer2d, core_renderer, quad_rotation: 0.0, quad_position: Vec2::splat(0.0), timer: 0.0, manager, handle, loaded }
     }
 
     fn update(&mut self, window: &mut Window, delta_t: f64) {
-        let asset = self.handle.get();
-        if asset.failed() {
-            println!("Failed!");
-        } else if asset.is_loaded() && !self.loaded {
-            self.loaded = true;
+        // The load callbacks run right before the update
+        if let Ok(handle) = self.loaded.try_recv() {
             // we can swap the image here
+            let asset = handle.get();
             let Some(texture) = asset.as_texture() else { unreachable!() };
 
             self.device.wait_idle();
@@ -0,0 +1,114 @@
+//! Checks the commands [`Renderer2D`] records, using the null backend so no gpu is needed.
+
+use log::LevelFilter;
+use mvcore::math::vec::{Vec2, Vec3, Vec4};
+use mvcore::render::backend::command_buffer::RecordedCommand;
+use mvcore::render::backend::device::{Device, Extensions, MVDeviceCreateInfo};
+use mvcore::render::backend::framebuffer::ClearColor;
+use mvcore::render::backend::{Backend, Extent2D};
+use mvengine_render2d::renderer2d::{Renderer2D, Transform};
+use mvutils::version::Version;
+
+fn main() {
+    mvlogger::init(std::io::stdout(), LevelFilter::Warn);
+
+    let device = Device::new_headless(
+        Backend::Null,
+        MVDeviceCreateInfo {
+            app_name: "Null backend tests".to_string(),
+            app_version: Version::new(0, 0, 1, 0),
+            engine_name: "MVEngine".to_string(),
+            engine_version: Version::new(0, 0, 1, 0),
+            device_extensions: Extensions::empty(),
+        },
+    );
+
+    let extent = Extent2D {
+        width: 64,
+        height: 32,
+    };
+    let mut renderer = Renderer2D::new_offscreen(device.clone(), extent, 1);
+    for i in 0..3 {
+        renderer.add_quad(Transform {
+            position: Vec3::new(i as f32 * 10.0, 10.0, 1.0),
+            rotation: Vec3::splat(0.0),
+            scale: Vec2::splat(5.0),
+            tex_coord: Vec4::new(0.0, 0.0, 1.0, 1.0),
+            color: Vec4::new(1.0, 0.0, 0.0, 1.0),
+        });
+    }
+
+    let cmd = device.begin_single_time_command(device.get_graphics_command_pool());
+    renderer.draw_to(&cmd, 0);
+    cmd.end();
+    let commands = cmd.get_recorded_commands();
+    device.end_single_time_command(
+        cmd,
+        device.get_graphics_command_pool(),
+        device.get_graphics_queue(),
+    );
+
+    assert_eq!(commands.first(), Some(&RecordedCommand::Begin));
+    assert_eq!(commands.last(), Some(&RecordedCommand::End));
+
+    let Some(RecordedCommand::BeginRenderPass {
+        framebuffer,
+        clear_color,
+        extent: pass_extent,
+    }) = commands
+        .iter()
+        .find(|c| matches!(c, RecordedCommand::BeginRenderPass { .. }))
+    else {
+        panic!("No render pass was started: {commands:?}");
+    };
+    assert_eq!(*pass_extent, extent);
+    assert_eq!(clear_color.len(), 2);
+    assert!(matches!(clear_color[1], ClearColor::Depth { .. }));
+
+    let pipeline = commands.iter().find_map(|c| match c {
+        RecordedCommand::BindPipeline { pipeline } => Some(*pipeline),
+        _ => None,
+    });
+    let sets = commands
+        .iter()
+        .filter_map(|c| match c {
+            RecordedCommand::BindDescriptorSet {
+                pipeline: bound,
+                set_index,
+                ..
+            } => {
+                assert_eq!(Some(*bound), pipeline, "Set bound to the wrong pipeline");
+                Some(*set_index)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(sets, vec![0, 1, 2]);
+
+    let instances = commands.iter().find_map(|c| match c {
+        RecordedCommand::DrawIndexed { instance_count, .. } => Some(*instance_count),
+        RecordedCommand::Draw { instance_count, .. } => Some(*instance_count),
+        _ => None,
+    });
+    assert_eq!(
+        instances,
+        Some(3),
+        "Expected a single instanced draw of all quads"
+    );
+
+    assert!(commands.contains(&RecordedCommand::EndRenderPass {
+        framebuffer: *framebuffer
+    }));
+
+    // Queued quads are consumed by the draw
+    let cmd = device.begin_single_time_command(device.get_graphics_command_pool());
+    renderer.draw_to(&cmd, 0);
+    let instances = cmd.get_recorded_commands().iter().find_map(|c| match c {
+        RecordedCommand::DrawIndexed { instance_count, .. } => Some(*instance_count),
+        RecordedCommand::Draw { instance_count, .. } => Some(*instance_count),
+        _ => None,
+    });
+    assert_eq!(instances, Some(0));
+
+    println!("null backend: ok");
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use log::LevelFilter;
+use mvcore::asset::archive::{ArchiveWriter, Compression};
+use mvcore::asset::cook::{Cooker, MANIFEST_NAME};
+
+const USAGE: &str = "Usage: mvcook <input directory> <output directory> [--pack <archive>] [--no-compression]
+
+Cooks all textures, glTF models, glsl shaders and texture atlases below the input directory into
+the output directory and writes a manifest. Mount the output directory, or the archive written with
+--pack, and load the manifest with AssetManager::load_cooked_manifest to use the cooked assets.";
+
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    pack: Option<PathBuf>,
+    compression: Compression,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut pack = None;
+    let mut compression = Compression::Deflate;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pack" => pack = Some(args.next().ok_or("Missing archive path after --pack")?.into()),
+            "--no-compression" => compression = Compression::None,
+            "-h" | "--help" => return Err(String::new()),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {flag}")),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+    let [input, output] = <[PathBuf; 2]>::try_from(positional).map_err(|_| "Expected an input and an output directory".to_string())?;
+    Ok(Args {
+        input,
+        output,
+        pack,
+        compression,
+    })
+}
+
+fn main() -> ExitCode {
+    mvlogger::init(std::io::stdout(), LevelFilter::Info);
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{e}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = match Cooker::new(&args.input, &args.output).cook() {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("Failed to cook {}: {e}", args.input.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    log::info!("Cooked {} assets, copied {} files, manifest written to {}", report.cooked, report.copied, args.output.join(MANIFEST_NAME).display());
+
+    if let Some(pack) = &args.pack {
+        let mut writer = ArchiveWriter::new();
+        if let Err(e) = writer.add_directory(&args.output, args.compression).and_then(|_| writer.write_to_file(pack)) {
+            log::error!("Failed to pack {} into {}: {e}", args.output.display(), pack.display());
+            return ExitCode::FAILURE;
+        }
+        log::info!("Packed {} files into {}", writer.len(), pack.display());
+    }
+
+    if report.failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        log::error!("{} assets failed to cook and were copied as they are", report.failed.len());
+        ExitCode::FAILURE
+    }
+}